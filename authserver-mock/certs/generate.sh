@@ -0,0 +1,16 @@
+#!/bin/sh
+# Generate the self-signed CA and the certificate of mocked campus hosts.
+# The certificates are checked in, run this script only if they need to be replaced.
+set -e
+cd "$(dirname "$0")"
+
+openssl ecparam -name prime256v1 -genkey -noout -out ca.key
+openssl req -x509 -new -key ca.key -sha256 -days 36500 -subj "/CN=Kite Mock CA" \
+    -addext "basicConstraints=critical,CA:TRUE" -addext "keyUsage=critical,keyCertSign,cRLSign" -out ca.pem
+
+openssl ecparam -name prime256v1 -genkey -noout | openssl pkcs8 -topk8 -nocrypt -out server.key
+openssl req -new -key server.key -subj "/CN=authserver.sit.edu.cn" -out server.csr
+printf "basicConstraints=CA:FALSE\nkeyUsage=critical,digitalSignature\nextendedKeyUsage=serverAuth\nsubjectAltName=DNS:authserver.sit.edu.cn,DNS:jwxt.sit.edu.cn\n" > server.ext
+openssl x509 -req -in server.csr -CA ca.pem -CAkey ca.key -CAcreateserial -sha256 -days 36500 -extfile server.ext -out server.pem
+
+rm -f ca.key ca.srl server.csr server.ext
@@ -0,0 +1,524 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A local mock of authserver.sit.edu.cn, which speaks the same login flow over TLS with a
+//! self-signed CA, so that the login code can be tested without the campus network.
+//!
+//! Requests with `Host: jwxt.sit.edu.cn` are served as the academic system, to test redirections
+//! across hosts and CAS service login. Responses are gzipped if the client accepts it.
+//!
+//! ```ignore
+//! let server = MockAuthserver::new().user("2112340101", "password", "张三");
+//! let stream = server.connect(); // TLS bytes to authserver go here
+//! let roots = authserver_mock::root_store();
+//! ```
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+use hyper::body::HttpBody;
+use hyper::header::{ACCEPT_ENCODING, CONTENT_ENCODING, COOKIE, HOST, LOCATION, SET_COOKIE};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use tokio::io::{AsyncRead, AsyncWrite, DuplexStream};
+use tokio_rustls::TlsAcceptor;
+
+const CA_CERTIFICATE: &[u8] = include_bytes!("../certs/ca.pem");
+const SERVER_CERTIFICATE: &[u8] = include_bytes!("../certs/server.pem");
+const SERVER_KEY: &[u8] = include_bytes!("../certs/server.key");
+
+/// Title of the home page on jwxt.sit.edu.cn, which is reached by redirections in tests.
+pub const JWXT_HOME_TITLE: &str = "教务管理系统";
+
+const JWXT_LOGIN: &str =
+    "https://authserver.sit.edu.cn/authserver/login?service=https%3A%2F%2Fjwxt.sit.edu.cn%2Fsso%2Fjziotlogin";
+
+/// Error message shown in `#msg` of the login page.
+pub const WRONG_PASSWORD_MESSAGE: &str = "您提供的用户名或者密码有误";
+pub const WRONG_CAPTCHA_MESSAGE: &str = "无效的验证码";
+
+/// Root certificates which trust the mock server.
+pub fn root_store() -> rustls::RootCertStore {
+    let mut store = rustls::RootCertStore::empty();
+
+    for cert in rustls_pemfile::certs(&mut &CA_CERTIFICATE[..]).unwrap() {
+        store.add(&rustls::Certificate(cert)).unwrap();
+    }
+    store
+}
+
+fn server_config() -> rustls::ServerConfig {
+    let certs = rustls_pemfile::certs(&mut &SERVER_CERTIFICATE[..])
+        .unwrap()
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+    let key = rustls_pemfile::pkcs8_private_keys(&mut &SERVER_KEY[..])
+        .unwrap()
+        .remove(0);
+
+    rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, rustls::PrivateKey(key))
+        .unwrap()
+}
+
+#[derive(Clone)]
+struct Account {
+    password: String,
+    name: String,
+    /// Captcha answer, if the account is asked for captcha.
+    captcha: Option<String>,
+}
+
+/// State of a `JSESSIONID`, created when the login page is fetched.
+struct LoginSession {
+    salt: String,
+    lt: String,
+}
+
+#[derive(Default)]
+struct State {
+    accounts: HashMap<String, Account>,
+    sessions: HashMap<String, LoginSession>,
+    /// `CASTGC` -> account
+    tickets: HashMap<String, String>,
+    /// Service ticket -> account, which can be used only once.
+    service_tickets: HashMap<String, String>,
+    /// `JSESSIONID` on jwxt -> account
+    jwxt_sessions: HashMap<String, String>,
+    /// Path -> response on jwxt, for logged in users.
+    jwxt_data: HashMap<String, String>,
+    counter: u32,
+    page_changed: bool,
+}
+
+impl State {
+    fn next_id(&mut self) -> u32 {
+        self.counter += 1;
+        self.counter
+    }
+}
+
+/// The mock server. Clones share the same accounts and sessions.
+#[derive(Clone, Default)]
+pub struct MockAuthserver {
+    state: Arc<Mutex<State>>,
+}
+
+impl MockAuthserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an account which can login with `password`.
+    pub fn user(self, account: &str, password: &str, name: &str) -> Self {
+        self.add_account(account, password, name, None)
+    }
+
+    /// Add an account which is asked for captcha, and `answer` is the right one.
+    pub fn user_with_captcha(self, account: &str, password: &str, name: &str, answer: &str) -> Self {
+        self.add_account(account, password, name, Some(answer.to_string()))
+    }
+
+    /// Serve a login page without `pwdDefaultEncryptSalt` and `lt`, like authserver is upgraded.
+    pub fn page_changed(self) -> Self {
+        self.state.lock().unwrap().page_changed = true;
+        self
+    }
+
+    /// Respond `body` on `path` of jwxt.sit.edu.cn, such as a saved course table, if logged in.
+    pub fn jwxt_data(self, path: &str, body: &str) -> Self {
+        let mut state = self.state.lock().unwrap();
+        state.jwxt_data.insert(path.to_string(), body.to_string());
+        drop(state);
+        self
+    }
+
+    fn add_account(self, account: &str, password: &str, name: &str, captcha: Option<String>) -> Self {
+        let account_info = Account {
+            password: password.to_string(),
+            name: name.to_string(),
+            captcha,
+        };
+        self.state
+            .lock()
+            .unwrap()
+            .accounts
+            .insert(account.to_string(), account_info);
+        self
+    }
+
+    /// Start a connection to the server. TLS handshake is expected on the returned stream.
+    pub fn connect(&self) -> DuplexStream {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let this = self.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = this.serve(server).await {
+                eprintln!("Mock authserver: connection closed with error: {}", e);
+            }
+        });
+        client
+    }
+
+    /// Serve a single connection.
+    pub async fn serve<T>(self, stream: T) -> anyhow::Result<()>
+    where
+        T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let acceptor = TlsAcceptor::from(Arc::new(server_config()));
+        let stream = acceptor.accept(stream).await?;
+
+        let service = service_fn(move |request| {
+            let this = self.clone();
+            async move { Ok::<_, Infallible>(this.handle(request).await) }
+        });
+        Http::new().http1_only(true).serve_connection(stream, service).await?;
+        Ok(())
+    }
+
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        let (parts, mut body) = request.into_parts();
+        let mut content = Vec::new();
+        while let Some(Ok(chunk)) = body.data().await {
+            content.extend_from_slice(&chunk);
+        }
+        let cookies = parse_cookies(parts.headers.get(COOKIE).and_then(|v| v.to_str().ok()));
+        let header = |name| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+        };
+        let query = parts.uri.query().unwrap_or_default();
+
+        let response = if header(HOST).starts_with("jwxt.sit.edu.cn") {
+            self.jwxt(&parts.method, parts.uri.path(), query, &cookies)
+        } else {
+            self.authserver(&parts.method, parts.uri.path(), query, &cookies, &content)
+        };
+        if header(ACCEPT_ENCODING).contains("gzip") {
+            gzip(response).await
+        } else {
+            response
+        }
+    }
+
+    fn authserver(
+        &self,
+        method: &Method,
+        path: &str,
+        query: &str,
+        cookies: &HashMap<String, String>,
+        content: &[u8],
+    ) -> Response<Body> {
+        match (method, path) {
+            (&Method::GET, "/authserver/login") => match parse_form(query.as_bytes()).get("service") {
+                Some(service) => self.service_login(cookies, service),
+                None => self.login_page(),
+            },
+            (&Method::POST, "/authserver/login") => self.login(cookies, content),
+            (&Method::GET, "/authserver/needCaptcha.html") => self.need_captcha(query),
+            (&Method::GET, "/authserver/captcha.html") => captcha_image(),
+            (&Method::GET, "/authserver/index.do") => self.index(cookies),
+            (&Method::GET, "/authserver/redirect") => redirect(query),
+            (&Method::GET, "/authserver/loop") => redirection(StatusCode::FOUND, "loop"),
+            _ => response(StatusCode::NOT_FOUND, "<html><body>Not Found</body></html>"),
+        }
+    }
+
+    fn login_page(&self) -> Response<Body> {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id();
+        let session_id = format!("MOCK{:08x}", id);
+        let session = LoginSession {
+            salt: format!("mockSalt{:08x}", id),
+            lt: format!("LT-{}-mock-cas", id),
+        };
+
+        let page = if state.page_changed {
+            "<html><body><form id=\"casLoginForm\"></form></body></html>".to_string()
+        } else {
+            format!(
+                "<html><head><script>var pwdDefaultEncryptSalt = \"{}\";</script></head><body>\
+                <form id=\"casLoginForm\"><input type=\"hidden\" name=\"lt\" value=\"{}\"/>\
+                <input type=\"hidden\" name=\"dllt\" value=\"userNamePasswordLogin\"/>\
+                <input type=\"hidden\" name=\"execution\" value=\"e1s1\"/>\
+                <input type=\"hidden\" name=\"_eventId\" value=\"submit\"/></form></body></html>",
+                session.salt, session.lt
+            )
+        };
+        state.sessions.insert(session_id.clone(), session);
+
+        let mut response = response(StatusCode::OK, page);
+        let cookie = format!("JSESSIONID={}; path=/authserver; HttpOnly", session_id);
+        response.headers_mut().insert(SET_COOKIE, cookie.parse().unwrap());
+        response
+    }
+
+    fn need_captcha(&self, query: &str) -> Response<Body> {
+        let form = parse_form(query.as_bytes());
+        let state = self.state.lock().unwrap();
+
+        let need = form
+            .get("username")
+            .and_then(|account| state.accounts.get(account))
+            .map(|account| account.captcha.is_some())
+            .unwrap_or_default();
+        response(StatusCode::OK, need.to_string())
+    }
+
+    fn login(&self, cookies: &HashMap<String, String>, content: &[u8]) -> Response<Body> {
+        let form = parse_form(content);
+        let field = |key: &str| form.get(key).map(String::as_str).unwrap_or_default();
+        let mut state = self.state.lock().unwrap();
+
+        let Some(session) = cookies.get("JSESSIONID").and_then(|id| state.sessions.get(id)) else {
+            return error_page(StatusCode::OK, "会话已过期，请刷新页面");
+        };
+        if field("lt") != session.lt || field("execution") != "e1s1" || field("_eventId") != "submit" {
+            return error_page(StatusCode::OK, "页面已过期，请刷新页面");
+        }
+        let password = decrypt_password(field("password"), &session.salt);
+        let Some(account) = state.accounts.get(field("username")).cloned() else {
+            return error_page(StatusCode::OK, WRONG_PASSWORD_MESSAGE);
+        };
+        if let Some(answer) = &account.captcha {
+            if field("captchaResponse") != answer {
+                return error_page(StatusCode::OK, WRONG_CAPTCHA_MESSAGE);
+            }
+        }
+        if password.as_deref() != Some(account.password.as_str()) {
+            return error_page(StatusCode::OK, WRONG_PASSWORD_MESSAGE);
+        }
+
+        let ticket = format!("TGT-{}-mock-cas", state.next_id());
+        state.tickets.insert(ticket.clone(), field("username").to_string());
+
+        let mut response = response(StatusCode::FOUND, "");
+        let headers = response.headers_mut();
+        headers.insert(
+            LOCATION,
+            "https://authserver.sit.edu.cn/authserver/index.do".parse().unwrap(),
+        );
+        let cookie = format!("CASTGC={}; path=/authserver/; HttpOnly", ticket);
+        headers.insert(SET_COOKIE, cookie.parse().unwrap());
+        response
+    }
+
+    /// Issue a service ticket and redirect to the service if logged in, like CAS does.
+    fn service_login(&self, cookies: &HashMap<String, String>, service: &str) -> Response<Body> {
+        let mut state = self.state.lock().unwrap();
+        let Some(account) = cookies
+            .get("CASTGC")
+            .and_then(|ticket| state.tickets.get(ticket))
+            .cloned()
+        else {
+            drop(state);
+            return self.login_page();
+        };
+        let ticket = format!("ST-{}-mock-cas", state.next_id());
+        state.service_tickets.insert(ticket.clone(), account);
+
+        let separator = if service.contains('?') { '&' } else { '?' };
+        redirection(StatusCode::FOUND, &format!("{}{}ticket={}", service, separator, ticket))
+    }
+
+    /// jwxt.sit.edu.cn, which accepts service tickets on `/sso/jziotlogin` like the real one, or `/jwxt/sso`,
+    /// and sets host-only cookies.
+    fn jwxt(&self, method: &Method, path: &str, query: &str, cookies: &HashMap<String, String>) -> Response<Body> {
+        let mut state = self.state.lock().unwrap();
+
+        match (method, path) {
+            // Relative `Location`
+            (&Method::GET, "/jwxt/") => redirection(StatusCode::SEE_OTHER, "home.do"),
+            (&Method::GET, "/jwxt/home.do") => {
+                let page = format!(
+                    "<html><head><title>{}</title></head><body></body></html>",
+                    JWXT_HOME_TITLE
+                );
+                let mut response = response(StatusCode::OK, page);
+                let cookie = "route=jwxt1; path=/jwxt".parse().unwrap();
+                response.headers_mut().insert(SET_COOKIE, cookie);
+                response
+            }
+            (&Method::GET, "/jwxt/sso" | "/sso/jziotlogin") => {
+                let ticket = parse_form(query.as_bytes()).remove("ticket").unwrap_or_default();
+                let Some(account) = state.service_tickets.remove(&ticket) else {
+                    return response(StatusCode::UNAUTHORIZED, "<html><body>Invalid ticket</body></html>");
+                };
+                let session_id = format!("JW{:08x}", state.next_id());
+                state.jwxt_sessions.insert(session_id.clone(), account);
+
+                let home = match path {
+                    "/jwxt/sso" => "/jwxt/home.do",
+                    _ => "/jwglxt/xtgl/index_initMenu.html",
+                };
+                let mut response = redirection(StatusCode::FOUND, home);
+                let cookie = format!("JSESSIONID={}; path=/; HttpOnly", session_id);
+                response.headers_mut().insert(SET_COOKIE, cookie.parse().unwrap());
+                response
+            }
+            (_, "/jwxt/profile.do") => {
+                let account = cookies
+                    .get("JSESSIONID")
+                    .and_then(|id| state.jwxt_sessions.get(id))
+                    .and_then(|account| state.accounts.get(account));
+                match account {
+                    Some(account) => {
+                        let page = format!("<html><body><span id=\"name\">{}</span></body></html>", account.name);
+                        response(StatusCode::OK, page)
+                    }
+                    None => redirection(StatusCode::FOUND, JWXT_LOGIN),
+                }
+            }
+            (&Method::GET, "/jwglxt/xtgl/index_initMenu.html") => {
+                response(StatusCode::OK, "<html><body>教学管理信息服务平台</body></html>")
+            }
+            (_, path) if state.jwxt_data.contains_key(path) => {
+                if !cookies
+                    .get("JSESSIONID")
+                    .is_some_and(|id| state.jwxt_sessions.contains_key(id))
+                {
+                    return redirection(StatusCode::FOUND, JWXT_LOGIN);
+                }
+                response(StatusCode::OK, state.jwxt_data[path].clone())
+            }
+            _ => response(StatusCode::NOT_FOUND, "<html><body>Not Found</body></html>"),
+        }
+    }
+
+    fn index(&self, cookies: &HashMap<String, String>) -> Response<Body> {
+        let state = self.state.lock().unwrap();
+        let account = cookies
+            .get("CASTGC")
+            .and_then(|ticket| state.tickets.get(ticket))
+            .and_then(|account| state.accounts.get(account));
+
+        let Some(account) = account else {
+            let mut response = response(StatusCode::FOUND, "");
+            let location = "https://authserver.sit.edu.cn/authserver/login".parse().unwrap();
+            response.headers_mut().insert(LOCATION, location);
+            return response;
+        };
+        let page = format!(
+            "<html><body><div id=\"auth_siderbar\"><div class=\"auth_username\">\
+            <span><span> {} </span></span></div></div></body></html>",
+            account.name
+        );
+        response(StatusCode::OK, page)
+    }
+}
+
+fn response(status: StatusCode, body: impl Into<Body>) -> Response<Body> {
+    let mut response = Response::new(body.into());
+    *response.status_mut() = status;
+    response
+}
+
+fn redirection(status: StatusCode, location: &str) -> Response<Body> {
+    let mut response = response(status, "");
+    response.headers_mut().insert(LOCATION, location.parse().unwrap());
+    response
+}
+
+/// Redirect to `target` with `status` in query string.
+fn redirect(query: &str) -> Response<Body> {
+    let form = parse_form(query.as_bytes());
+    let status = form.get("status").and_then(|s| s.parse().ok());
+    match (status.and_then(|s| StatusCode::from_u16(s).ok()), form.get("target")) {
+        (Some(status), Some(target)) => redirection(status, target),
+        _ => response(StatusCode::BAD_REQUEST, ""),
+    }
+}
+
+async fn gzip(response: Response<Body>) -> Response<Body> {
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    let (mut parts, body) = response.into_parts();
+    let content = hyper::body::to_bytes(body).await.unwrap_or_default();
+    if content.is_empty() {
+        return Response::from_parts(parts, Body::empty());
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&content).unwrap();
+    parts.headers.insert(CONTENT_ENCODING, "gzip".parse().unwrap());
+    Response::from_parts(parts, Body::from(encoder.finish().unwrap()))
+}
+
+fn error_page(status: StatusCode, message: &str) -> Response<Body> {
+    let page = format!(
+        "<html><body><form id=\"casLoginForm\"><span id=\"msg\" class=\"auth_error\">{}</span></form></body></html>",
+        message
+    );
+    response(status, page)
+}
+
+/// A valid image is not needed, only the bytes are relayed.
+fn captcha_image() -> Response<Body> {
+    let mut response = response(StatusCode::OK, &b"\x89PNG\r\n\x1a\nmock captcha"[..]);
+    response
+        .headers_mut()
+        .insert("Content-Type", "image/png".parse().unwrap());
+    response
+}
+
+/// Parse `Cookie` header like "JSESSIONID=xxx;CASTGC=yyy;".
+fn parse_cookies(header: Option<&str>) -> HashMap<String, String> {
+    header
+        .unwrap_or_default()
+        .split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/// Parse urlencoded form or query string.
+fn parse_form(content: &[u8]) -> HashMap<String, String> {
+    let decode = |s: &str| {
+        let s = s.replace('+', " ");
+        percent_encoding::percent_decode_str(&s).decode_utf8_lossy().to_string()
+    };
+
+    String::from_utf8_lossy(content)
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (decode(k), decode(v)))
+        .collect()
+}
+
+/// Reverse of `Portal::generate_password_string`: base64, AES-128-CBC with zero IV, and 64 bytes of prefix.
+fn decrypt_password(encrypted: &str, salt: &str) -> Option<String> {
+    use base64::engine::general_purpose::STANDARD as base64_standard;
+    use base64::Engine;
+    use block_modes::block_padding::Pkcs7;
+    use block_modes::{BlockMode, Cbc};
+    type Aes128Cbc = Cbc<aes::Aes128, Pkcs7>;
+
+    let content = base64_standard.decode(encrypted).ok()?;
+    let cipher = Aes128Cbc::new_var(salt.as_bytes(), &[0u8; 16]).ok()?;
+    let plaintext = cipher.decrypt_vec(&content).ok()?;
+
+    plaintext.get(64..).and_then(|s| String::from_utf8(s.to_vec()).ok())
+}
@@ -32,6 +32,10 @@ struct CacheParameter {
     #[darling(default)]
     /// Cache timeout option (in second)
     timeout: Option<i64>,
+    #[darling(default)]
+    /// Cache scope, a path to u8 constant like `kite::cache::SCOPE_CLASSROOM`, so that the results can be
+    /// erased together.
+    scope: Option<syn::Path>,
 }
 
 fn parse_attribute(args: syn::AttributeArgs) -> CacheParameter {
@@ -84,6 +88,10 @@ pub fn cache(args: TokenStream, item: TokenStream) -> TokenStream {
     // Parse cache parameter
     let param = parse_attribute(args);
     let timeout = param.timeout.unwrap_or(DEFAULT_CACHE_TIMEOUT);
+    let scope = param
+        .scope
+        .map(|path| quote!(#path))
+        .unwrap_or_else(|| quote!(kite::cache::SCOPE_PUBLIC));
 
     // Parse function signature
     let syn::ItemFn { attrs, vis, sig, block } = parse_fn(item);
@@ -101,7 +109,7 @@ pub fn cache(args: TokenStream, item: TokenStream) -> TokenStream {
             use chrono::Duration;
 
             // Query cache
-            if let Ok(Some(cache)) = kite::cache::cache_query!(key = #punctuated_args; timeout = Duration::seconds(#timeout)) {
+            if let Ok(Some(cache)) = kite::cache::cache_query!(key = #punctuated_args; scope = #scope; timeout = Duration::seconds(#timeout)) {
                 return Ok(cache);
             };
 
@@ -110,7 +118,7 @@ pub fn cache(args: TokenStream, item: TokenStream) -> TokenStream {
             let data = db_result?;
 
             // Save result to cache
-            kite::cache::cache_save!(key = #punctuated_args; value = data.clone());
+            kite::cache::cache_save!(scope = #scope; key = #punctuated_args; value = data.clone());
             Ok(data)
         }
     });
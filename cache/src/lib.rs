@@ -22,6 +22,12 @@ use once_cell::sync::OnceCell;
 
 pub const SCOPE_PUBLIC: u8 = 0;
 pub const SCOPE_BALANCE: u8 = 1;
+/// Classroom occupancy, depending on the course arrangement in the `edu` schema.
+pub const SCOPE_CLASSROOM: u8 = 2;
+/// Term calendars and class periods, erased when the calendar is changed.
+pub const SCOPE_CALENDAR: u8 = 3;
+/// Campus card statistics, erased when transactions are imported.
+pub const SCOPE_CARD: u8 = 4;
 
 // TODO: Consider OS compatability
 const SLED_CACHE_PATH: &'static str = "./.runtime-cache/";
@@ -101,7 +107,7 @@ pub fn u64_to_u8_array(mut n: u64) -> [u8; 8] {
 #[macro_export]
 macro_rules! cache_calc_key {
     ($($arg: expr),*) => {{
-        $crate::cache_calc_key!(scope = $crare::SCOPE_PUBLIC, $($arg),*)
+        $crate::cache_calc_key!(scope = $crate::SCOPE_PUBLIC; $($arg),*)
     }};
     (scope = $scope: expr; $($arg: expr),*) => {{
         let scope = $scope;
@@ -162,7 +168,7 @@ macro_rules! cache_erase {
         use $crate::CacheOperation;
 
         let cache = $crate::get();
-        let cache_key = $crate::cache_calc_key!($($arg),*);
+        let cache_key = $crate::cache_calc_key!(scope = $scope; $($arg),*);
         if let Err(e) = cache.erase(&cache_key) {
             tracing::warn!("failed to erase item in cache (key: {:?}): {}", cache_key, e);
         }
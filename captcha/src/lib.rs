@@ -69,6 +69,11 @@ pub async fn async_init() {
     let _ = CHANNEL_SENDER.set(tx);
 }
 
+/// Whether `async_init()` has finished and the recognition thread is accepting images.
+pub fn is_ready() -> bool {
+    CHANNEL_SENDER.get().map(|sender| !sender.is_closed()).unwrap_or(false)
+}
+
 pub async fn async_recognize(image: Vec<u8>) -> Result<String> {
     let sender = CHANNEL_SENDER
         .get()
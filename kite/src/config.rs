@@ -30,17 +30,48 @@ pub struct ServerConfig {
     /// Usually "0.0.0.0:443"
     pub bind: String,
     /// JWT Secret for encrypt.
+    /// When `jwt` is set, it's only used to verify old tokens which carry no `kid`, if `jwt.accept_legacy` is on.
     pub secret: String,
+    /// JWT keyring, which allows key rotation and asymmetric signing.
+    pub jwt: Option<JwtConfig>,
     /// Database for postgresql.
     pub db: String,
     /// Max db conn
     pub db_conn: u32,
+    /// Times to retry OCR when authserver rejects the captcha, before asking user. 2 by default.
+    pub captcha_ocr_retry: Option<u32>,
 
     /* External API */
     /// QWeather.com API key.
     pub qweather_key: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct JwtConfig {
+    /// `kid` of the key used to sign new tokens.
+    pub active: String,
+    /// Keys accepted on verification, including the active one.
+    pub keys: Vec<JwtKeyConfig>,
+    /// Whether tokens without `kid`, which are signed by `secret`, are still accepted. Off by default,
+    /// turn it on only during the migration to keyring.
+    #[serde(default)]
+    pub accept_legacy: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JwtKeyConfig {
+    /// Key ID, written to the `kid` field of token header.
+    pub kid: String,
+    /// Algorithm name, "HS256", "EdDSA" or "RS256" for example.
+    pub algorithm: String,
+    /// Shared secret for HMAC algorithms.
+    pub secret: Option<String>,
+    /// Path to the PEM private key for asymmetric algorithms. Only the active key needs it.
+    pub private_key: Option<String>,
+    /// Path to the PEM public key for asymmetric algorithms.
+    pub public_key: Option<String>,
+}
+
 fn get_config_path() -> String {
     std::env::var_os("KITE_CONFIG")
         .and_then(|s| Some(s.into_string().unwrap()))
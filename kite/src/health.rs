@@ -0,0 +1,81 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use sqlx::PgPool;
+
+/// Max time to wait for the database before marking it unhealthy.
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+/// The balance updater pulls every 20 minutes, so data older than three missed pulls is stale.
+const BALANCE_MAX_AGE_SECS: i64 = 3600;
+
+/// Health of the dependencies that the http and gRPC servers rely on.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct HealthReport {
+    /// Whether the database pool can execute queries.
+    pub database: bool,
+    /// Whether the captcha recognition module finished initializing.
+    pub captcha: bool,
+    /// Whether the dormitory balance is refreshed recently by balance-updater.
+    pub balance: bool,
+}
+
+impl HealthReport {
+    /// Whether every dependency is available.
+    pub fn is_ready(&self) -> bool {
+        self.database && self.captcha && self.balance
+    }
+}
+
+/// Check whether the database pool is usable by sending a trivial query.
+pub async fn check_database(pool: &PgPool) -> bool {
+    if pool.is_closed() {
+        return false;
+    }
+    let query = sqlx::query("SELECT 1;").execute(pool);
+    matches!(tokio::time::timeout(DB_CHECK_TIMEOUT, query).await, Ok(Ok(_)))
+}
+
+/// Check whether balance-updater has written the dormitory balance table recently.
+pub async fn check_balance_freshness(pool: &PgPool) -> bool {
+    let query = sqlx::query_as("SELECT MAX(ts) FROM dormitory_balance;").fetch_one(pool);
+
+    match tokio::time::timeout(DB_CHECK_TIMEOUT, query).await {
+        Ok(Ok((Some(ts),))) => {
+            let ts: DateTime<Local> = ts;
+            (Local::now() - ts).num_seconds() < BALANCE_MAX_AGE_SECS
+        }
+        _ => false,
+    }
+}
+
+/// Collect a health report. The captcha module is not a dependency of this crate, so the caller
+/// passes its state in.
+pub async fn check(pool: &PgPool, captcha_ready: bool) -> HealthReport {
+    let database = check_database(pool).await;
+    let balance = database && check_balance_freshness(pool).await;
+
+    HealthReport {
+        database,
+        captcha: captcha_ready,
+        balance,
+    }
+}
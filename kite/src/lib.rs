@@ -22,5 +22,6 @@ pub use db::get_db;
 
 pub mod config;
 pub mod db;
+pub mod health;
 pub mod model;
 pub mod service;
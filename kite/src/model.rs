@@ -21,6 +21,18 @@ pub use template::*;
 pub mod badge;
 pub mod balance;
 pub mod board;
+pub mod calendar;
+pub mod campus_session;
+pub mod card;
+pub mod captcha_sample;
 pub mod classroom_browser;
+pub mod edu;
+pub mod exam;
+pub mod freshman;
+pub mod library;
+pub mod notification;
+pub mod score;
+pub mod second_class;
 pub mod template;
+pub mod timetable;
 pub mod user;
@@ -0,0 +1,387 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use bincode::{Decode, Encode};
+use chrono::{Duration, NaiveDate, NaiveTime};
+use sqlx::{FromRow, PgPool};
+
+use crate as kite;
+use crate::model::classroom_browser::calculate_week_day;
+use crate::model::timetable::CLASS_TIME;
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode, FromRow)]
+pub struct Term {
+    /// 学年起始年份
+    pub year: i32,
+    /// 学期, 1 或 2
+    pub semester: i32,
+    /// 第一周的周一
+    #[bincode(with_serde)]
+    pub begin_date: NaiveDate,
+    /// 学期最后一天
+    #[bincode(with_serde)]
+    pub end_date: NaiveDate,
+}
+
+/// A day whose schedule differs from the usual one: a holiday, or a make-up day (调休) with classes of another day.
+#[derive(Debug, Clone, PartialEq, Encode, Decode, FromRow)]
+pub struct DayOverride {
+    #[bincode(with_serde)]
+    pub date: NaiveDate,
+    /// Classes on `date` follow the schedule of this day, and there is no class if `None`.
+    #[bincode(with_serde)]
+    pub follow_date: Option<NaiveDate>,
+    /// 名称, 如 "国庆节"
+    pub name: String,
+}
+
+/// Position of a date in a term.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct TermDay {
+    pub year: i32,
+    pub semester: i32,
+    /// Week and day whose classes are taken, see `calculate_week_day`.
+    pub week: i32,
+    pub day: i32,
+    pub has_class: bool,
+    /// Name of the holiday or make-up day, empty on usual days.
+    pub note: String,
+}
+
+/// Begin and end time of a class period.
+#[derive(Debug, Clone, PartialEq, Encode, Decode, FromRow)]
+pub struct ClassPeriod {
+    /// 1 - 11
+    pub index: i32,
+    #[bincode(with_serde)]
+    pub begin_time: NaiveTime,
+    #[bincode(with_serde)]
+    pub end_time: NaiveTime,
+}
+
+/// A term with its holidays and make-up days.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct TermCalendar {
+    pub term: Term,
+    pub overrides: Vec<DayOverride>,
+}
+
+impl TermCalendar {
+    /// Calendar of a term without overrides, used when only the begin date is known.
+    pub fn from_begin_date(year: i32, semester: i32, begin_date: NaiveDate, weeks: i32) -> Self {
+        let term = Term {
+            year,
+            semester,
+            begin_date,
+            end_date: begin_date + Duration::weeks(weeks as i64) - Duration::days(1),
+        };
+        TermCalendar {
+            term,
+            overrides: Vec::new(),
+        }
+    }
+
+    fn find_override(&self, date: NaiveDate) -> Option<&DayOverride> {
+        self.overrides.iter().find(|o| o.date == date)
+    }
+
+    /// Week and day of `date`, following holidays and make-up days. `None` if it's out of the term.
+    pub fn resolve(&self, date: NaiveDate) -> Option<TermDay> {
+        let term = &self.term;
+        let (schedule_date, has_class, note) = match self.find_override(date) {
+            Some(DayOverride {
+                follow_date: Some(follow),
+                name,
+                ..
+            }) => (*follow, true, name.clone()),
+            Some(DayOverride { name, .. }) => (date, false, name.clone()),
+            None => (date, true, String::new()),
+        };
+        // A make-up day may be just out of the term, and it belongs to the term if the day it follows does.
+        if schedule_date < term.begin_date || schedule_date > term.end_date {
+            return None;
+        }
+        let (week, day) = calculate_week_day(term.begin_date, schedule_date);
+
+        Some(TermDay {
+            year: term.year,
+            semester: term.semester,
+            week,
+            day,
+            has_class,
+            note,
+        })
+    }
+
+    /// Dates on which classes of `week` and `day` are taken. Usually it's one day, but it may be moved to a
+    /// make-up day, or cancelled for a holiday.
+    pub fn dates_of(&self, week: i32, day: i32) -> Vec<NaiveDate> {
+        let date = self.term.begin_date + Duration::days(((week - 1) * 7 + day - 1) as i64);
+        if date < self.term.begin_date || date > self.term.end_date {
+            return Vec::new();
+        }
+        let mut dates = Vec::new();
+
+        if self.find_override(date).is_none() {
+            dates.push(date);
+        }
+        for o in &self.overrides {
+            if o.follow_date == Some(date) {
+                dates.push(o.date);
+            }
+        }
+        dates.sort();
+        dates
+    }
+}
+
+/// Postgres channel notified after the calendar is changed, so that every instance erases its cached calendars.
+pub const CHANGE_CHANNEL: &str = "calendar_change";
+
+/// Overrides in so many days around a term are loaded with it, since make-up days may be out of the term, such
+/// as the weekend before it begins.
+const OVERRIDE_MARGIN_DAYS: i32 = 7;
+
+/// Cached calendars are erased on changes, rather than expiring in an hour. The local cache is erased at once,
+/// and others on notification.
+async fn erase_cache(db: &PgPool) -> Result<()> {
+    kite::cache::get().erase_keys(kite::cache::SCOPE_CALENDAR);
+    sqlx::query("SELECT pg_notify($1, 'calendar');")
+        .bind(CHANGE_CHANNEL)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+const TERM_COLUMNS: &str = "year, semester, begin_date, end_date";
+
+pub async fn list_terms(db: &PgPool) -> Result<Vec<Term>> {
+    sqlx::query_as(&format!(
+        "SELECT {TERM_COLUMNS} FROM calendar_term ORDER BY begin_date DESC;"
+    ))
+    .fetch_all(db)
+    .await
+    .map_err(Into::into)
+}
+
+pub async fn save_term(db: &PgPool, term: &Term) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO calendar_term (year, semester, begin_date, end_date) VALUES ($1, $2, $3, $4)
+            ON CONFLICT (year, semester) DO UPDATE SET begin_date = $3, end_date = $4;",
+    )
+    .bind(term.year)
+    .bind(term.semester)
+    .bind(term.begin_date)
+    .bind(term.end_date)
+    .execute(db)
+    .await?;
+    erase_cache(db).await
+}
+
+pub async fn list_overrides(db: &PgPool, from: NaiveDate, to: NaiveDate) -> Result<Vec<DayOverride>> {
+    sqlx::query_as(
+        "SELECT date, follow_date, name FROM calendar_day_override WHERE date BETWEEN $1 AND $2 ORDER BY date;",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await
+    .map_err(Into::into)
+}
+
+pub async fn save_override(db: &PgPool, day: &DayOverride) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO calendar_day_override (date, follow_date, name) VALUES ($1, $2, $3)
+            ON CONFLICT (date) DO UPDATE SET follow_date = $2, name = $3;",
+    )
+    .bind(day.date)
+    .bind(day.follow_date)
+    .bind(&day.name)
+    .execute(db)
+    .await?;
+    erase_cache(db).await
+}
+
+pub async fn delete_override(db: &PgPool, date: NaiveDate) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM calendar_day_override WHERE date = $1;")
+        .bind(date)
+        .execute(db)
+        .await?;
+    erase_cache(db).await?;
+    Ok(result.rows_affected() > 0)
+}
+
+async fn load_calendar(db: &PgPool, term: Term) -> Result<TermCalendar> {
+    let margin = Duration::days(OVERRIDE_MARGIN_DAYS as i64);
+    let overrides = list_overrides(db, term.begin_date - margin, term.end_date + margin).await?;
+    Ok(TermCalendar { term, overrides })
+}
+
+/// The calendar which `date` belongs to, see `TermCalendar::resolve`.
+fn find_calendar(calendars: Vec<TermCalendar>, date: NaiveDate) -> Option<TermCalendar> {
+    calendars.into_iter().find(|calendar| calendar.resolve(date).is_some())
+}
+
+/// Calendar of a term. It's cached until the calendar is changed.
+#[crate::cache_result(timeout = 3600, scope = "kite::cache::SCOPE_CALENDAR")]
+pub async fn get_term_calendar(db: &PgPool, year: i32, semester: i32) -> Result<Option<TermCalendar>> {
+    let term = sqlx::query_as(&format!(
+        "SELECT {TERM_COLUMNS} FROM calendar_term WHERE year = $1 AND semester = $2;"
+    ))
+    .bind(year)
+    .bind(semester)
+    .fetch_optional(db)
+    .await?;
+    match term {
+        Some(term) => Ok(Some(load_calendar(db, term).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Calendar of the term which `date` is in, or whose make-up day it is.
+#[crate::cache_result(timeout = 3600, scope = "kite::cache::SCOPE_CALENDAR")]
+pub async fn get_term_calendar_by_date(db: &PgPool, date: NaiveDate) -> Result<Option<TermCalendar>> {
+    let terms: Vec<Term> = sqlx::query_as(&format!(
+        "SELECT {TERM_COLUMNS} FROM calendar_term
+            WHERE $1 BETWEEN begin_date - $2 AND end_date + $2
+            ORDER BY begin_date;"
+    ))
+    .bind(date)
+    .bind(OVERRIDE_MARGIN_DAYS)
+    .fetch_all(db)
+    .await?;
+
+    let mut calendars = Vec::with_capacity(terms.len());
+    for term in terms {
+        calendars.push(load_calendar(db, term).await?);
+    }
+    Ok(find_calendar(calendars, date))
+}
+
+/// Campus used to get class periods if it's not specified or unknown, 奉贤校区.
+pub const DEFAULT_CAMPUS: i32 = 1;
+
+/// Class periods of a campus. The default time table is used if it's not configured.
+#[crate::cache_result(timeout = 3600, scope = "kite::cache::SCOPE_CALENDAR")]
+pub async fn get_class_periods(db: &PgPool, campus: i32) -> Result<Vec<ClassPeriod>> {
+    let periods: Vec<ClassPeriod> = sqlx::query_as(
+        "SELECT period AS index, begin_time, end_time FROM calendar_class_period WHERE campus = $1 ORDER BY period;",
+    )
+    .bind(campus)
+    .fetch_all(db)
+    .await?;
+
+    if !periods.is_empty() {
+        return Ok(periods);
+    }
+    Ok(default_class_periods())
+}
+
+/// Class periods of the default time table.
+pub fn default_class_periods() -> Vec<ClassPeriod> {
+    CLASS_TIME
+        .iter()
+        .enumerate()
+        .map(|(i, &(begin_time, end_time))| ClassPeriod {
+            index: i as i32 + 1,
+            begin_time,
+            end_time,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn calendar() -> TermCalendar {
+        let mut calendar = TermCalendar::from_begin_date(2023, 1, date("2023-09-04"), 18);
+        // 国庆节 10-01 ~ 10-06 (week 4 - 5), and classes of 10-05 and 10-06 are taken on 10-07 and 10-08.
+        for day in 1..=6 {
+            let d = NaiveDate::from_ymd_opt(2023, 10, day).unwrap();
+            calendar.overrides.push(DayOverride {
+                date: d,
+                follow_date: None,
+                name: "国庆节".to_string(),
+            });
+        }
+        calendar.overrides.push(DayOverride {
+            date: date("2023-10-07"),
+            follow_date: Some(date("2023-10-05")),
+            name: "国庆调休".to_string(),
+        });
+        // Classes of Friday in week 1 are taken on the Saturday before the term.
+        calendar.overrides.push(DayOverride {
+            date: date("2023-09-02"),
+            follow_date: Some(date("2023-09-08")),
+            name: "调休".to_string(),
+        });
+        calendar.overrides.push(DayOverride {
+            date: date("2023-10-08"),
+            follow_date: Some(date("2023-10-06")),
+            name: "国庆调休".to_string(),
+        });
+        calendar
+    }
+
+    #[test]
+    fn test_resolve() {
+        let calendar = calendar();
+        let day = |s: &str| calendar.resolve(date(s)).map(|d| (d.week, d.day, d.has_class));
+
+        assert_eq!(day("2023-09-04"), Some((1, 1, true)));
+        assert_eq!(day("2023-09-17"), Some((2, 7, true)));
+        assert_eq!(day("2023-10-02"), Some((5, 1, false)));
+        assert_eq!(calendar.resolve(date("2023-10-02")).unwrap().note, "国庆节");
+        // Saturday with classes of Thursday.
+        assert_eq!(day("2023-10-07"), Some((5, 4, true)));
+        assert_eq!(day("2023-09-03"), None);
+        assert_eq!(day("2023-09-02"), Some((1, 5, true)));
+        assert_eq!(day("2024-01-07"), Some((18, 7, true)));
+        assert_eq!(day("2024-01-08"), None);
+    }
+
+    #[test]
+    fn test_dates_of() {
+        let calendar = calendar();
+
+        assert_eq!(calendar.dates_of(1, 1), vec![date("2023-09-04")]);
+        assert_eq!(calendar.dates_of(1, 5), vec![date("2023-09-02"), date("2023-09-08")]);
+        assert_eq!(calendar.dates_of(19, 1), vec![]);
+        assert_eq!(calendar.dates_of(5, 1), vec![]);
+        assert_eq!(calendar.dates_of(5, 4), vec![date("2023-10-07")]);
+        assert_eq!(calendar.dates_of(5, 5), vec![date("2023-10-08")]);
+    }
+
+    #[test]
+    fn test_find_calendar() {
+        let next = TermCalendar::from_begin_date(2023, 2, date("2024-02-26"), 18);
+        let find = |s: &str| find_calendar(vec![calendar(), next.clone()], date(s)).map(|c| c.term.semester);
+
+        assert_eq!(find("2023-09-02"), Some(1));
+        assert_eq!(find("2023-09-03"), None);
+        assert_eq!(find("2024-01-07"), Some(1));
+        assert_eq!(find("2024-02-25"), None);
+        assert_eq!(find("2024-02-26"), Some(2));
+    }
+}
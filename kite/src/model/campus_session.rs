@@ -0,0 +1,55 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Sessions on campus systems, in the format of `service_v3::campus::SavedSession`.
+
+use anyhow::Result;
+use sqlx::types::JsonValue;
+use sqlx::PgPool;
+
+pub async fn get(db: &PgPool, uid: i32) -> Result<Option<JsonValue>> {
+    let row: Option<(JsonValue,)> = sqlx::query_as("SELECT session FROM campus_session WHERE uid = $1;")
+        .bind(uid)
+        .fetch_optional(db)
+        .await?;
+
+    Ok(row.map(|(session,)| session))
+}
+
+pub async fn save(db: &PgPool, uid: i32, session: JsonValue) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO campus_session (uid, session) VALUES ($1, $2) \
+        ON CONFLICT (uid) DO UPDATE SET session = $2, update_time = current_timestamp;",
+    )
+    .bind(uid)
+    .bind(session)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Forget the sessions, when the user logs out.
+pub async fn delete(db: &PgPool, uid: i32) -> Result<()> {
+    sqlx::query("DELETE FROM campus_session WHERE uid = $1;")
+        .bind(uid)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
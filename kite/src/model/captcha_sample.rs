@@ -0,0 +1,63 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use sqlx::PgPool;
+
+/// Who gives the answer of a captcha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptchaSource {
+    /// 模型识别
+    Ocr,
+    /// 用户手动输入
+    User,
+}
+
+impl CaptchaSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptchaSource::Ocr => "ocr",
+            CaptchaSource::User => "user",
+        }
+    }
+}
+
+/// Captcha image with answer submitted in login, used to evaluate and train the OCR model.
+#[derive(Debug, Clone)]
+pub struct CaptchaSample {
+    /// 验证码图片
+    pub image: Vec<u8>,
+    /// 提交的答案
+    pub answer: String,
+    pub source: CaptchaSource,
+    /// 答案是否正确. 登录因其他原因失败时无法判断
+    pub correct: Option<bool>,
+}
+
+pub async fn save(db: &PgPool, samples: &[CaptchaSample]) -> Result<()> {
+    for sample in samples {
+        sqlx::query("INSERT INTO captcha_sample (image, answer, source, correct) VALUES ($1, $2, $3, $4);")
+            .bind(&sample.image)
+            .bind(&sample.answer)
+            .bind(sample.source.as_str())
+            .bind(sample.correct)
+            .execute(db)
+            .await?;
+    }
+    Ok(())
+}
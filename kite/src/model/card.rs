@@ -0,0 +1,238 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use bincode::{Decode, Encode};
+use chrono::{DateTime, Local, NaiveDate};
+use sqlx::{FromRow, PgPool};
+
+use crate as kite;
+use crate::model::{PageSort, PageView};
+
+/// Postgres channel notified after transactions are imported, so that every instance erases cached statistics.
+pub const IMPORT_CHANNEL: &str = "card_import";
+
+/// 食堂
+pub const CATEGORY_CANTEEN: &str = "canteen";
+/// 超市, 商店
+pub const CATEGORY_SHOP: &str = "shop";
+/// 浴室, 热水
+pub const CATEGORY_BATHHOUSE: &str = "bathhouse";
+/// 充值, 补助等收入
+pub const CATEGORY_RECHARGE: &str = "recharge";
+pub const CATEGORY_OTHER: &str = "other";
+
+/// Campus card transaction, imported from the card system.
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct Transaction {
+    pub ts: DateTime<Local>,
+    /// Negative for consumption, and positive for recharge.
+    pub amount: f32,
+    /// Card balance after the transaction
+    pub balance: f32,
+    /// 商户名称
+    pub merchant: String,
+    /// 交易类型, 如 "持卡人消费"
+    pub kind: String,
+    pub category: String,
+}
+
+/// Income and expense in a day ('yyyy-MM-dd') or a month ('yyyy-MM').
+#[derive(Clone, Encode, Decode, FromRow)]
+pub struct PeriodStatistics {
+    pub period: String,
+    pub income: f32,
+    /// Positive amount of consumption.
+    pub expense: f32,
+}
+
+#[derive(Clone, Encode, Decode, FromRow)]
+pub struct CategoryStatistics {
+    pub category: String,
+    pub expense: f32,
+    pub count: i32,
+}
+
+/// Guess the category of a transaction by its merchant name.
+pub fn categorize(merchant: &str, amount: f32) -> &'static str {
+    let contains = |words: &[&str]| words.iter().any(|w| merchant.contains(w));
+
+    if amount > 0.0 {
+        CATEGORY_RECHARGE
+    } else if contains(&["食堂", "餐厅", "餐饮"]) {
+        CATEGORY_CANTEEN
+    } else if contains(&["超市", "商店", "便利"]) {
+        CATEGORY_SHOP
+    } else if contains(&["浴室", "淋浴", "热水"]) {
+        CATEGORY_BATHHOUSE
+    } else {
+        CATEGORY_OTHER
+    }
+}
+
+/// Save transactions of a user, and return the count of new ones. Transactions imported before are skipped.
+///
+/// Cached statistics are erased if there are new ones, at once on this instance and on notification on others.
+pub async fn save(db: &PgPool, uid: i32, transactions: &[Transaction]) -> Result<u64> {
+    let mut ts = Vec::with_capacity(transactions.len());
+    let mut amount = Vec::with_capacity(transactions.len());
+    let mut balance = Vec::with_capacity(transactions.len());
+    let mut merchant = Vec::with_capacity(transactions.len());
+    let mut kind = Vec::with_capacity(transactions.len());
+    let mut category = Vec::with_capacity(transactions.len());
+    for t in transactions {
+        ts.push(t.ts);
+        amount.push(t.amount);
+        balance.push(t.balance);
+        merchant.push(t.merchant.as_str());
+        kind.push(t.kind.as_str());
+        category.push(t.category.as_str());
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO card_transaction (uid, ts, amount, balance, merchant, kind, category)
+            SELECT $1, * FROM UNNEST($2::timestamptz[], $3::real[], $4::real[], $5::text[], $6::text[], $7::text[])
+            ON CONFLICT DO NOTHING;",
+    )
+    .bind(uid)
+    .bind(ts)
+    .bind(amount)
+    .bind(balance)
+    .bind(merchant)
+    .bind(kind)
+    .bind(category)
+    .execute(db)
+    .await?;
+
+    if result.rows_affected() > 0 {
+        kite::cache::get().erase_keys(kite::cache::SCOPE_CARD);
+        sqlx::query("SELECT pg_notify($1, $2);")
+            .bind(IMPORT_CHANNEL)
+            .bind(uid.to_string())
+            .execute(db)
+            .await?;
+    }
+    Ok(result.rows_affected())
+}
+
+pub async fn list(db: &PgPool, uid: i32, category: Option<&str>, page: &PageView) -> Result<Vec<Transaction>> {
+    let order = match page.sort {
+        PageSort::Asc => "ASC",
+        PageSort::Desc => "DESC",
+    };
+    sqlx::query_as(&format!(
+        "SELECT ts, amount, balance, merchant, kind, category FROM card_transaction
+            WHERE uid = $1 AND ($2::text IS NULL OR category = $2)
+            ORDER BY ts {order}
+            LIMIT $3 OFFSET $4;"
+    ))
+    .bind(uid)
+    .bind(category)
+    .bind(page.count(50))
+    .bind(page.offset(50))
+    .fetch_all(db)
+    .await
+    .map_err(Into::into)
+}
+
+#[crate::cache_result(timeout = 3600, scope = "kite::cache::SCOPE_CARD")]
+pub async fn get_daily_statistics(
+    pool: &PgPool,
+    uid: i32,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<PeriodStatistics>> {
+    sqlx::query_as(
+        "SELECT to_char(d.day, 'yyyy-MM-dd') AS period,
+                COALESCE(SUM(t.amount) FILTER (WHERE t.amount > 0), 0)::real AS income,
+                COALESCE(-SUM(t.amount) FILTER (WHERE t.amount < 0), 0)::real AS expense
+            FROM generate_series($2::date, $3::date, '1 day') AS d(day)
+            LEFT JOIN card_transaction t ON t.uid = $1 AND t.ts::date = d.day::date
+            GROUP BY d.day
+            ORDER BY d.day;",
+    )
+    .bind(uid)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    .map_err(Into::into)
+}
+
+#[crate::cache_result(timeout = 3600, scope = "kite::cache::SCOPE_CARD")]
+pub async fn get_monthly_statistics(
+    pool: &PgPool,
+    uid: i32,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<PeriodStatistics>> {
+    sqlx::query_as(
+        "SELECT to_char(m.month, 'yyyy-MM') AS period,
+                COALESCE(SUM(t.amount) FILTER (WHERE t.amount > 0), 0)::real AS income,
+                COALESCE(-SUM(t.amount) FILTER (WHERE t.amount < 0), 0)::real AS expense
+            FROM generate_series(date_trunc('month', $2::date), $3::date, '1 month') AS m(month)
+            LEFT JOIN card_transaction t ON t.uid = $1
+                AND date_trunc('month', t.ts) = m.month
+                AND t.ts::date BETWEEN $2::date AND $3::date
+            GROUP BY m.month
+            ORDER BY m.month;",
+    )
+    .bind(uid)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    .map_err(Into::into)
+}
+
+#[crate::cache_result(timeout = 3600, scope = "kite::cache::SCOPE_CARD")]
+pub async fn get_category_statistics(
+    pool: &PgPool,
+    uid: i32,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<CategoryStatistics>> {
+    sqlx::query_as(
+        "SELECT category, (-SUM(amount))::real AS expense, COUNT(*)::int AS count
+            FROM card_transaction
+            WHERE uid = $1 AND amount < 0 AND ts::date BETWEEN $2::date AND $3::date
+            GROUP BY category
+            ORDER BY expense DESC;",
+    )
+    .bind(uid)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_categorize() {
+        assert_eq!(categorize("一食堂", -8.5), CATEGORY_CANTEEN);
+        assert_eq!(categorize("第二餐厅", -12.0), CATEGORY_CANTEEN);
+        assert_eq!(categorize("奉贤校区教育超市", -3.0), CATEGORY_SHOP);
+        assert_eq!(categorize("学生浴室", -4.0), CATEGORY_BATHHOUSE);
+        assert_eq!(categorize("一食堂", 100.0), CATEGORY_RECHARGE);
+        assert_eq!(categorize("图书馆打印", -1.0), CATEGORY_OTHER);
+    }
+}
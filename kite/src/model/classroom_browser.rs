@@ -17,7 +17,10 @@
  */
 
 use bincode::{Decode, Encode};
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::model::calendar::ClassPeriod;
+use crate::model::edu::Building;
 
 #[derive(Encode, Decode, Clone, sqlx::FromRow)]
 pub struct Classroom {
@@ -27,6 +30,10 @@ pub struct Classroom {
     pub busy_flag: i32,
     /// Room seats
     pub capacity: Option<i32>,
+    /// See `edu::ROOM_TYPE_*`
+    pub room_type: i32,
+    pub multimedia: bool,
+    pub floor: Option<i32>,
 }
 
 #[derive(Debug, Encode, Decode, Clone, sqlx::FromRow)]
@@ -47,6 +54,53 @@ pub struct ClassroomQuery {
     /// right presents the first class (8:20 - 9:55).
     /// For example, 110b to find the available classroom on class 1-2.
     pub want_time: Option<i32>,
+    /// Minimum room seats
+    pub min_capacity: Option<i32>,
+    /// See `edu::ROOM_TYPE_*`
+    pub room_type: Option<i32>,
+    /// Only rooms with multimedia equipment if set
+    pub multimedia: bool,
+    pub floor: Option<i32>,
+}
+
+/// Occupancy of a room in a class period.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct ScheduleSlot {
+    pub busy: bool,
+    /// The course in the room, if known.
+    pub course_name: Option<String>,
+}
+
+/// Occupancy of a room in a week.
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct ClassroomSchedule {
+    pub room: String,
+    pub capacity: Option<i32>,
+    pub week: i32,
+    /// 7 days × 11 class periods
+    pub days: Vec<Vec<ScheduleSlot>>,
+}
+
+/// Build the 7 × 11 grid by busy flags of Monday to Sunday, and courses in the form of (day, time flag, name).
+///
+/// A slot is busy if the room is booked in the busy flag or a course is known to be there.
+pub fn build_schedule_grid(busy_flags: &[i32; 7], courses: &[(i32, i32, String)]) -> Vec<Vec<ScheduleSlot>> {
+    (1..=7)
+        .map(|day| {
+            (1..=11)
+                .map(|index| {
+                    let course_name = courses
+                        .iter()
+                        .find(|(d, flag, _)| *d == day && flag & (1 << index) != 0)
+                        .map(|(_, _, name)| name.clone());
+                    ScheduleSlot {
+                        busy: busy_flags[day as usize - 1] & (1 << index) != 0 || course_name.is_some(),
+                        course_name,
+                    }
+                })
+                .collect()
+        })
+        .collect()
 }
 
 /// Convert course index range string (like 1-9, 2-4) to binary, as a integer
@@ -74,6 +128,91 @@ pub fn convert_range_string_to_binary(s: &str) -> i32 {
     result
 }
 
+/// Convert a time range in a day to the flag of class periods it overlaps, in the same format as
+/// `convert_range_string_to_binary`. If `begin` equals `end`, the period containing that moment is taken.
+pub fn convert_time_range_to_binary(periods: &[ClassPeriod], begin: NaiveTime, end: NaiveTime) -> i32 {
+    periods
+        .iter()
+        .filter(|p| {
+            if begin == end {
+                p.begin_time <= begin && begin < p.end_time
+            } else {
+                p.begin_time < end && begin < p.end_time
+            }
+        })
+        .fold(0, |flag, p| flag | (1 << p.index))
+}
+
+/// The time when a room with `busy_flag` is occupied next after `time`, which is `time` itself if the room is
+/// busy at the moment. `None` if it's free for the rest of the day.
+pub fn calculate_free_until(busy_flag: i32, periods: &[ClassPeriod], time: NaiveTime) -> Option<NaiveTime> {
+    periods
+        .iter()
+        .filter(|p| busy_flag & (1 << p.index) != 0 && p.end_time > time)
+        .map(|p| p.begin_time.max(time))
+        .min()
+}
+
+/// The longest run of continuous free class periods in a day.
+pub fn longest_free_window(busy_flag: i32) -> i32 {
+    let (mut longest, mut current) = (0, 0);
+    for i in 1..=11 {
+        if busy_flag & (1 << i) == 0 {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+/// Sort rooms by the longest free window, from long to short.
+pub fn rank_by_free_window(rooms: &mut [Classroom]) {
+    rooms.sort_by_key(|room| std::cmp::Reverse(longest_free_window(room.busy_flag)));
+}
+
+/// Approximate distance in meters, which is accurate enough inside a campus.
+fn distance(a: &Building, b: &Building) -> f64 {
+    const EARTH_RADIUS: f64 = 6_371_000.0;
+
+    let latitude = (a.latitude + b.latitude).to_radians() / 2.0;
+    let x = (b.longitude - a.longitude).to_radians() * latitude.cos();
+    let y = (b.latitude - a.latitude).to_radians();
+    (x * x + y * y).sqrt() * EARTH_RADIUS
+}
+
+/// Sort rooms by the distance between its building and `near`. Rooms in `near` come first, and rooms in
+/// buildings without location come last.
+pub fn rank_by_distance(rooms: &mut [Classroom], near: &str, buildings: &[Building]) {
+    let find = |name: &str| buildings.iter().find(|b| b.name == name);
+    let origin = find(near);
+    let room_distance = |room: &Classroom| {
+        let building = split_room_title(&room.title).map(|(building, _)| building);
+        match building.as_deref() {
+            Some(building) if building == near => 0.0,
+            Some(building) => match (origin, find(building)) {
+                (Some(origin), Some(building)) => distance(origin, building),
+                _ => f64::INFINITY,
+            },
+            None => f64::INFINITY,
+        }
+    };
+    rooms.sort_by(|a, b| room_distance(a).total_cmp(&room_distance(b)));
+}
+
+/// Get building and region from room title, like ("一教", Some("A")) for "一教A101".
+///
+/// The naming is the same as `ClassroomQuery`, and `None` is returned for rooms outside teaching buildings.
+pub fn split_room_title(title: &str) -> Option<(String, Option<String>)> {
+    let regex = regex_macro::regex!(r"([一二三四五六七八九十]+教)([A-Z])?\d*");
+    let captures = regex.captures(title)?;
+
+    let building = captures[1].to_string();
+    let region = captures.get(2).map(|m| m.as_str().to_string());
+    Some((building, region))
+}
+
 /// Calculate week and day pair.
 pub fn calculate_week_day(term_begin: NaiveDate, date_to_calculate: NaiveDate) -> (i32, i32) {
     // Calculate the days between two dates
@@ -88,20 +227,155 @@ pub fn calculate_week_day(term_begin: NaiveDate, date_to_calculate: NaiveDate) -
 
 #[cfg(test)]
 mod test {
+    use chrono::NaiveTime;
+
     use super::calculate_week_day;
     use super::convert_range_string_to_binary;
+    use super::split_room_title;
+    use super::{build_schedule_grid, calculate_free_until, convert_time_range_to_binary};
+    use super::{longest_free_window, rank_by_distance, rank_by_free_window, Classroom};
+    use crate::model::calendar::ClassPeriod;
+    use crate::model::edu::Building;
+    use crate::model::timetable::CLASS_TIME;
+
+    fn periods() -> Vec<ClassPeriod> {
+        CLASS_TIME
+            .iter()
+            .enumerate()
+            .map(|(i, &(begin_time, end_time))| ClassPeriod {
+                index: i as i32 + 1,
+                begin_time,
+                end_time,
+            })
+            .collect()
+    }
+
+    fn t(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_convert_time_range_to_binary() {
+        let periods = periods();
+
+        assert_eq!(convert_time_range_to_binary(&periods, t(8, 20), t(9, 55)), 0b110);
+        assert_eq!(convert_time_range_to_binary(&periods, t(8, 30), t(8, 30)), 0b10);
+        assert_eq!(convert_time_range_to_binary(&periods, t(9, 0), t(10, 30)), 0b1110);
+        // Lunch break
+        assert_eq!(convert_time_range_to_binary(&periods, t(12, 0), t(12, 0)), 0);
+        assert_eq!(convert_time_range_to_binary(&periods, t(0, 0), t(23, 59)), 4094);
+    }
+
+    #[test]
+    fn test_calculate_free_until() {
+        let periods = periods();
+        // Class 3-4 and 7-8
+        let busy = 0b110011000;
+
+        assert_eq!(calculate_free_until(busy, &periods, t(8, 0)), Some(t(10, 15)));
+        assert_eq!(calculate_free_until(busy, &periods, t(10, 30)), Some(t(10, 30)));
+        assert_eq!(calculate_free_until(busy, &periods, t(12, 0)), Some(t(14, 55)));
+        assert_eq!(calculate_free_until(busy, &periods, t(16, 30)), None);
+        assert_eq!(calculate_free_until(0, &periods, t(8, 0)), None);
+    }
+
+    #[test]
+    fn test_split_room_title() {
+        let room = |b: &str, r: Option<&str>| Some((b.to_string(), r.map(ToString::to_string)));
+
+        assert_eq!(split_room_title("一教A101"), room("一教", Some("A")));
+        assert_eq!(split_room_title("奉贤校区 二教B203"), room("二教", Some("B")));
+        assert_eq!(split_room_title("十教101"), room("十教", None));
+        assert_eq!(split_room_title("体育馆"), None);
+    }
+
+    fn room(title: &str, busy_flag: i32) -> Classroom {
+        Classroom {
+            title: title.to_string(),
+            busy_flag,
+            capacity: None,
+            room_type: 0,
+            multimedia: false,
+            floor: None,
+        }
+    }
+
+    fn titles(rooms: &[Classroom]) -> Vec<&str> {
+        rooms.iter().map(|room| room.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_longest_free_window() {
+        assert_eq!(longest_free_window(0), 11);
+        assert_eq!(longest_free_window(4094), 0);
+        // Busy in class 1-2 and 5-6
+        assert_eq!(longest_free_window(0b1100110), 5);
+    }
+
+    #[test]
+    fn test_rank_by_free_window() {
+        let mut rooms = vec![room("一教A101", 0b110), room("一教A102", 0b100000), room("一教A103", 0)];
+        rank_by_free_window(&mut rooms);
+        assert_eq!(titles(&rooms), vec!["一教A103", "一教A101", "一教A102"]);
+    }
+
+    #[test]
+    fn test_rank_by_distance() {
+        let building = |name: &str, latitude, longitude| Building {
+            name: name.to_string(),
+            campus: 1,
+            latitude,
+            longitude,
+        };
+        let buildings = vec![
+            building("一教", 30.8400, 121.5100),
+            building("二教", 30.8420, 121.5100),
+            building("三教", 30.8410, 121.5100),
+        ];
+        let mut rooms = vec![
+            room("二教B201", 0),
+            room("体育馆", 0),
+            room("三教C301", 0),
+            room("五教E101", 0),
+            room("一教A101", 0),
+        ];
+        rank_by_distance(&mut rooms, "一教", &buildings);
+        assert_eq!(
+            titles(&rooms),
+            vec!["一教A101", "三教C301", "二教B201", "体育馆", "五教E101"]
+        );
+    }
+
+    #[test]
+    fn test_build_schedule_grid() {
+        // Monday class 1-2, and Wednesday class 5-6.
+        let busy_flags = [0b110, 0, 0b1100000, 0, 0, 0, 0];
+        let courses = vec![(1, 0b110, "高等数学".to_string()), (2, 0b11000, "大学物理".to_string())];
+        let grid = build_schedule_grid(&busy_flags, &courses);
+
+        assert_eq!(grid.len(), 7);
+        assert!(grid.iter().all(|day| day.len() == 11));
+        assert!(grid[0][0].busy && grid[0][1].busy && !grid[0][2].busy);
+        assert_eq!(grid[0][1].course_name.as_deref(), Some("高等数学"));
+        // Known from the timetable, but not in the busy flag.
+        assert!(grid[1][2].busy);
+        assert_eq!(grid[1][3].course_name.as_deref(), Some("大学物理"));
+        assert!(grid[2][4].busy && grid[2][4].course_name.is_none());
+        assert!(!grid[6].iter().any(|slot| slot.busy));
+    }
 
     #[test]
     fn test_convert_time_string() {
         // Normal cases
-        assert_eq!(convert_time_string("1-11"), 4094); // 1111 1111 1110
-        assert_eq!(convert_time_string("1-2"), 6); // 0110
-        assert_eq!(convert_time_string(""), 0);
-        assert_eq!(convert_time_string("1-2,3-4"), 30); // 0001 1110
-        assert_eq!(convert_time_string("1-2,5-6"), 102); // 0110 0110
-        assert_eq!(convert_time_string("1-2,5-6,9-11"), 3686); // 1110 0110 0110
-                                                               // Error cases
-        assert_eq!(convert_time_string("1-a"), 0);
-        assert_eq!(convert_time_string("1-2,1-b"), 6);
+        assert_eq!(convert_range_string_to_binary("1-11"), 4094); // 1111 1111 1110
+        assert_eq!(convert_range_string_to_binary("1-2"), 6); // 0110
+        assert_eq!(convert_range_string_to_binary(""), 0);
+        assert_eq!(convert_range_string_to_binary("1-2,3-4"), 30); // 0001 1110
+        assert_eq!(convert_range_string_to_binary("1-2,5-6"), 102); // 0110 0110
+        assert_eq!(convert_range_string_to_binary("1-2,5-6,9-11"), 3686); // 1110 0110 0110
+
+        // Error cases
+        assert_eq!(convert_range_string_to_binary("1-a"), 0);
+        assert_eq!(convert_range_string_to_binary("1-2,1-b"), 6);
     }
 }
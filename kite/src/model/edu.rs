@@ -0,0 +1,381 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Course arrangement of the whole school in the `edu` schema, which classroom occupancy is calculated from.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use bincode::{Decode, Encode};
+use sqlx::PgPool;
+
+/// Postgres channel notified after the course arrangement of a term is imported, with payload "year-semester",
+/// or classrooms and buildings are imported, with payload "room" and "building".
+pub const IMPORT_CHANNEL: &str = "edu_import";
+
+/// A course taking a room, imported from the export of the academic system.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Arrangement {
+    /// 课程代码
+    pub course_code: String,
+    /// 课程名称
+    pub course_name: String,
+    /// 教学班名称
+    pub class_name: String,
+    /// 任课教师
+    pub teacher: String,
+    /// 校区, 1 = 奉贤校区, 2 = 徐汇校区
+    pub campus: i32,
+    /// 上课地点, 如 "一教A101"
+    pub room: String,
+    /// Week flag, in the same format as `timetable::Course::weeks`.
+    pub weeks: i32,
+    /// Day index in a week, 1 - 7
+    pub day: i32,
+    /// Course time flag, in the same format as `classroom_browser::convert_range_string_to_binary`.
+    pub time_index: i32,
+}
+
+/// 普通教室
+pub const ROOM_TYPE_ORDINARY: i32 = 0;
+/// 阶梯教室、报告厅
+pub const ROOM_TYPE_LECTURE_HALL: i32 = 1;
+/// 实验室、机房
+pub const ROOM_TYPE_LAB: i32 = 2;
+
+/// Classroom metadata, imported from the room list of the academic system.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Room {
+    /// 教室名称, 如 "一教A101"
+    pub title: String,
+    pub campus: i32,
+    /// 教学楼和区域, see `classroom_browser::split_room_title`
+    pub building: String,
+    pub region: Option<String>,
+    /// 座位数
+    pub capacity: Option<i32>,
+    /// One of `ROOM_TYPE_*`
+    pub room_type: i32,
+    /// 是否有多媒体设备
+    pub multimedia: bool,
+    pub floor: Option<i32>,
+}
+
+/// Location of a building.
+#[derive(Debug, Clone, PartialEq, Encode, Decode, sqlx::FromRow)]
+pub struct Building {
+    /// 教学楼名称, 如 "一教"
+    pub name: String,
+    pub campus: i32,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Room type by its name in the room list, like "阶梯教室".
+pub fn parse_room_type(name: &str) -> Option<i32> {
+    match name {
+        "" | "普通教室" | "多媒体教室" => Some(ROOM_TYPE_ORDINARY),
+        "阶梯教室" | "报告厅" => Some(ROOM_TYPE_LECTURE_HALL),
+        "实验室" | "机房" => Some(ROOM_TYPE_LAB),
+        _ => None,
+    }
+}
+
+/// Floor by the room number in the title, like 1 for "一教A101" and 12 for "一教A1201".
+pub fn parse_floor(title: &str) -> Option<i32> {
+    let digits: String = title.chars().rev().take_while(char::is_ascii_digit).collect();
+    match digits.len() {
+        3 | 4 => digits
+            .chars()
+            .rev()
+            .collect::<String>()
+            .parse::<i32>()
+            .ok()
+            .map(|n| n / 100),
+        _ => None,
+    }
+}
+
+/// Campus id by its name, like "奉贤校区".
+pub fn parse_campus(name: &str) -> Option<i32> {
+    if name.contains("奉贤") {
+        Some(1)
+    } else if name.contains("徐汇") {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Find pairs of arrangements of different classes, which take the same room at the same time.
+///
+/// Pairs are indexes into `arrangements`, and the smaller one comes first.
+pub fn find_conflicts(arrangements: &[Arrangement]) -> Vec<(usize, usize)> {
+    let mut groups: HashMap<(&str, i32), Vec<usize>> = HashMap::new();
+    for (i, a) in arrangements.iter().enumerate() {
+        groups.entry((a.room.as_str(), a.day)).or_default().push(i);
+    }
+
+    let mut result = Vec::new();
+    for indexes in groups.values() {
+        for (k, &i) in indexes.iter().enumerate() {
+            for &j in &indexes[k + 1..] {
+                let (a, b) = (&arrangements[i], &arrangements[j]);
+                if a.class_name != b.class_name && a.weeks & b.weeks != 0 && a.time_index & b.time_index != 0 {
+                    result.push((i, j));
+                }
+            }
+        }
+    }
+    result.sort_unstable();
+    result
+}
+
+/// Titles of all known classrooms.
+pub async fn list_rooms(db: &PgPool) -> Result<Vec<String>> {
+    let rows: Vec<(String,)> = sqlx::query_as("SELECT title FROM edu.classroom;").fetch_all(db).await?;
+
+    Ok(rows.into_iter().map(|(title,)| title).collect())
+}
+
+/// Insert or update classrooms, and notify `IMPORT_CHANNEL` on commit.
+pub async fn save_rooms(db: &PgPool, rooms: &[Room]) -> Result<u64> {
+    let mut title = Vec::with_capacity(rooms.len());
+    let mut campus = Vec::with_capacity(rooms.len());
+    let mut building = Vec::with_capacity(rooms.len());
+    let mut region = Vec::with_capacity(rooms.len());
+    let mut capacity = Vec::with_capacity(rooms.len());
+    let mut room_type = Vec::with_capacity(rooms.len());
+    let mut multimedia = Vec::with_capacity(rooms.len());
+    let mut floor = Vec::with_capacity(rooms.len());
+    for r in rooms {
+        title.push(r.title.as_str());
+        campus.push(r.campus);
+        building.push(r.building.as_str());
+        region.push(r.region.as_deref());
+        capacity.push(r.capacity);
+        room_type.push(r.room_type);
+        multimedia.push(r.multimedia);
+        floor.push(r.floor);
+    }
+
+    let mut tx = db.begin().await?;
+    let result = sqlx::query(
+        "INSERT INTO edu.classroom (title, campus, building, region, capacity, room_type, multimedia, floor)
+            SELECT * FROM UNNEST($1::text[], $2::int[], $3::text[], $4::text[], $5::int[], $6::int[], $7::bool[],
+                $8::int[])
+            ON CONFLICT (title) DO UPDATE SET campus = excluded.campus, building = excluded.building,
+                region = excluded.region, capacity = excluded.capacity, room_type = excluded.room_type,
+                multimedia = excluded.multimedia, floor = excluded.floor;",
+    )
+    .bind(title)
+    .bind(campus)
+    .bind(building)
+    .bind(region)
+    .bind(capacity)
+    .bind(room_type)
+    .bind(multimedia)
+    .bind(floor)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("SELECT pg_notify($1, 'room');")
+        .bind(IMPORT_CHANNEL)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn list_buildings(db: &PgPool) -> Result<Vec<Building>> {
+    sqlx::query_as("SELECT name, campus, latitude, longitude FROM edu.building;")
+        .fetch_all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Insert or update buildings, and notify `IMPORT_CHANNEL` on commit.
+pub async fn save_buildings(db: &PgPool, buildings: &[Building]) -> Result<u64> {
+    let name: Vec<_> = buildings.iter().map(|b| b.name.as_str()).collect();
+    let campus: Vec<_> = buildings.iter().map(|b| b.campus).collect();
+    let latitude: Vec<_> = buildings.iter().map(|b| b.latitude).collect();
+    let longitude: Vec<_> = buildings.iter().map(|b| b.longitude).collect();
+
+    let mut tx = db.begin().await?;
+    let result = sqlx::query(
+        "INSERT INTO edu.building (name, campus, latitude, longitude)
+            SELECT * FROM UNNEST($1::text[], $2::int[], $3::float8[], $4::float8[])
+            ON CONFLICT (name) DO UPDATE SET campus = excluded.campus, latitude = excluded.latitude,
+                longitude = excluded.longitude;",
+    )
+    .bind(name)
+    .bind(campus)
+    .bind(latitude)
+    .bind(longitude)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("SELECT pg_notify($1, 'building');")
+        .bind(IMPORT_CHANNEL)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Replace the course arrangement of a term, and notify `IMPORT_CHANNEL` on commit.
+///
+/// Re-importing the same data leaves the table unchanged, so it's safe to run again during add/drop week.
+pub async fn replace(db: &PgPool, year: i32, semester: i32, arrangements: &[Arrangement]) -> Result<u64> {
+    let mut course_code = Vec::with_capacity(arrangements.len());
+    let mut course_name = Vec::with_capacity(arrangements.len());
+    let mut class_name = Vec::with_capacity(arrangements.len());
+    let mut teacher = Vec::with_capacity(arrangements.len());
+    let mut campus = Vec::with_capacity(arrangements.len());
+    let mut room = Vec::with_capacity(arrangements.len());
+    let mut weeks = Vec::with_capacity(arrangements.len());
+    let mut day = Vec::with_capacity(arrangements.len());
+    let mut time_index = Vec::with_capacity(arrangements.len());
+    for a in arrangements {
+        course_code.push(a.course_code.as_str());
+        course_name.push(a.course_name.as_str());
+        class_name.push(a.class_name.as_str());
+        teacher.push(a.teacher.as_str());
+        campus.push(a.campus);
+        room.push(a.room.as_str());
+        weeks.push(a.weeks);
+        day.push(a.day);
+        time_index.push(a.time_index);
+    }
+
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM edu.course_arrangement WHERE year = $1 AND semester = $2;")
+        .bind(year)
+        .bind(semester)
+        .execute(&mut *tx)
+        .await?;
+    let result = sqlx::query(
+        "INSERT INTO edu.course_arrangement
+            (year, semester, course_code, course_name, class_name, teacher, campus, room, weeks, day, time_index)
+            SELECT $1, $2, * FROM UNNEST($3::text[], $4::text[], $5::text[], $6::text[], $7::int[], $8::text[],
+                $9::int[], $10::int[], $11::int[])
+            ON CONFLICT DO NOTHING;",
+    )
+    .bind(year)
+    .bind(semester)
+    .bind(course_code)
+    .bind(course_name)
+    .bind(class_name)
+    .bind(teacher)
+    .bind(campus)
+    .bind(room)
+    .bind(weeks)
+    .bind(day)
+    .bind(time_index)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("SELECT pg_notify($1, $2);")
+        .bind(IMPORT_CHANNEL)
+        .bind(format!("{year}-{semester}"))
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Courses in a room in a week, in the form of (day, time flag, course name).
+pub async fn get_courses_in_room(
+    db: &PgPool,
+    year: i32,
+    semester: i32,
+    room: &str,
+    week: i32,
+) -> Result<Vec<(i32, i32, String)>> {
+    let rows = sqlx::query_as(
+        "SELECT day, time_index, course_name FROM edu.course_arrangement
+            WHERE year = $1 AND semester = $2 AND room = $3 AND (weeks >> $4) & 1 = 1
+            ORDER BY day, time_index;",
+    )
+    .bind(year)
+    .bind(semester)
+    .bind(room)
+    .bind(week)
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_conflicts, parse_campus, parse_floor, parse_room_type, Arrangement, ROOM_TYPE_LAB};
+
+    fn arrangement(class_name: &str, room: &str, weeks: i32, day: i32, time_index: i32) -> Arrangement {
+        Arrangement {
+            course_code: "B1220012".to_string(),
+            course_name: "高等数学".to_string(),
+            class_name: class_name.to_string(),
+            teacher: "王五".to_string(),
+            campus: 1,
+            room: room.to_string(),
+            weeks,
+            day,
+            time_index,
+        }
+    }
+
+    #[test]
+    fn test_parse_campus() {
+        assert_eq!(parse_campus("奉贤校区"), Some(1));
+        assert_eq!(parse_campus("徐汇校区"), Some(2));
+        assert_eq!(parse_campus(""), None);
+    }
+
+    #[test]
+    fn test_parse_room_type() {
+        assert_eq!(parse_room_type("机房"), Some(ROOM_TYPE_LAB));
+        assert_eq!(parse_room_type("操场"), None);
+    }
+
+    #[test]
+    fn test_parse_floor() {
+        assert_eq!(parse_floor("一教A101"), Some(1));
+        assert_eq!(parse_floor("一教A1201"), Some(12));
+        assert_eq!(parse_floor("体育馆"), None);
+        assert_eq!(parse_floor("实验室1"), None);
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let arrangements = vec![
+            arrangement("机械1班", "一教A101", 0b1110, 1, 0b110),
+            // Same class, taught by another teacher.
+            arrangement("机械1班", "一教A101", 0b1110, 1, 0b110),
+            // Overlaps in week 2 and class 2.
+            arrangement("机械2班", "一教A101", 0b0100, 1, 0b1100),
+            // Different week, day or room.
+            arrangement("机械3班", "一教A101", 0b10000, 1, 0b110),
+            arrangement("机械4班", "一教A101", 0b1110, 2, 0b110),
+            arrangement("机械5班", "一教A102", 0b1110, 1, 0b110),
+        ];
+
+        assert_eq!(find_conflicts(&arrangements), vec![(0, 2), (1, 2)]);
+        assert!(find_conflicts(&[]).is_empty());
+    }
+}
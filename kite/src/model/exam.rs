@@ -0,0 +1,91 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::PgPool;
+
+/// `kind` of notifications about upcoming exams.
+pub const NOTIFICATION_KIND: &str = "exam";
+
+/// Exam arrangement, fetched from the academic system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exam {
+    /// 课程名称
+    pub course_name: String,
+    /// 课程代码
+    pub course_code: String,
+    /// 考试名称
+    pub exam_name: String,
+    /// 开始时间, 未安排时为空
+    pub start: Option<NaiveDateTime>,
+    pub end: Option<NaiveDateTime>,
+    /// 考场
+    pub place: String,
+    /// 校区
+    pub campus: String,
+    /// 考场所在教学楼和区域, see `classroom_browser::split_room_title`
+    pub building: Option<String>,
+    pub region: Option<String>,
+    /// 座位号
+    pub seat: String,
+}
+
+pub async fn get(db: &PgPool, uid: i32, year: i32, semester: i32) -> Result<Option<(Vec<Exam>, DateTime<Local>)>> {
+    let row: Option<(Json<Vec<Exam>>, DateTime<Local>)> =
+        sqlx::query_as("SELECT exams, update_time FROM exam_cache WHERE uid = $1 AND year = $2 AND semester = $3;")
+            .bind(uid)
+            .bind(year)
+            .bind(semester)
+            .fetch_optional(db)
+            .await?;
+
+    Ok(row.map(|(exams, update_time)| (exams.0, update_time)))
+}
+
+pub async fn save(db: &PgPool, uid: i32, year: i32, semester: i32, exams: &[Exam]) -> Result<DateTime<Local>> {
+    let (update_time,): (DateTime<Local>,) = sqlx::query_as(
+        "INSERT INTO exam_cache (uid, year, semester, exams) VALUES ($1, $2, $3, $4) \
+        ON CONFLICT (uid, year, semester) DO UPDATE SET exams = $4, update_time = current_timestamp \
+        RETURNING update_time;",
+    )
+    .bind(uid)
+    .bind(year)
+    .bind(semester)
+    .bind(Json(exams))
+    .fetch_one(db)
+    .await?;
+
+    Ok(update_time)
+}
+
+/// Exams of all users which start between `from` and `to`, in local time like `Exam::start`.
+pub async fn get_upcoming(db: &PgPool, from: NaiveDateTime, to: NaiveDateTime) -> Result<Vec<(i32, Exam)>> {
+    let rows: Vec<(i32, Json<Exam>)> = sqlx::query_as(
+        "SELECT uid, e FROM exam_cache, jsonb_array_elements(exams) AS e \
+        WHERE (e->>'start')::timestamp BETWEEN $1 AND $2;",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows.into_iter().map(|(uid, exam)| (uid, exam.0)).collect())
+}
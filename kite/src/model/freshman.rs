@@ -0,0 +1,299 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Freshman data for the welcome page, which is read by every freshman in September, so most queries are cached.
+
+use std::num::NonZeroU32;
+
+use anyhow::{anyhow, Result};
+use bincode::{Decode, Encode};
+use chrono::{DateTime, Local};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use sqlx::PgPool;
+
+use crate as kite;
+
+/// A freshman, without the credential.
+#[derive(Debug, Clone, Encode, Decode, sqlx::FromRow)]
+pub struct Freshman {
+    pub fid: i32,
+    pub entrance_year: i32,
+    /// 学号
+    pub student_id: String,
+    pub name: String,
+    /// 0 = male, 1 = female
+    pub gender: i32,
+    pub college: String,
+    pub major: String,
+    /// 行政班
+    pub class_name: String,
+    pub campus: String,
+    /// 宿舍楼, 寝室号和床号
+    pub building: String,
+    pub room: i32,
+    pub bed_index: i32,
+    pub province: Option<String>,
+    pub city: Option<String>,
+    pub high_school: Option<String>,
+    /// Visible in "people may know" of others
+    pub visible: bool,
+    /// 联系方式, JSON string
+    pub contact: Option<String>,
+    #[bincode(with_serde)]
+    pub last_seen: Option<DateTime<Local>>,
+}
+
+/// Statistics of the freshmen in the same year as someone.
+#[derive(Debug, Clone, Encode, Decode, sqlx::FromRow)]
+pub struct Analysis {
+    /// Others with the same name
+    pub same_name: i64,
+    /// Others from the same city
+    pub same_city: i64,
+    /// Others from the same high school
+    pub same_high_school: i64,
+    /// Freshmen in the same college, including oneself
+    pub college_count: i64,
+}
+
+const FRESHMAN_COLUMNS: &str = "s.fid, s.entrance_year, s.student_id, s.name, s.gender, s.college, s.major, \
+    s.class_name, s.campus, s.building, s.room, s.bed_index, s.province, s.city, s.high_school, s.visible, \
+    s.contact, s.last_seen";
+
+/// Max number of people returned by `get_people_may_know`.
+const PEOPLE_MAY_KNOW_LIMIT: i32 = 30;
+
+/// Secrets are hashed by PBKDF2-HMAC-SHA256, in the form of "pbkdf2-sha256$iterations$salt$hash" in hex.
+const SECRET_HASH_SCHEME: &str = "pbkdf2-sha256";
+const SECRET_HASH_ITERATIONS: u32 = 10_000;
+const SECRET_SALT_LEN: usize = 16;
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    let pairs = s.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+    pairs
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Hash a secret with a random salt.
+pub fn hash_secret(secret: &str) -> Result<String> {
+    let mut salt = [0u8; SECRET_SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| anyhow!("Failed to generate salt"))?;
+    let mut hash = [0u8; ring::digest::SHA256_OUTPUT_LEN];
+    let iterations = NonZeroU32::new(SECRET_HASH_ITERATIONS).unwrap();
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        &salt,
+        secret.as_bytes(),
+        &mut hash,
+    );
+
+    Ok(format!(
+        "{SECRET_HASH_SCHEME}${SECRET_HASH_ITERATIONS}${}${}",
+        to_hex(&salt),
+        to_hex(&hash)
+    ))
+}
+
+/// Check a secret against the hash by `hash_secret` in constant time. Malformed hashes never match.
+pub fn verify_secret(secret: &str, hash: &str) -> bool {
+    let verify = || -> Option<bool> {
+        let mut parts = hash.split('$');
+        if parts.next()? != SECRET_HASH_SCHEME {
+            return None;
+        }
+        let iterations = NonZeroU32::new(parts.next()?.parse().ok()?)?;
+        let salt = from_hex(parts.next()?)?;
+        let expected = from_hex(parts.next()?)?;
+        let result = pbkdf2::verify(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &salt,
+            secret.as_bytes(),
+            &expected,
+        );
+        Some(result.is_ok())
+    };
+    verify().unwrap_or(false)
+}
+
+/// Check the credential, and return fid and entrance year of the freshman. `account` is admission ID or student ID, and the
+/// latest year is taken if `entrance_year` is not given.
+pub async fn authenticate(
+    db: &PgPool,
+    account: &str,
+    secret: &str,
+    entrance_year: Option<i32>,
+) -> Result<Option<(i32, i32)>> {
+    let candidates: Vec<(i32, i32, String)> = sqlx::query_as(
+        "SELECT fid, entrance_year, secret_hash FROM freshman
+            WHERE (admission_id = $1 OR student_id = $1) AND secret_hash IS NOT NULL
+                AND ($2::int IS NULL OR entrance_year = $2)
+            ORDER BY entrance_year DESC;",
+    )
+    .bind(account)
+    .bind(entrance_year)
+    .fetch_all(db)
+    .await?;
+
+    // PBKDF2 takes a while, so it's kept off the async workers.
+    let secret = secret.to_string();
+    let freshman = tokio::task::spawn_blocking(move || {
+        candidates
+            .into_iter()
+            .find(|(_, _, hash)| verify_secret(&secret, hash))
+            .map(|(fid, year, _)| (fid, year))
+    })
+    .await?;
+    Ok(freshman)
+}
+
+/// Hash secrets loaded in plain text, and clear them. Return the number of secrets hashed.
+pub async fn hash_plain_secrets(db: &PgPool) -> Result<u64> {
+    let rows: Vec<(i32, String)> = sqlx::query_as("SELECT fid, secret FROM freshman WHERE secret IS NOT NULL;")
+        .fetch_all(db)
+        .await?;
+
+    let mut fid = Vec::with_capacity(rows.len());
+    let mut secret_hash = Vec::with_capacity(rows.len());
+    for (id, secret) in &rows {
+        fid.push(*id);
+        secret_hash.push(hash_secret(secret)?);
+    }
+    let result = sqlx::query(
+        "UPDATE freshman SET secret_hash = t.secret_hash, secret = NULL
+            FROM UNNEST($1::int[], $2::text[]) AS t (fid, secret_hash)
+            WHERE freshman.fid = t.fid;",
+    )
+    .bind(fid)
+    .bind(secret_hash)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn get(db: &PgPool, fid: i32) -> Result<Option<Freshman>> {
+    sqlx::query_as(&format!("SELECT {FRESHMAN_COLUMNS} FROM freshman s WHERE s.fid = $1;"))
+        .bind(fid)
+        .fetch_optional(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Update the last seen time. It's written at most once in 5 minutes to reduce writes in the rush, and the
+/// caller is expected to throttle it too.
+pub async fn touch(db: &PgPool, fid: i32) -> Result<()> {
+    sqlx::query(
+        "UPDATE freshman SET last_seen = current_timestamp
+            WHERE fid = $1 AND (last_seen IS NULL OR last_seen < current_timestamp - interval '5 minutes');",
+    )
+    .bind(fid)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Others in the same year as the freshman, who meet the `condition`. `tail` is appended to the query for
+/// ordering and limit.
+async fn query_related(db: &PgPool, fid: i32, condition: &str, tail: &str) -> Result<Vec<Freshman>> {
+    sqlx::query_as(&format!(
+        "SELECT {FRESHMAN_COLUMNS} FROM freshman me
+            JOIN freshman s ON s.entrance_year = me.entrance_year AND s.fid <> me.fid AND ({condition})
+            WHERE me.fid = $1
+            {tail};"
+    ))
+    .bind(fid)
+    .fetch_all(db)
+    .await
+    .map_err(Into::into)
+}
+
+#[crate::cache_result(timeout = 600)]
+pub async fn get_roommates(db: &PgPool, fid: i32) -> Result<Vec<Freshman>> {
+    let condition = "s.campus = me.campus AND s.building = me.building AND s.room = me.room";
+    query_related(db, fid, condition, "ORDER BY s.bed_index").await
+}
+
+#[crate::cache_result(timeout = 600)]
+pub async fn get_classmates(db: &PgPool, fid: i32) -> Result<Vec<Freshman>> {
+    query_related(db, fid, "s.class_name = me.class_name", "ORDER BY s.student_id").await
+}
+
+/// Visible freshmen from the same high school or city, and the former come first.
+#[crate::cache_result(timeout = 600)]
+pub async fn get_people_may_know(db: &PgPool, fid: i32) -> Result<Vec<Freshman>> {
+    let condition = "s.visible AND (s.high_school = me.high_school OR s.city = me.city)";
+    let tail = format!(
+        "ORDER BY (s.high_school = me.high_school) IS TRUE DESC, s.last_seen DESC NULLS LAST \
+        LIMIT {PEOPLE_MAY_KNOW_LIMIT}"
+    );
+    query_related(db, fid, condition, &tail).await
+}
+
+#[crate::cache_result(timeout = 3600)]
+pub async fn get_analysis(db: &PgPool, fid: i32) -> Result<Option<Analysis>> {
+    sqlx::query_as(
+        "SELECT
+                (SELECT count(*) FROM freshman s
+                    WHERE s.entrance_year = me.entrance_year AND s.name = me.name AND s.fid <> me.fid) AS same_name,
+                (SELECT count(*) FROM freshman s
+                    WHERE s.entrance_year = me.entrance_year AND s.city = me.city AND s.fid <> me.fid) AS same_city,
+                (SELECT count(*) FROM freshman s
+                    WHERE s.entrance_year = me.entrance_year AND s.high_school = me.high_school AND s.fid <> me.fid)
+                    AS same_high_school,
+                (SELECT count(*) FROM freshman s
+                    WHERE s.entrance_year = me.entrance_year AND s.college = me.college) AS college_count
+            FROM freshman me WHERE me.fid = $1;",
+    )
+    .bind(fid)
+    .fetch_optional(db)
+    .await
+    .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{hash_secret, verify_secret};
+
+    #[test]
+    fn test_secret_hash() {
+        let hash = hash_secret("123456").unwrap();
+
+        assert!(hash.starts_with("pbkdf2-sha256$10000$"));
+        assert!(verify_secret("123456", &hash));
+        assert!(!verify_secret("123457", &hash));
+        // Salted, so the same secret is hashed differently.
+        assert_ne!(hash, hash_secret("123456").unwrap());
+
+        assert!(!verify_secret("123456", "123456"));
+        assert!(!verify_secret("123456", "pbkdf2-sha256$0$00$00"));
+        assert!(!verify_secret("123456", "pbkdf2-sha256$10000$0g$00"));
+    }
+}
@@ -0,0 +1,100 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use bincode::{Decode, Encode};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::PgPool;
+
+/// `kind` of notifications about books due soon.
+pub const NOTIFICATION_KIND: &str = "library";
+
+/// Book borrowed, fetched from the library system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Loan {
+    /// 条码号
+    pub barcode: String,
+    /// 题名
+    pub title: String,
+    /// 责任者
+    pub author: String,
+    /// 索书号
+    pub call_number: String,
+    /// 馆藏地点
+    pub location: String,
+    pub loan_date: NaiveDate,
+    pub due_date: NaiveDate,
+    /// 已续借次数
+    pub renew_count: i32,
+}
+
+/// Book in the catalog. Search results are cached.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct Book {
+    /// 书目记录号
+    pub book_id: String,
+    pub title: String,
+    pub author: String,
+    pub publisher: String,
+    pub publish_date: String,
+    pub call_number: String,
+    /// 馆藏复本数
+    pub copy_count: i32,
+    /// 可借复本数
+    pub available_count: i32,
+}
+
+pub async fn get(db: &PgPool, uid: i32) -> Result<Option<(Vec<Loan>, DateTime<Local>)>> {
+    let row: Option<(Json<Vec<Loan>>, DateTime<Local>)> =
+        sqlx::query_as("SELECT loans, update_time FROM library_loan_cache WHERE uid = $1;")
+            .bind(uid)
+            .fetch_optional(db)
+            .await?;
+
+    Ok(row.map(|(loans, update_time)| (loans.0, update_time)))
+}
+
+pub async fn save(db: &PgPool, uid: i32, loans: &[Loan]) -> Result<DateTime<Local>> {
+    let (update_time,): (DateTime<Local>,) = sqlx::query_as(
+        "INSERT INTO library_loan_cache (uid, loans) VALUES ($1, $2) \
+        ON CONFLICT (uid) DO UPDATE SET loans = $2, update_time = current_timestamp \
+        RETURNING update_time;",
+    )
+    .bind(uid)
+    .bind(Json(loans))
+    .fetch_one(db)
+    .await?;
+
+    Ok(update_time)
+}
+
+/// Loans of all users which are due between `from` and `to`, both included.
+pub async fn get_due_soon(db: &PgPool, from: NaiveDate, to: NaiveDate) -> Result<Vec<(i32, Loan)>> {
+    let rows: Vec<(i32, Json<Loan>)> = sqlx::query_as(
+        "SELECT uid, l FROM library_loan_cache, jsonb_array_elements(loans) AS l \
+        WHERE (l->>'due_date')::date BETWEEN $1 AND $2;",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows.into_iter().map(|(uid, loan)| (uid, loan.0)).collect())
+}
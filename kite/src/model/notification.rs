@@ -0,0 +1,95 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use sqlx::{FromRow, PgExecutor, PgPool};
+
+/// Message for a user, produced by background jobs.
+#[derive(Debug, Clone, FromRow)]
+pub struct Notification {
+    pub id: i32,
+    pub uid: i32,
+    /// Feature the message belongs to, like "score"
+    pub kind: String,
+    pub title: String,
+    pub content: String,
+    pub create_time: DateTime<Local>,
+}
+
+pub async fn create(db: impl PgExecutor<'_>, uid: i32, kind: &str, title: &str, content: &str) -> Result<()> {
+    sqlx::query("INSERT INTO notification (uid, kind, title, content) VALUES ($1, $2, $3, $4);")
+        .bind(uid)
+        .bind(kind)
+        .bind(title)
+        .bind(content)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Create a notification unless the one with the same `key` exists, so that a job doesn't repeat it.
+pub async fn create_once(
+    db: impl PgExecutor<'_>,
+    uid: i32,
+    kind: &str,
+    key: &str,
+    title: &str,
+    content: &str,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO notification (uid, kind, key, title, content) VALUES ($1, $2, $3, $4, $5) \
+        ON CONFLICT (uid, kind, key) DO NOTHING;",
+    )
+    .bind(uid)
+    .bind(kind)
+    .bind(key)
+    .bind(title)
+    .bind(content)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Get recent notifications of `kind`, the latest first.
+pub async fn list(db: &PgPool, uid: i32, kind: &str, limit: i64) -> Result<Vec<Notification>> {
+    sqlx::query_as(
+        "SELECT id, uid, kind, title, content, create_time FROM notification \
+        WHERE uid = $1 AND kind = $2 ORDER BY create_time DESC LIMIT $3;",
+    )
+    .bind(uid)
+    .bind(kind)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+    .map_err(Into::into)
+}
+
+/// Get notifications of `kind` created after the one with id `after`, the earliest first.
+pub async fn list_after(db: &PgPool, uid: i32, kind: &str, after: i32) -> Result<Vec<Notification>> {
+    sqlx::query_as(
+        "SELECT id, uid, kind, title, content, create_time FROM notification \
+        WHERE uid = $1 AND kind = $2 AND id > $3 ORDER BY id;",
+    )
+    .bind(uid)
+    .bind(kind)
+    .bind(after)
+    .fetch_all(db)
+    .await
+    .map_err(Into::into)
+}
@@ -0,0 +1,246 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+
+/// `kind` of notifications about new grades.
+pub const NOTIFICATION_KIND: &str = "score";
+
+/// Grade of a course, fetched from the academic system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    /// 课程名称
+    pub course_name: String,
+    /// 课程代码
+    pub course_code: String,
+    /// 教学班名称
+    pub class_name: String,
+    /// 学分
+    pub credit: f32,
+    /// 成绩, 分数或 "优秀" 等等级
+    pub score: String,
+    /// 绩点. 只记 "合格" 与否的课程不计绩点
+    pub grade_point: Option<f32>,
+    /// 课程性质
+    pub course_type: String,
+    /// 考试性质, 如 "正常考试", "补考", "重修"
+    pub exam_type: String,
+}
+
+/// Grade point of a score, by the rule of the school: (score - 50) / 10 if passed, and 0 otherwise.
+///
+/// Levels are converted to the middle of their range, and pass / fail courses are not counted.
+pub fn grade_point(score: &str) -> Option<f32> {
+    let score = match score.trim() {
+        "优秀" => 95.0,
+        "良好" => 85.0,
+        "中等" => 75.0,
+        "及格" => 65.0,
+        "不及格" => 0.0,
+        s => s.parse::<f32>().ok()?,
+    };
+    if score >= 60.0 {
+        Some(((score - 50.0) / 10.0).min(5.0))
+    } else {
+        Some(0.0)
+    }
+}
+
+/// Average grade point weighted by credit. If a course is taken more than once, such as 补考 and 重修,
+/// the best one is counted.
+pub fn gpa(scores: &[Score]) -> Option<f32> {
+    let mut best: HashMap<&str, (f32, f32)> = HashMap::new();
+
+    for score in scores {
+        let Some(point) = score.grade_point else {
+            continue;
+        };
+        let entry = best.entry(&score.course_code).or_insert((point, score.credit));
+        if point > entry.0 {
+            *entry = (point, score.credit);
+        }
+    }
+    let total_credit: f32 = best.values().map(|(_, credit)| credit).sum();
+    if total_credit <= 0.0 {
+        return None;
+    }
+    let total_point: f32 = best.values().map(|(point, credit)| point * credit).sum();
+    Some(total_point / total_credit)
+}
+
+/// Scores in `new` which are absent or different in `old`.
+pub fn diff<'a>(old: &[Score], new: &'a [Score]) -> Vec<&'a Score> {
+    let key = |s: &Score| (s.course_code.clone(), s.class_name.clone(), s.exam_type.clone());
+    let old: HashMap<_, _> = old.iter().map(|s| (key(s), s.score.as_str())).collect();
+
+    new.iter()
+        .filter(|s| old.get(&key(s)) != Some(&s.score.as_str()))
+        .collect()
+}
+
+#[derive(Debug, FromRow)]
+pub struct Snapshot {
+    pub id: i32,
+    pub uid: i32,
+    pub year: i32,
+    pub semester: i32,
+    pub scores: Json<Vec<Score>>,
+    pub update_time: DateTime<Local>,
+}
+
+const SNAPSHOT_COLUMNS: &str = "id, uid, year, semester, scores, update_time";
+
+pub async fn get_latest(db: &PgPool, uid: i32, year: i32, semester: i32) -> Result<Option<Snapshot>> {
+    sqlx::query_as(&format!(
+        "SELECT {SNAPSHOT_COLUMNS} FROM score_snapshot WHERE uid = $1 AND year = $2 AND semester = $3 \
+        ORDER BY id DESC LIMIT 1;"
+    ))
+    .bind(uid)
+    .bind(year)
+    .bind(semester)
+    .fetch_optional(db)
+    .await
+    .map_err(Into::into)
+}
+
+/// Save a snapshot if the scores differ from the last one, and return the update time.
+pub async fn save(db: &PgPool, uid: i32, year: i32, semester: i32, scores: &[Score]) -> Result<DateTime<Local>> {
+    if let Some(latest) = get_latest(db, uid, year, semester).await? {
+        if latest.scores.0 == scores {
+            let (update_time,): (DateTime<Local>,) = sqlx::query_as(
+                "UPDATE score_snapshot SET update_time = current_timestamp WHERE id = $1 RETURNING update_time;",
+            )
+            .bind(latest.id)
+            .fetch_one(db)
+            .await?;
+            return Ok(update_time);
+        }
+    }
+    let (update_time,): (DateTime<Local>,) = sqlx::query_as(
+        "INSERT INTO score_snapshot (uid, year, semester, scores) VALUES ($1, $2, $3, $4) RETURNING update_time;",
+    )
+    .bind(uid)
+    .bind(year)
+    .bind(semester)
+    .bind(Json(scores))
+    .fetch_one(db)
+    .await?;
+    Ok(update_time)
+}
+
+/// Snapshots not compared with the previous ones yet, in the order of creation.
+pub async fn get_unprocessed(db: &PgPool, limit: i64) -> Result<Vec<Snapshot>> {
+    sqlx::query_as(&format!(
+        "SELECT {SNAPSHOT_COLUMNS} FROM score_snapshot WHERE NOT processed ORDER BY id LIMIT $1;"
+    ))
+    .bind(limit)
+    .fetch_all(db)
+    .await
+    .map_err(Into::into)
+}
+
+pub async fn get_previous(db: &PgPool, snapshot: &Snapshot) -> Result<Option<Snapshot>> {
+    sqlx::query_as(&format!(
+        "SELECT {SNAPSHOT_COLUMNS} FROM score_snapshot WHERE uid = $1 AND year = $2 AND semester = $3 AND id < $4 \
+        ORDER BY id DESC LIMIT 1;"
+    ))
+    .bind(snapshot.uid)
+    .bind(snapshot.year)
+    .bind(snapshot.semester)
+    .bind(snapshot.id)
+    .fetch_optional(db)
+    .await
+    .map_err(Into::into)
+}
+
+/// The latest term of each user whose campus sessions are saved, which is checked for new grades.
+pub async fn get_terms_to_check(db: &PgPool) -> Result<Vec<(i32, i32, i32)>> {
+    sqlx::query_as(
+        "SELECT DISTINCT ON (uid) uid, year, semester FROM score_snapshot \
+        WHERE uid IN (SELECT uid FROM campus_session) ORDER BY uid, year DESC, semester DESC;",
+    )
+    .fetch_all(db)
+    .await
+    .map_err(Into::into)
+}
+
+pub async fn set_processed(db: impl sqlx::PgExecutor<'_>, id: i32) -> Result<()> {
+    sqlx::query("UPDATE score_snapshot SET processed = TRUE WHERE id = $1;")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn score(code: &str, score: &str, credit: f32, exam_type: &str) -> Score {
+        Score {
+            course_name: code.to_string(),
+            course_code: code.to_string(),
+            class_name: String::new(),
+            credit,
+            score: score.to_string(),
+            grade_point: grade_point(score),
+            course_type: "必修".to_string(),
+            exam_type: exam_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_grade_point() {
+        assert_eq!(grade_point("95"), Some(4.5));
+        assert_eq!(grade_point("60"), Some(1.0));
+        assert_eq!(grade_point("59.5"), Some(0.0));
+        assert_eq!(grade_point("优秀"), Some(4.5));
+        assert_eq!(grade_point("合格"), None);
+    }
+
+    #[test]
+    fn test_gpa() {
+        let scores = vec![
+            score("A", "95", 4.0, "正常考试"),
+            score("B", "45", 2.0, "正常考试"),
+            score("B", "70", 2.0, "补考"),
+            score("C", "合格", 1.0, "正常考试"),
+        ];
+        // (4.5 * 4 + 2.0 * 2) / 6
+        assert!((gpa(&scores).unwrap() - 22.0 / 6.0).abs() < 1e-5);
+        assert_eq!(gpa(&scores[3..]), None);
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = vec![score("A", "95", 4.0, "正常考试"), score("B", "45", 2.0, "正常考试")];
+        let new = vec![
+            score("A", "95", 4.0, "正常考试"),
+            score("B", "50", 2.0, "正常考试"),
+            score("B", "70", 2.0, "补考"),
+        ];
+        let changed: Vec<_> = diff(&old, &new).into_iter().map(|s| s.score.as_str()).collect();
+        assert_eq!(changed, vec!["50", "70"]);
+    }
+}
@@ -0,0 +1,134 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::PgPool;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryScore {
+    /// 分类, 如 "主题报告"
+    pub category: String,
+    pub score: f32,
+    /// 毕业要求分数
+    pub required: f32,
+}
+
+/// Activity the user joined.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Activity {
+    pub activity_id: String,
+    pub title: String,
+    pub category: String,
+    pub score: f32,
+    pub time: Option<NaiveDateTime>,
+    /// 状态, 如 "已加分"
+    pub status: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpcomingActivity {
+    pub activity_id: String,
+    pub title: String,
+    pub category: String,
+    pub start: Option<NaiveDateTime>,
+    pub place: String,
+}
+
+/// Everything fetched from the second classroom (第二课堂) system at a time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecondClass {
+    pub scores: Vec<CategoryScore>,
+    pub activities: Vec<Activity>,
+    pub upcoming: Vec<UpcomingActivity>,
+}
+
+impl SecondClass {
+    pub fn total(&self) -> f32 {
+        self.scores.iter().map(|s| s.score).sum()
+    }
+
+    /// Upcoming activities which haven't started at `now`. Those without start time are kept.
+    pub fn upcoming_after(&self, now: NaiveDateTime) -> Vec<UpcomingActivity> {
+        self.upcoming
+            .iter()
+            .filter(|a| match a.start {
+                Some(start) => start > now,
+                None => true,
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+pub async fn get(db: &PgPool, uid: i32) -> Result<Option<(SecondClass, DateTime<Local>)>> {
+    let row: Option<(Json<SecondClass>, DateTime<Local>)> =
+        sqlx::query_as("SELECT data, update_time FROM second_class_cache WHERE uid = $1;")
+            .bind(uid)
+            .fetch_optional(db)
+            .await?;
+
+    Ok(row.map(|(data, update_time)| (data.0, update_time)))
+}
+
+pub async fn save(db: &PgPool, uid: i32, data: &SecondClass) -> Result<DateTime<Local>> {
+    let (update_time,): (DateTime<Local>,) = sqlx::query_as(
+        "INSERT INTO second_class_cache (uid, data) VALUES ($1, $2) \
+        ON CONFLICT (uid) DO UPDATE SET data = $2, update_time = current_timestamp \
+        RETURNING update_time;",
+    )
+    .bind(uid)
+    .bind(Json(data))
+    .fetch_one(db)
+    .await?;
+
+    Ok(update_time)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn upcoming(title: &str, start: Option<&str>) -> UpcomingActivity {
+        UpcomingActivity {
+            activity_id: title.to_string(),
+            title: title.to_string(),
+            category: "主题报告".to_string(),
+            start: start.map(|s| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()),
+            place: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_upcoming_after() {
+        let data = SecondClass {
+            upcoming: vec![
+                upcoming("A", Some("2023-03-10 14:00")),
+                upcoming("B", Some("2023-03-12 09:00")),
+                upcoming("C", None),
+            ],
+            ..Default::default()
+        };
+        let now = NaiveDateTime::parse_from_str("2023-03-11 00:00", "%Y-%m-%d %H:%M").unwrap();
+        let titles: Vec<_> = data.upcoming_after(now).into_iter().map(|a| a.title).collect();
+
+        assert_eq!(titles, vec!["B", "C"]);
+    }
+}
@@ -0,0 +1,169 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::PgPool;
+
+/// A course in the timetable, fetched from the academic system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Course {
+    /// 课程名称
+    pub course_name: String,
+    /// 课程代码
+    pub course_code: String,
+    /// 教学班名称
+    pub class_name: String,
+    /// 任课教师
+    pub teachers: Vec<String>,
+    /// 上课地点, 如 "一教A101"
+    pub place: String,
+    /// 校区
+    pub campus: String,
+    /// Week flag, the n-th bit is set if there is class in the n-th week.
+    pub weeks: i32,
+    /// Day index in a week, 1 - 7
+    pub day: i32,
+    /// Course time flag, in the same format as `classroom_browser::convert_range_string_to_binary`.
+    pub time_index: i32,
+    /// 学分
+    pub credit: f32,
+}
+
+/// Begin and end time of class 1 - 11.
+pub const CLASS_TIME: [(NaiveTime, NaiveTime); 11] = {
+    const fn t(h: u32, m: u32) -> NaiveTime {
+        match NaiveTime::from_hms_opt(h, m, 0) {
+            Some(time) => time,
+            None => panic!("Invalid time"),
+        }
+    }
+    [
+        (t(8, 20), t(9, 5)),
+        (t(9, 10), t(9, 55)),
+        (t(10, 15), t(11, 0)),
+        (t(11, 5), t(11, 50)),
+        (t(13, 0), t(13, 45)),
+        (t(13, 50), t(14, 35)),
+        (t(14, 55), t(15, 40)),
+        (t(15, 45), t(16, 30)),
+        (t(18, 0), t(18, 45)),
+        (t(18, 50), t(19, 35)),
+        (t(19, 40), t(20, 25)),
+    ]
+};
+
+/// Split course time flag into continuous ranges of class index, like [(1, 2), (5, 6)] for 1-2,5-6.
+pub fn split_time_index(time_index: i32) -> Vec<(i32, i32)> {
+    let mut result = Vec::new();
+    let mut begin = None;
+
+    for i in 1..=12 {
+        let set = i <= 11 && time_index & (1 << i) != 0;
+        match (set, begin) {
+            (true, None) => begin = Some(i),
+            (false, Some(b)) => {
+                result.push((b, i - 1));
+                begin = None;
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Convert week string like "1-8周,10-16周(双)" or "3周" to week flag.
+pub fn parse_weeks(s: &str) -> i32 {
+    let mut result = 0;
+
+    for part in s.split(',') {
+        let (part, step_filter): (&str, fn(i32) -> bool) = if let Some(p) = part.strip_suffix("(单)") {
+            (p, |w| w % 2 == 1)
+        } else if let Some(p) = part.strip_suffix("(双)") {
+            (p, |w| w % 2 == 0)
+        } else {
+            (part, |_| true)
+        };
+        let part = part.trim().trim_end_matches('周');
+        let (begin, end) = part.split_once('-').unwrap_or((part, part));
+        let (Ok(begin), Ok(end)) = (begin.parse::<i32>(), end.parse::<i32>()) else {
+            continue;
+        };
+        // Weeks are in 1..=30, so that the flag fits in i32.
+        for week in begin.max(1)..=end.min(30) {
+            if step_filter(week) {
+                result |= 1 << week;
+            }
+        }
+    }
+    result
+}
+
+/// Get cached timetable and the time it's fetched.
+pub async fn get(db: &PgPool, uid: i32, year: i32, semester: i32) -> Result<Option<(Vec<Course>, DateTime<Local>)>> {
+    let row: Option<(Json<Vec<Course>>, DateTime<Local>)> = sqlx::query_as(
+        "SELECT courses, update_time FROM timetable_cache WHERE uid = $1 AND year = $2 AND semester = $3;",
+    )
+    .bind(uid)
+    .bind(year)
+    .bind(semester)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.map(|(courses, update_time)| (courses.0, update_time)))
+}
+
+pub async fn save(db: &PgPool, uid: i32, year: i32, semester: i32, courses: &[Course]) -> Result<DateTime<Local>> {
+    let (update_time,): (DateTime<Local>,) = sqlx::query_as(
+        "INSERT INTO timetable_cache (uid, year, semester, courses) VALUES ($1, $2, $3, $4) \
+        ON CONFLICT (uid, year, semester) DO UPDATE SET courses = $4, update_time = current_timestamp \
+        RETURNING update_time;",
+    )
+    .bind(uid)
+    .bind(year)
+    .bind(semester)
+    .bind(Json(courses))
+    .fetch_one(db)
+    .await?;
+
+    Ok(update_time)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_weeks, split_time_index};
+
+    #[test]
+    fn test_parse_weeks() {
+        assert_eq!(parse_weeks("1-3周"), 0b1110);
+        assert_eq!(parse_weeks("3周"), 0b1000);
+        assert_eq!(parse_weeks("1-5周(单)"), 0b101010);
+        assert_eq!(parse_weeks("1-4周(双),6周"), 0b1010100);
+        assert_eq!(parse_weeks(""), 0);
+    }
+
+    #[test]
+    fn test_split_time_index() {
+        assert_eq!(split_time_index(6), vec![(1, 2)]);
+        assert_eq!(split_time_index(102), vec![(1, 2), (5, 6)]);
+        assert_eq!(split_time_index(4094), vec![(1, 11)]);
+        assert_eq!(split_time_index(0), vec![]);
+    }
+}
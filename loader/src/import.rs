@@ -0,0 +1,321 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Import the course arrangement of a term from the export (CSV or XLSX) of the academic system.
+//!
+//! Usage: `loader import <year> <semester> <file> [--dry-run]`
+//!
+//! Courses in rooms not in `edu.classroom` are skipped, so import the room list by `loader import-room` first.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use calamine::Reader;
+
+use kite::model::classroom_browser::{convert_range_string_to_binary, split_room_title};
+use kite::model::edu::{self, Arrangement};
+use kite::model::timetable::parse_weeks;
+
+pub mod building;
+pub mod room;
+
+/// Column names in the export, the first matched is taken.
+const COURSE_CODE: &[&str] = &["课程代码", "课程号"];
+const COURSE_NAME: &[&str] = &["课程名称"];
+const CLASS_NAME: &[&str] = &["教学班名称", "教学班"];
+const TEACHER: &[&str] = &["上课教师", "教师"];
+const CAMPUS: &[&str] = &["校区"];
+const ROOM: &[&str] = &["上课地点", "教室", "场地名称"];
+const WEEKS: &[&str] = &["周次"];
+const DAY: &[&str] = &["星期"];
+const TIME: &[&str] = &["节次"];
+
+/// Row number (from 1, including the header) and reason of a row which is not imported.
+type RowError = (usize, String);
+
+/// Result of parsing the export.
+#[derive(Debug, Default)]
+struct ParseResult {
+    arrangements: Vec<Arrangement>,
+    errors: Vec<RowError>,
+    /// Rows without a room, like online courses.
+    no_room: usize,
+}
+
+fn read_csv<R: Read>(reader: R) -> Result<Vec<Vec<String>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+
+    reader
+        .records()
+        .map(|record| Ok(record?.iter().map(ToString::to_string).collect()))
+        .collect()
+}
+
+fn read_xlsx(path: &Path) -> Result<Vec<Vec<String>>> {
+    let mut workbook = calamine::open_workbook_auto(path)?;
+    let sheet = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| anyhow!("No worksheet in {}", path.display()))??;
+
+    Ok(sheet
+        .rows()
+        .map(|row| row.iter().map(ToString::to_string).collect())
+        .collect())
+}
+
+/// Read rows from the export by its extension.
+fn read_table(path: &Path) -> Result<Vec<Vec<String>>> {
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or_default();
+    match extension.to_lowercase().as_str() {
+        "csv" => read_csv(std::fs::File::open(path)?),
+        "xlsx" | "xls" => read_xlsx(path),
+        _ => bail!("Unsupported file type: {}, CSV or XLSX expected.", path.display()),
+    }
+}
+
+/// Day index by "1" or "星期一".
+fn parse_day(s: &str) -> Option<i32> {
+    let s = s.trim().trim_start_matches("星期").trim_start_matches('周');
+    let day = match s {
+        "一" => 1,
+        "二" => 2,
+        "三" => 3,
+        "四" => 4,
+        "五" => 5,
+        "六" => 6,
+        "日" | "天" => 7,
+        _ => s.parse().ok()?,
+    };
+    (1..=7).contains(&day).then_some(day)
+}
+
+fn parse_row(cell: &dyn Fn(&[&str]) -> String) -> Result<Option<Arrangement>, String> {
+    let room = cell(ROOM);
+    if room.is_empty() {
+        return Ok(None);
+    }
+    if split_room_title(&room).is_none() {
+        return Err(format!("invalid room {room:?}"));
+    }
+    let campus = cell(CAMPUS);
+    let campus = edu::parse_campus(&campus).ok_or_else(|| format!("unknown campus {campus:?}"))?;
+    let day = cell(DAY);
+    let day = parse_day(&day).ok_or_else(|| format!("invalid day {day:?}"))?;
+    let weeks = parse_weeks(&cell(WEEKS));
+    if weeks == 0 {
+        return Err(format!("invalid weeks {:?}", cell(WEEKS)));
+    }
+    let time_index = convert_range_string_to_binary(cell(TIME).trim_end_matches('节'));
+    if time_index == 0 {
+        return Err(format!("invalid class time {:?}", cell(TIME)));
+    }
+
+    Ok(Some(Arrangement {
+        course_code: cell(COURSE_CODE),
+        course_name: cell(COURSE_NAME),
+        class_name: cell(CLASS_NAME),
+        teacher: cell(TEACHER),
+        campus,
+        room,
+        weeks,
+        day,
+        time_index,
+    }))
+}
+
+fn find_column(header: &[String], names: &[&str]) -> Option<usize> {
+    names
+        .iter()
+        .find_map(|name| header.iter().position(|h| h.trim() == *name))
+}
+
+/// Split the header from rows, and make sure the `required` columns exist.
+fn split_header<'a>(rows: &'a [Vec<String>], required: &[&[&str]]) -> Result<(&'a [String], &'a [Vec<String>])> {
+    let (header, rows) = rows.split_first().ok_or_else(|| anyhow!("The file is empty."))?;
+    for names in required {
+        if find_column(header, names).is_none() {
+            bail!("Column {} is missing.", names[0]);
+        }
+    }
+    Ok((header, rows))
+}
+
+/// Trimmed cell in the column, or an empty string if the column is missing.
+fn get_cell(header: &[String], row: &[String], names: &[&str]) -> String {
+    find_column(header, names)
+        .and_then(|index| row.get(index))
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn is_empty_row(row: &[String]) -> bool {
+    row.iter().all(|cell| cell.trim().is_empty())
+}
+
+fn parse_table(rows: &[Vec<String>]) -> Result<ParseResult> {
+    let (header, rows) = split_header(rows, &[COURSE_NAME, CLASS_NAME, CAMPUS, ROOM, WEEKS, DAY, TIME])?;
+
+    let mut result = ParseResult::default();
+    // Index of arrangements by the primary key in `edu.course_arrangement`.
+    let mut seen = HashMap::new();
+    for (i, row) in rows.iter().enumerate() {
+        if is_empty_row(row) {
+            continue;
+        }
+        let cell = |names: &[&str]| get_cell(header, row, names);
+        match parse_row(&cell) {
+            // The same class may be listed once per teacher.
+            Ok(Some(arrangement)) => {
+                let key = (
+                    arrangement.class_name.clone(),
+                    arrangement.room.clone(),
+                    arrangement.weeks,
+                    arrangement.day,
+                    arrangement.time_index,
+                );
+                if let Some(&index) = seen.get(&key) {
+                    let existing: &mut Arrangement = &mut result.arrangements[index];
+                    existing.teacher = format!("{},{}", existing.teacher, arrangement.teacher);
+                } else {
+                    seen.insert(key, result.arrangements.len());
+                    result.arrangements.push(arrangement);
+                }
+            }
+            Ok(None) => result.no_room += 1,
+            Err(reason) => result.errors.push((i + 2, reason)),
+        }
+    }
+    Ok(result)
+}
+
+/// Entry of `loader import`, which expects database configured.
+pub async fn run(args: &[String]) -> Result<()> {
+    let (year, semester, path, dry_run) = match args {
+        [year, semester, path, rest @ ..] if rest.iter().all(|s| s == "--dry-run") => (
+            year.parse::<i32>().context("Invalid year")?,
+            semester.parse::<i32>().context("Invalid semester")?,
+            Path::new(path),
+            !rest.is_empty(),
+        ),
+        _ => bail!("Usage: loader import <year> <semester> <file> [--dry-run]"),
+    };
+    if !(1..=2).contains(&semester) {
+        bail!("Semester should be 1 or 2.");
+    }
+
+    let rows = read_table(path)?;
+    let ParseResult {
+        mut arrangements,
+        errors,
+        no_room,
+    } = parse_table(&rows)?;
+    for (row, reason) in &errors {
+        tracing::warn!("Row {row} skipped: {reason}");
+    }
+
+    let db = kite::get_db();
+    let rooms: HashSet<String> = edu::list_rooms(db).await?.into_iter().collect();
+    let unknown_rooms: HashSet<String> = arrangements
+        .iter()
+        .filter(|a| !rooms.contains(&a.room))
+        .map(|a| a.room.clone())
+        .collect();
+    for room in &unknown_rooms {
+        tracing::warn!("Unknown room {room}, courses in it are skipped.");
+    }
+    arrangements.retain(|a| rooms.contains(&a.room));
+
+    let conflicts = edu::find_conflicts(&arrangements);
+    for (i, j) in &conflicts {
+        let (a, b) = (&arrangements[*i], &arrangements[*j]);
+        tracing::warn!(
+            "Conflict in {} on day {}: {}({}) and {}({})",
+            a.room,
+            a.day,
+            a.course_name,
+            a.class_name,
+            b.course_name,
+            b.class_name
+        );
+    }
+    tracing::info!(
+        "{} arrangements parsed, {} rows skipped, {} rows without room, {} unknown rooms, {} conflicts.",
+        arrangements.len(),
+        errors.len(),
+        no_room,
+        unknown_rooms.len(),
+        conflicts.len()
+    );
+
+    if dry_run {
+        tracing::info!("Dry run, nothing is written.");
+        return Ok(());
+    }
+    let count = edu::replace(db, year, semester, &arrangements).await?;
+    tracing::info!("{count} arrangements of {year}-{semester} imported.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_day, parse_table, read_csv};
+
+    const EXPORT: &str = "\
+课程代码,课程名称,教学班名称,教师,校区,上课地点,周次,星期,节次
+B1220012,高等数学,22级机械1班,王五,奉贤校区,一教A101,1-16周,1,1-2
+B1220012,高等数学,22级机械1班,赵六,奉贤校区,一教A101,1-16周,1,1-2
+B1220013,大学物理,22级机械2班,钱七,徐汇校区,二教B203,1-15周(单),星期三,5-6节
+B1220014,形势与政策,22级机械1班,孙八,奉贤校区,,1-8周,2,9-10
+B1220015,体育,22级机械1班,周九,奉贤校区,操场,1-16周,4,3-4
+B1220016,线性代数,22级机械1班,吴十,奉贤校区,一教A102,1-16周,8,1-2
+";
+
+    #[test]
+    fn test_parse_day() {
+        assert_eq!(parse_day("1"), Some(1));
+        assert_eq!(parse_day("星期日"), Some(7));
+        assert_eq!(parse_day("周三"), Some(3));
+        assert_eq!(parse_day("8"), None);
+        assert_eq!(parse_day(""), None);
+    }
+
+    #[test]
+    fn test_parse_table() {
+        let rows = read_csv(EXPORT.as_bytes()).unwrap();
+        let result = parse_table(&rows).unwrap();
+
+        assert_eq!(result.arrangements.len(), 2);
+        assert_eq!(result.arrangements[0].teacher, "王五,赵六");
+        assert_eq!(result.arrangements[1].campus, 2);
+        assert_eq!(result.arrangements[1].day, 3);
+        assert_eq!(result.arrangements[1].time_index, 0b1100000);
+        assert_eq!(result.no_room, 1);
+        assert_eq!(
+            result.errors.iter().map(|(row, _)| *row).collect::<Vec<_>>(),
+            vec![6, 7]
+        );
+
+        assert!(parse_table(&[]).is_err());
+        assert!(parse_table(&rows[..1].iter().map(|r| r[..5].to_vec()).collect::<Vec<_>>()).is_err());
+    }
+}
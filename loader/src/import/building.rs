@@ -0,0 +1,132 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Import the location of buildings from a list (CSV or XLSX), which is used to rank classrooms by distance.
+//!
+//! Usage: `loader import-building <file> [--dry-run]`
+
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use kite::model::edu::{self, Building};
+
+use super::{get_cell, is_empty_row, read_table, split_header, RowError};
+
+const NAME: &[&str] = &["教学楼", "楼宇名称", "名称"];
+const CAMPUS: &[&str] = &["校区"];
+const LATITUDE: &[&str] = &["纬度"];
+const LONGITUDE: &[&str] = &["经度"];
+
+fn parse_coordinate(s: &str, max: f64) -> Option<f64> {
+    let value: f64 = s.parse().ok()?;
+    (-max..=max).contains(&value).then_some(value)
+}
+
+fn parse_row(cell: &dyn Fn(&[&str]) -> String) -> Result<Building, String> {
+    let name = cell(NAME);
+    if name.is_empty() {
+        return Err("empty name".to_string());
+    }
+    let campus = cell(CAMPUS);
+    let campus = edu::parse_campus(&campus).ok_or_else(|| format!("unknown campus {campus:?}"))?;
+    let latitude = cell(LATITUDE);
+    let latitude = parse_coordinate(&latitude, 90.0).ok_or_else(|| format!("invalid latitude {latitude:?}"))?;
+    let longitude = cell(LONGITUDE);
+    let longitude = parse_coordinate(&longitude, 180.0).ok_or_else(|| format!("invalid longitude {longitude:?}"))?;
+
+    Ok(Building {
+        name,
+        campus,
+        latitude,
+        longitude,
+    })
+}
+
+/// Buildings parsed, and rows which are skipped.
+fn parse_table(rows: &[Vec<String>]) -> Result<(Vec<Building>, Vec<RowError>)> {
+    let (header, rows) = split_header(rows, &[NAME, CAMPUS, LATITUDE, LONGITUDE])?;
+
+    let mut buildings: Vec<Building> = Vec::new();
+    let mut errors = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        if is_empty_row(row) {
+            continue;
+        }
+        match parse_row(&|names| get_cell(header, row, names)) {
+            Ok(building) if buildings.iter().any(|b| b.name == building.name) => {
+                errors.push((i + 2, format!("duplicated building {:?}", building.name)));
+            }
+            Ok(building) => buildings.push(building),
+            Err(reason) => errors.push((i + 2, reason)),
+        }
+    }
+    Ok((buildings, errors))
+}
+
+/// Entry of `loader import-building`, which expects database configured.
+pub async fn run(args: &[String]) -> Result<()> {
+    let (path, dry_run) = match args {
+        [path, rest @ ..] if rest.iter().all(|s| s == "--dry-run") => (Path::new(path), !rest.is_empty()),
+        _ => bail!("Usage: loader import-building <file> [--dry-run]"),
+    };
+
+    let rows = read_table(path)?;
+    let (buildings, errors) = parse_table(&rows)?;
+    for (row, reason) in &errors {
+        tracing::warn!("Row {row} skipped: {reason}");
+    }
+    tracing::info!("{} buildings parsed, {} rows skipped.", buildings.len(), errors.len());
+
+    if dry_run {
+        tracing::info!("Dry run, nothing is written.");
+        return Ok(());
+    }
+    let count = edu::save_buildings(kite::get_db(), &buildings).await?;
+    tracing::info!("{count} buildings imported.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::read_csv;
+    use super::parse_table;
+
+    const BUILDING_LIST: &str = "\
+教学楼,校区,纬度,经度
+一教,奉贤校区,30.8400,121.5100
+二教,徐汇校区,31.1700,121.4200
+一教,奉贤校区,30.8400,121.5100
+三教,奉贤校区,north,121.5100
+四教,奉贤校区,30.8400,190
+";
+
+    #[test]
+    fn test_parse_table() {
+        let rows = read_csv(BUILDING_LIST.as_bytes()).unwrap();
+        let (buildings, errors) = parse_table(&rows).unwrap();
+
+        assert_eq!(buildings.len(), 2);
+        assert_eq!(buildings[0].name, "一教");
+        assert_eq!(buildings[0].campus, 1);
+        assert_eq!(buildings[0].latitude, 30.84);
+        assert_eq!(buildings[1].campus, 2);
+        assert_eq!(buildings[1].longitude, 121.42);
+        assert_eq!(errors.iter().map(|(row, _)| *row).collect::<Vec<_>>(), vec![4, 5, 6]);
+    }
+}
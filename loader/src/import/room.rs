@@ -0,0 +1,147 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Import classroom metadata from the room list (CSV or XLSX) of the academic system.
+//!
+//! Usage: `loader import-room <file> [--dry-run]`
+
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use kite::model::classroom_browser::split_room_title;
+use kite::model::edu::{self, Room};
+
+use super::{get_cell, is_empty_row, read_table, split_header, RowError};
+
+const TITLE: &[&str] = &["教室名称", "教室", "场地名称"];
+const CAMPUS: &[&str] = &["校区"];
+const CAPACITY: &[&str] = &["座位数", "容量"];
+const ROOM_TYPE: &[&str] = &["教室类型", "场地类别"];
+const MULTIMEDIA: &[&str] = &["多媒体"];
+const FLOOR: &[&str] = &["楼层"];
+
+fn parse_row(cell: &dyn Fn(&[&str]) -> String) -> Result<Room, String> {
+    let title = cell(TITLE);
+    let (building, region) = split_room_title(&title).ok_or_else(|| format!("invalid room {title:?}"))?;
+    let campus = cell(CAMPUS);
+    let campus = edu::parse_campus(&campus).ok_or_else(|| format!("unknown campus {campus:?}"))?;
+    let capacity = match cell(CAPACITY).as_str() {
+        "" => None,
+        s => Some(s.parse().map_err(|_| format!("invalid capacity {s:?}"))?),
+    };
+    let room_type = cell(ROOM_TYPE);
+    let room_type = edu::parse_room_type(&room_type).ok_or_else(|| format!("unknown room type {room_type:?}"))?;
+    let multimedia = matches!(cell(MULTIMEDIA).as_str(), "是" | "有" | "1");
+    // The floor is taken from the room number if not given.
+    let floor = match cell(FLOOR).as_str() {
+        "" => edu::parse_floor(&title),
+        s => Some(s.parse().map_err(|_| format!("invalid floor {s:?}"))?),
+    };
+
+    Ok(Room {
+        title,
+        campus,
+        building,
+        region,
+        capacity,
+        room_type,
+        multimedia,
+        floor,
+    })
+}
+
+/// Rooms parsed, and rows which are skipped.
+fn parse_table(rows: &[Vec<String>]) -> Result<(Vec<Room>, Vec<RowError>)> {
+    let (header, rows) = split_header(rows, &[TITLE, CAMPUS])?;
+
+    let mut rooms: Vec<Room> = Vec::new();
+    let mut errors = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        if is_empty_row(row) {
+            continue;
+        }
+        match parse_row(&|names| get_cell(header, row, names)) {
+            Ok(room) if rooms.iter().any(|r| r.title == room.title) => {
+                errors.push((i + 2, format!("duplicated room {:?}", room.title)));
+            }
+            Ok(room) => rooms.push(room),
+            Err(reason) => errors.push((i + 2, reason)),
+        }
+    }
+    Ok((rooms, errors))
+}
+
+/// Entry of `loader import-room`, which expects database configured.
+pub async fn run(args: &[String]) -> Result<()> {
+    let (path, dry_run) = match args {
+        [path, rest @ ..] if rest.iter().all(|s| s == "--dry-run") => (Path::new(path), !rest.is_empty()),
+        _ => bail!("Usage: loader import-room <file> [--dry-run]"),
+    };
+
+    let rows = read_table(path)?;
+    let (rooms, errors) = parse_table(&rows)?;
+    for (row, reason) in &errors {
+        tracing::warn!("Row {row} skipped: {reason}");
+    }
+    tracing::info!("{} rooms parsed, {} rows skipped.", rooms.len(), errors.len());
+
+    if dry_run {
+        tracing::info!("Dry run, nothing is written.");
+        return Ok(());
+    }
+    let count = edu::save_rooms(kite::get_db(), &rooms).await?;
+    tracing::info!("{count} rooms imported.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use kite::model::edu::{ROOM_TYPE_LECTURE_HALL, ROOM_TYPE_ORDINARY};
+
+    use super::super::read_csv;
+    use super::parse_table;
+
+    const ROOM_LIST: &str = "\
+教室名称,校区,座位数,教室类型,多媒体
+一教A101,奉贤校区,120,阶梯教室,是
+二教B1203,徐汇校区,,普通教室,否
+一教A101,奉贤校区,120,阶梯教室,是
+操场,奉贤校区,,,否
+三教C301,奉贤校区,60,泳池,否
+";
+
+    #[test]
+    fn test_parse_table() {
+        let rows = read_csv(ROOM_LIST.as_bytes()).unwrap();
+        let (rooms, errors) = parse_table(&rows).unwrap();
+
+        assert_eq!(rooms.len(), 2);
+        assert_eq!(rooms[0].building, "一教");
+        assert_eq!(rooms[0].region.as_deref(), Some("A"));
+        assert_eq!(rooms[0].capacity, Some(120));
+        assert_eq!(rooms[0].room_type, ROOM_TYPE_LECTURE_HALL);
+        assert!(rooms[0].multimedia);
+        assert_eq!(rooms[0].floor, Some(1));
+        assert_eq!(rooms[1].campus, 2);
+        assert_eq!(rooms[1].capacity, None);
+        assert_eq!(rooms[1].room_type, ROOM_TYPE_ORDINARY);
+        assert_eq!(rooms[1].floor, Some(12));
+        assert_eq!(errors.iter().map(|(row, _)| *row).collect::<Vec<_>>(), vec![4, 5, 6]);
+    }
+}
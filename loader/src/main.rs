@@ -21,9 +21,38 @@ use kite::config;
 use kite::db;
 use kite::service::KiteModule;
 
+mod import;
+
+/// Entry of `loader hash-freshman-secret`, run after freshman data is loaded.
+async fn hash_freshman_secret() -> anyhow::Result<()> {
+    let count = kite::model::freshman::hash_plain_secrets(kite::get_db()).await?;
+    tracing::info!("{count} secrets hashed.");
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt().with_max_level(tracing::Level::INFO).init();
+
+    let args: Vec<String> = std::env::args().collect();
+    let subcommand = args.get(1).map(String::as_str);
+    if let Some(subcommand @ ("import" | "import-room" | "import-building" | "hash-freshman-secret")) = subcommand {
+        config::initialize();
+        // The cache is held by the running server, which clears it on notification.
+        db::initialize_db().await;
+        let result = match subcommand {
+            "import" => import::run(&args[2..]).await,
+            "import-room" => import::room::run(&args[2..]).await,
+            "import-building" => import::building::run(&args[2..]).await,
+            _ => hash_freshman_secret().await,
+        };
+        if let Err(e) = result {
+            tracing::error!("{subcommand} failed: {e:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     tracing::info!("Starting...");
 
     config::initialize();
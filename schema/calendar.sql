@@ -0,0 +1,34 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Terms of the school calendar (校历). Week 1 begins on begin_date, which is a Monday.
+CREATE TABLE IF NOT EXISTS calendar_term
+(
+    year       INT  NOT NULL,
+    semester   INT  NOT NULL,
+    begin_date DATE NOT NULL,
+    end_date   DATE NOT NULL,
+    PRIMARY KEY (year, semester),
+    CHECK (begin_date <= end_date)
+);
+
+-- Holidays and make-up days (调休). On a make-up day, classes of follow_date are taken,
+-- and there is no class on a holiday, whose follow_date is NULL.
+CREATE TABLE IF NOT EXISTS calendar_day_override
+(
+    date        DATE PRIMARY KEY,
+    follow_date DATE,
+    name        TEXT NOT NULL
+);
+
+-- Class period time table of each campus, 1 = 奉贤校区, 2 = 徐汇校区.
+-- The default time table in `kite::model::timetable::CLASS_TIME` is used for campuses not listed.
+CREATE TABLE IF NOT EXISTS calendar_class_period
+(
+    campus     INT  NOT NULL,
+    period     INT  NOT NULL CHECK (period BETWEEN 1 AND 11),
+    begin_time TIME NOT NULL,
+    end_time   TIME NOT NULL,
+    PRIMARY KEY (campus, period)
+);
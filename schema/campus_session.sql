@@ -0,0 +1,12 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Sessions on campus systems, which are logged in during kite login, one row per user.
+-- Cookies of these systems and their entry pages are stored, but neither password nor the login ticket of authserver.
+CREATE TABLE IF NOT EXISTS campus_session
+(
+    uid         INT PRIMARY KEY REFERENCES user_account (uid),
+    session     JSONB       NOT NULL,
+    update_time TIMESTAMPTZ NOT NULL DEFAULT current_timestamp
+);
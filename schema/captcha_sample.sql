@@ -0,0 +1,15 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Captcha images met in login, labeled by OCR or user.
+-- `correct` is NULL when the login fails for other reasons, so the answer can't be judged.
+CREATE TABLE IF NOT EXISTS captcha_sample
+(
+    id          SERIAL PRIMARY KEY,
+    image       BYTEA       NOT NULL,
+    answer      TEXT        NOT NULL,
+    source      TEXT        NOT NULL CHECK (source IN ('ocr', 'user')),
+    correct     BOOLEAN,
+    create_time TIMESTAMPTZ NOT NULL DEFAULT current_timestamp
+);
@@ -0,0 +1,20 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Campus card transactions imported from the card system.
+-- The same transaction may be imported more than once, and the unique constraint skips duplicates.
+CREATE TABLE IF NOT EXISTS card_transaction
+(
+    id       SERIAL PRIMARY KEY,
+    uid      INT         NOT NULL REFERENCES user_account (uid),
+    ts       TIMESTAMPTZ NOT NULL,
+    -- Negative for consumption, and positive for recharge
+    amount   REAL        NOT NULL,
+    balance  REAL        NOT NULL,
+    merchant TEXT        NOT NULL,
+    kind     TEXT        NOT NULL,
+    -- See `kite::model::card::categorize`
+    category TEXT        NOT NULL,
+    UNIQUE (uid, ts, merchant, amount)
+);
@@ -0,0 +1,29 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- `edu.classroom`, `edu.course_arrangement` and `edu.query_available_classrooms` are maintained outside this
+-- repository, and they are not redefined here. `loader import-room` and `loader import` write the existing
+-- columns below, and this file only adds what they don't have.
+--
+--   edu.classroom (title TEXT PRIMARY KEY, campus INT, building TEXT, region TEXT, capacity INT)
+--   edu.course_arrangement (year INT, semester INT, course_code TEXT, course_name TEXT, class_name TEXT,
+--                           teacher TEXT, campus INT, room TEXT, weeks INT, day INT, time_index INT)
+--
+-- Campus: 1 = 奉贤校区, 2 = 徐汇校区.
+
+-- Room metadata used by filters, maintained by `loader import-room`.
+-- Room type: 0 = 普通教室, 1 = 阶梯教室, 2 = 实验室, the same as `RoomType` in classroom_browser.proto.
+ALTER TABLE edu.classroom
+    ADD COLUMN IF NOT EXISTS room_type  INT     NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS multimedia BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS floor      INT;
+
+-- Location of buildings, used to rank classrooms by distance. Maintained by `loader import-building`.
+CREATE TABLE IF NOT EXISTS edu.building
+(
+    name      TEXT PRIMARY KEY,
+    campus    INT              NOT NULL,
+    latitude  DOUBLE PRECISION NOT NULL,
+    longitude DOUBLE PRECISION NOT NULL
+);
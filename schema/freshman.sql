@@ -0,0 +1,45 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Freshman data from the admission office, loaded before September each year.
+-- Freshmen log in by admission ID (or student ID) and secret before they have a user account.
+CREATE TABLE IF NOT EXISTS freshman
+(
+    fid           SERIAL PRIMARY KEY,
+    entrance_year INT     NOT NULL,
+    -- 考生号
+    admission_id  TEXT    NOT NULL,
+    student_id    TEXT    NOT NULL UNIQUE,
+    name          TEXT    NOT NULL,
+    -- 验证码 in plain text as loaded, generated from the ID card number. It's replaced by `secret_hash` and
+    -- cleared by `loader hash-freshman-secret` once loaded.
+    secret        TEXT,
+    -- See `kite::model::freshman::hash_secret`
+    secret_hash   TEXT,
+    -- 0 = male, 1 = female, the same as `template.Gender`
+    gender        INT     NOT NULL,
+    college       TEXT    NOT NULL,
+    major         TEXT    NOT NULL,
+    -- 行政班
+    class_name    TEXT    NOT NULL,
+    campus        TEXT    NOT NULL,
+    building      TEXT    NOT NULL,
+    room          INT     NOT NULL,
+    bed_index     INT     NOT NULL,
+    province      TEXT,
+    city          TEXT,
+    high_school   TEXT,
+    -- Visible in "people may know" of others
+    visible       BOOLEAN NOT NULL DEFAULT TRUE,
+    -- JSON string set by the user
+    contact       TEXT,
+    last_seen     TIMESTAMPTZ,
+    UNIQUE (entrance_year, admission_id)
+);
+
+CREATE INDEX IF NOT EXISTS freshman_room_idx ON freshman (entrance_year, campus, building, room);
+CREATE INDEX IF NOT EXISTS freshman_class_idx ON freshman (entrance_year, class_name);
+CREATE INDEX IF NOT EXISTS freshman_city_idx ON freshman (entrance_year, city);
+CREATE INDEX IF NOT EXISTS freshman_high_school_idx ON freshman (entrance_year, high_school);
+CREATE INDEX IF NOT EXISTS freshman_name_idx ON freshman (entrance_year, name);
@@ -0,0 +1,12 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Current loans fetched from the library system, one row per user.
+-- Loans are stored as a JSON array, in the format of `kite::model::library::Loan`.
+CREATE TABLE IF NOT EXISTS library_loan_cache
+(
+    uid         INT PRIMARY KEY REFERENCES user_account (uid),
+    loans       JSONB       NOT NULL,
+    update_time TIMESTAMPTZ NOT NULL DEFAULT current_timestamp
+);
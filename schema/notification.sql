@@ -0,0 +1,20 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Messages produced by background jobs for a user, such as new grades and exam reminders.
+-- `kind` tells which feature the message belongs to, like "score".
+CREATE TABLE IF NOT EXISTS notification
+(
+    id          SERIAL PRIMARY KEY,
+    uid         INT         NOT NULL REFERENCES user_account (uid),
+    kind        TEXT        NOT NULL,
+    title       TEXT        NOT NULL,
+    content     TEXT        NOT NULL,
+    -- Set by jobs which create a notification only once, such as reminders. NULLs don't conflict.
+    key         TEXT,
+    create_time TIMESTAMPTZ NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX IF NOT EXISTS notification_uid_kind_index ON notification (uid, kind, create_time);
+CREATE UNIQUE INDEX IF NOT EXISTS notification_key_index ON notification (uid, kind, key);
@@ -0,0 +1,21 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Grades fetched from the academic system. A snapshot is saved only if it differs from the last one
+-- of the same user and term, and background job compares it with the last one to find new grades.
+CREATE TABLE IF NOT EXISTS score_snapshot
+(
+    id          SERIAL PRIMARY KEY,
+    uid         INT         NOT NULL REFERENCES user_account (uid),
+    year        INT         NOT NULL,
+    semester    INT         NOT NULL,
+    -- JSON array in the format of `kite::model::score::Score`
+    scores      JSONB       NOT NULL,
+    create_time TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    -- Last time the same grades are fetched
+    update_time TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    processed   BOOLEAN     NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS score_snapshot_term_index ON score_snapshot (uid, year, semester, id);
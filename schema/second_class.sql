@@ -0,0 +1,12 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Scores, joined activities and upcoming activities fetched from the second classroom system, one row per user.
+-- Data is stored as JSON, in the format of `kite::model::second_class::SecondClass`.
+CREATE TABLE IF NOT EXISTS second_class_cache
+(
+    uid         INT PRIMARY KEY REFERENCES user_account (uid),
+    data        JSONB       NOT NULL,
+    update_time TIMESTAMPTZ NOT NULL DEFAULT current_timestamp
+);
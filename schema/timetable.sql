@@ -0,0 +1,15 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Course table fetched from the academic system, one row per user and term.
+-- Courses are stored as a JSON array, in the format of `kite::model::timetable::Course`.
+CREATE TABLE IF NOT EXISTS timetable_cache
+(
+    uid         INT         NOT NULL REFERENCES user_account (uid),
+    year        INT         NOT NULL,
+    semester    INT         NOT NULL,
+    courses     JSONB       NOT NULL,
+    update_time TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    PRIMARY KEY (uid, year, semester)
+);
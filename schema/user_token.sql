@@ -0,0 +1,25 @@
+-- 上应小风筝  便利校园，一步到位
+-- Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Refresh tokens issued on login. A refresh token can be used only once: it's revoked when
+-- exchanged for a new pair.
+CREATE TABLE IF NOT EXISTS user_refresh_token
+(
+    token       UUID PRIMARY KEY,
+    uid         INT         NOT NULL REFERENCES user_account (uid),
+    create_time TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    expire_time TIMESTAMPTZ NOT NULL,
+    revoked     BOOLEAN     NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS user_refresh_token_uid_index ON user_refresh_token (uid);
+
+-- Access tokens revoked before they expire, keyed by the `jti` claim.
+-- Rows whose expire_time has passed are useless and can be deleted at any time.
+CREATE TABLE IF NOT EXISTS user_token_revocation
+(
+    jti         UUID PRIMARY KEY,
+    uid         INT         NOT NULL,
+    expire_time TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,45 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use poem::http::StatusCode;
+use poem::web::{Data, Json};
+use poem::{handler, IntoResponse, Response};
+use sqlx::PgPool;
+
+use crate::response::ApiResponse;
+
+/// Liveness probe: the process is up and able to answer http requests.
+#[handler]
+pub async fn liveness() -> &'static str {
+    "ok"
+}
+
+/// Readiness probe: database, captcha module and balance data are all available.
+/// Respond 503 with the detailed report if any of them is not.
+#[handler]
+pub async fn readiness(pool: Data<&PgPool>) -> Response {
+    let report = kite::health::check(&pool, captcha::is_ready()).await;
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let body: serde_json::Value = ApiResponse::normal(report).into();
+
+    Json(body).with_status(status).into_response()
+}
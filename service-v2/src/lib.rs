@@ -27,6 +27,7 @@ mod response;
 mod captcha;
 mod electricity;
 mod error;
+mod health;
 
 pub struct ServerHttp;
 
@@ -47,7 +48,9 @@ async fn http_service() -> Result<(), std::io::Error> {
                 .at("/room/:room/bill/days", get(electricity::query_room_bills_by_day))
                 .at("/room/:room/bill/hours", get(electricity::query_room_bills_by_hour)),
         )
-        .nest("/ocr", Route::new().at("/captcha", post(captcha::recognize_captcha)));
+        .nest("/ocr", Route::new().at("/captcha", post(captcha::recognize_captcha)))
+        .at("/healthz", get(health::liveness))
+        .at("/readyz", get(health::readiness));
 
     let app = route.with(AddData::new(get_db().clone()));
     poem::Server::new(TcpListener::bind("127.0.0.1:3000")).run(app).await
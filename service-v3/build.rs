@@ -31,11 +31,18 @@ fn main() {
         "../proto/badge.proto",
         "../proto/balance.proto",
         "../proto/board.proto",
+        "../proto/calendar.proto",
+        "../proto/campus_card.proto",
         "../proto/captcha.proto",
         "../proto/classroom_browser.proto",
+        "../proto/exam.proto",
         "../proto/exception.proto",
         "../proto/freshman.proto",
         "../proto/game.proto",
+        "../proto/library.proto",
+        "../proto/score.proto",
+        "../proto/second_class.proto",
+        "../proto/timetable.proto",
         "../proto/user.proto",
         "../proto/yellow_page.proto",
     ];
@@ -16,12 +16,24 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-pub use client::Session;
-pub use portal::{Credential, Portal, PortalConnector};
+#[cfg(test)]
+pub use client::MockConnector;
+pub use client::{Session, Transport};
+pub use cookie::CookieJar;
+#[cfg(test)]
+pub use portal::bind_mock;
+pub use portal::{Credential, LoginError, Portal, PortalConnector, ServiceSession};
+pub use solver::CaptchaSolver;
 pub use tls::get as tls_get;
+#[cfg(test)]
+pub use tls::set_roots as tls_set_roots;
 
 mod client;
+mod cookie;
+mod parser;
 mod portal;
+mod solver;
+mod strategy;
 mod tls;
 
 pub mod constants {
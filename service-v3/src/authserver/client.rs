@@ -16,10 +16,18 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
+//! A small HTTP/1.1 client for campus hosts.
+//!
+//! The connection to authserver is relayed by the app, while other hosts, which are reached by redirections
+//! after login, are connected by a `Connector`. All of them share one cookie jar.
+
 use std::collections::HashMap;
+use std::io::Read;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use bytes::{BufMut, Bytes, BytesMut};
+use http::header::{CONTENT_ENCODING, CONTENT_LENGTH, LOCATION};
+use http::Uri;
 use hyper::body::HttpBody;
 use hyper::client::conn;
 use hyper::{Body, Method, Response, StatusCode};
@@ -29,38 +37,82 @@ use tokio::sync::oneshot;
 use tokio_rustls::client::TlsStream;
 
 use super::constants::*;
+use super::cookie::CookieJar;
+use super::portal::LoginError;
+
+/// Byte stream to a host. TLS is set up by `Session` for https.
+pub trait Transport: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Transport for T {}
 
-#[derive(Default)]
-struct CookieJar {
-    pub inner: HashMap<String, String>,
+/// Open connections to hosts other than authserver.
+#[async_trait::async_trait]
+pub trait Connector: Send + Sync {
+    async fn connect(&self, host: &str, port: u16) -> Result<Box<dyn Transport>>;
 }
 
-impl CookieJar {
-    fn parse_line(cookie: &str) -> Option<(&str, &str)> {
-        // JSESSIONID=xSiUKpqm0lmjhDXB41_hhyxiNUa69u4xMnHkFOFS61E6VZ6Osp7S!-1266297679; path=/; HttpOnly
-        cookie.split_once(';').and_then(|s| s.0.split_once('='))
+/// Connect by TCP from kite-server.
+pub struct DirectConnector;
+
+#[async_trait::async_trait]
+impl Connector for DirectConnector {
+    async fn connect(&self, host: &str, port: u16) -> Result<Box<dyn Transport>> {
+        let stream = tokio::net::TcpStream::connect((host, port)).await?;
+        Ok(Box::new(stream))
     }
+}
 
-    pub fn append(&mut self, cookie: &str) {
-        if let Some((k, v)) = Self::parse_line(cookie) {
-            // This method will override the old one if k already exists.
-            self.inner.insert(k.to_string(), v.to_string());
+/// Scheme, host and port of a request.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Origin {
+    https: bool,
+    host: String,
+    port: u16,
+}
+
+impl Origin {
+    fn authserver() -> Self {
+        Origin {
+            https: true,
+            host: SERVER_NAME.to_string(),
+            port: 443,
         }
     }
 
-    pub fn to_string(&self) -> Option<String> {
-        if self.inner.is_empty() {
-            return None;
+    /// Relative URI is on authserver.
+    fn of(uri: &Uri) -> Result<Self> {
+        let Some(host) = uri.host() else {
+            return Ok(Self::authserver());
+        };
+        let https = match uri.scheme_str() {
+            Some("https") => true,
+            Some("http") => false,
+            _ => bail!("Unsupported URI: {}", uri),
+        };
+        let port = uri.port_u16().unwrap_or(if https { 443 } else { 80 });
+        Ok(Origin {
+            https,
+            host: host.to_ascii_lowercase(),
+            port,
+        })
+    }
+
+    fn is_default_port(&self) -> bool {
+        self.port == if self.https { 443 } else { 80 }
+    }
+
+    /// Value of `Host` header.
+    fn host_header(&self) -> String {
+        if self.is_default_port() {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
         }
-        let result = self
-            .inner
-            .iter()
-            .fold(String::new(), |s, (k, v)| s + &*format!("{}={};", k, v));
-        return Some(result);
     }
 
-    pub fn clear(&mut self) {
-        self.inner.clear();
+    fn to_url_prefix(&self) -> String {
+        let scheme = if self.https { "https" } else { "http" };
+        format!("{}://{}", scheme, self.host_header())
     }
 }
 
@@ -69,8 +121,12 @@ pub struct Session<T>
 where
     T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
 {
-    /// 会话用的连接
-    sender: conn::SendRequest<Body>,
+    /// 会话用的连接, 通往 authserver. 恢复的会话没有这个连接
+    sender: Option<conn::SendRequest<Body>>,
+    /// 其他主机的连接
+    others: HashMap<Origin, conn::SendRequest<Body>>,
+    /// 连接其他主机的方式
+    connector: Box<dyn Connector>,
     /// Cookie 存储
     cookie_jar: CookieJar,
     /// receiver to released TLS stream
@@ -99,13 +155,54 @@ where
             }
         });
         let result = Session {
-            sender,
+            sender: Some(sender),
+            others: HashMap::new(),
+            connector: Box::new(DirectConnector),
             cookie_jar: CookieJar::default(),
             released_rx: rx,
         };
         Ok(result)
     }
 
+    /// Change how to connect hosts other than authserver, such as to a mock server in tests.
+    #[cfg(test)]
+    pub fn set_connector(&mut self, connector: Box<dyn Connector>) {
+        self.connector = connector;
+        self.others.clear();
+    }
+
+    /// Get the connection to `origin`, and connect if there is no one alive.
+    async fn sender(&mut self, origin: &Origin) -> Result<&mut conn::SendRequest<Body>> {
+        if *origin == Origin::authserver() {
+            // Campus systems redirect to authserver when their sessions expire.
+            return self.sender.as_mut().ok_or_else(|| LoginError::NotLoggedIn.into());
+        }
+        // Drop the connection closed by server.
+        if let Some(sender) = self.others.get_mut(origin) {
+            if std::future::poll_fn(|cx| sender.poll_ready(cx)).await.is_err() {
+                self.others.remove(origin);
+            }
+        }
+        if !self.others.contains_key(origin) {
+            let stream = self.connector.connect(&origin.host, origin.port).await?;
+            let stream: Box<dyn Transport> = if origin.https {
+                let connector = tokio_rustls::TlsConnector::from(super::tls::get().clone());
+                let server_name = origin.host.as_str().try_into()?;
+                Box::new(connector.connect(server_name, stream).await?)
+            } else {
+                stream
+            };
+            let (sender, connection) = conn::handshake(stream).await?;
+            tokio::spawn(async move {
+                if let Err(e) = connection.await {
+                    tracing::debug!("Connection closed with error: {}", e);
+                }
+            });
+            self.others.insert(origin.clone(), sender);
+        }
+        Ok(self.others.get_mut(origin).unwrap())
+    }
+
     /// A simple wrapper, which can send a HTTP(S) request
     async fn request(
         &mut self,
@@ -114,35 +211,45 @@ where
         text_payload: Option<String>,
         header: Vec<(String, String)>,
     ) -> Result<Response<Bytes>> {
+        let target: Uri = uri.parse()?;
+        let origin = Origin::of(&target)?;
+        let path = target.path().to_string();
+        let path_and_query = target.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+
         let mut builder = http::Request::builder()
             .method(method)
-            .uri(uri)
-            .header("Host", SERVER_NAME)
-            .header("User-Agent", DESKTOP_USER_AGENT);
+            .uri(path_and_query)
+            .header("Host", origin.host_header())
+            .header("User-Agent", DESKTOP_USER_AGENT)
+            .header("Accept-Encoding", "gzip, deflate");
         for (k, v) in header {
             builder = builder.header(k, v);
         }
 
-        if let Some(cookie) = self.cookie_jar.to_string() {
+        if let Some(cookie) = self.cookie_jar.header_value(&origin.host, &path, origin.https) {
             builder = builder.header("Cookie", cookie);
         }
         let body = text_payload.map(Body::from).unwrap_or_else(|| Body::empty());
         let request = builder.body(body)?;
 
         /* Send request and receive header*/
-        let response = self.sender.send_request(request).await?;
-        let (header, mut body) = response.into_parts();
+        let sender = self.sender(&origin).await?;
+        let response = sender.send_request(request).await?;
+        let (mut header, mut body) = response.into_parts();
         // Store cookies
-        if let Some(cookies) = header.headers.get("Set-Cookie") {
-            self.cookie_jar.append(cookies.to_str().unwrap());
-        }
+        self.cookie_jar.store_response(&origin.host, &path, &header.headers);
         // Pull data chunks
         let mut content = BytesMut::new();
         while let Some(chunk) = body.data().await {
             let chunk = chunk?;
             content.put(chunk);
         }
-        let content = Bytes::from(content);
+        let encoding = header.headers.remove(CONTENT_ENCODING);
+        let encoding = encoding.as_ref().map(|v| v.to_str()).transpose()?;
+        let content = decode_body(encoding, Bytes::from(content))?;
+        if encoding.is_some() {
+            header.headers.remove(CONTENT_LENGTH);
+        }
         let response = Response::from_parts(header, content);
         Ok(response)
     }
@@ -151,30 +258,28 @@ where
         self.request(Method::GET, url, None, vec![]).await
     }
 
-    pub async fn get_with_redirection(&mut self, url: &str, max_direction: u8) -> Result<Response<Bytes>> {
-        let mut count = 0u8;
-        let mut target = String::from(url);
-        let mut response: Response<Bytes> = Default::default();
-
-        assert!(max_direction > count);
-        while count < max_direction {
-            count += 1;
+    /// GET `url` and follow redirections, which may lead to other hosts.
+    pub async fn get_with_redirection(&mut self, url: &str, max_redirection: u8) -> Result<Response<Bytes>> {
+        let (_, response) = self.navigate(url, max_redirection).await?;
+        Ok(response)
+    }
 
-            response = self.get(&target).await?;
-            let status = response.status();
+    /// Like `get_with_redirection`, and return the URL of the last response too.
+    pub async fn navigate(&mut self, url: &str, max_redirection: u8) -> Result<(String, Response<Bytes>)> {
+        let mut target = String::from(url);
 
-            if status == StatusCode::FOUND || status == StatusCode::MOVED_PERMANENTLY {
-                let new_target = response.headers().get("Location").unwrap();
-                target = new_target.to_str()?.to_string();
-            } else {
-                break;
+        for _ in 0..max_redirection {
+            let response = self.get(&target).await?;
+            if !is_redirection(response.status()) {
+                return Ok((target, response));
             }
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .ok_or_else(|| anyhow!("Redirection without Location header."))?;
+            target = resolve_location(&target, location.to_str()?)?;
         }
-        if count == max_direction {
-            Err(anyhow::anyhow!("Max redirection count exceeds."))
-        } else {
-            Ok(response)
-        }
+        Err(anyhow!("Max redirection count exceeds."))
     }
 
     pub async fn post(
@@ -210,7 +315,234 @@ where
         self.cookie_jar.clear();
     }
 
+    /// Cookies of all hosts except authserver, which are enough to visit campus systems logged in.
+    pub fn export_cookies(&self) -> CookieJar {
+        let mut cookie_jar = self.cookie_jar.clone();
+        cookie_jar.remove_host(SERVER_NAME);
+        cookie_jar
+    }
+
     pub async fn wait_for_shutdown(self) -> Result<TlsStream<T>> {
         self.released_rx.await.map_err(Into::into)
     }
 }
+
+impl Session<Box<dyn Transport>> {
+    /// A session without login, for public pages of campus systems. Only the hosts requested are connected.
+    pub fn anonymous() -> Self {
+        Self::restore(CookieJar::default())
+    }
+
+    /// Restore a session on campus systems with saved cookies, without connection to authserver.
+    pub fn restore(cookie_jar: CookieJar) -> Self {
+        let (_, released_rx) = oneshot::channel();
+        Session {
+            sender: None,
+            others: HashMap::new(),
+            connector: Box::new(DirectConnector),
+            cookie_jar,
+            released_rx,
+        }
+    }
+}
+
+/// GET is used for the next request on all of these, as there is no redirection after POST so far.
+fn is_redirection(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    )
+}
+
+/// Resolve `Location` header against the URL requested, and return an absolute URL.
+pub(super) fn resolve_location(base: &str, location: &str) -> Result<String> {
+    if location.starts_with("https://") || location.starts_with("http://") {
+        return Ok(location.to_string());
+    }
+    let base: Uri = base.parse()?;
+    let origin = Origin::of(&base)?;
+
+    if let Some(rest) = location.strip_prefix("//") {
+        let scheme = if origin.https { "https" } else { "http" };
+        return Ok(format!("{}://{}", scheme, rest));
+    }
+    let (path, query) = match location.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (location, None),
+    };
+    let path = if path.is_empty() {
+        base.path().to_string()
+    } else if path.starts_with('/') {
+        path.to_string()
+    } else {
+        // Relative to the directory of base path.
+        let directory = &base.path()[..=base.path().rfind('/').unwrap_or(0)];
+        format!("{}{}", directory, path)
+    };
+    let mut result = origin.to_url_prefix() + &remove_dot_segments(&path);
+    if let Some(query) = query {
+        result = result + "?" + query;
+    }
+    Ok(result)
+}
+
+/// Remove "." and ".." in path, see RFC 3986 section 5.2.4.
+fn remove_dot_segments(path: &str) -> String {
+    let mut output: Vec<&str> = Vec::new();
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i == segments.len() - 1;
+        match *segment {
+            "." if last => output.push(""),
+            "." => {}
+            ".." => {
+                output.pop();
+                if last {
+                    output.push("");
+                }
+            }
+            s => output.push(s),
+        }
+    }
+    format!("/{}", output.join("/"))
+}
+
+/// Decode body by `Content-Encoding`.
+fn decode_body(encoding: Option<&str>, content: Bytes) -> Result<Bytes> {
+    use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+
+    let mut output = Vec::new();
+    match encoding.map(|e| e.trim().to_ascii_lowercase()).as_deref() {
+        None | Some("") | Some("identity") => return Ok(content),
+        Some("gzip") | Some("x-gzip") => {
+            GzDecoder::new(&content[..]).read_to_end(&mut output)?;
+        }
+        // "deflate" should be zlib format, but some servers send raw deflate data.
+        Some("deflate") => {
+            if ZlibDecoder::new(&content[..]).read_to_end(&mut output).is_err() {
+                output.clear();
+                DeflateDecoder::new(&content[..]).read_to_end(&mut output)?;
+            }
+        }
+        Some(other) => bail!("Unsupported Content-Encoding: {}", other),
+    }
+    Ok(Bytes::from(output))
+}
+
+/// Connect every host to the mock server.
+#[cfg(test)]
+pub struct MockConnector(pub authserver_mock::MockAuthserver);
+
+#[cfg(test)]
+#[async_trait::async_trait]
+impl Connector for MockConnector {
+    async fn connect(&self, _host: &str, _port: u16) -> Result<Box<dyn Transport>> {
+        Ok(Box::new(self.0.connect()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use authserver_mock::MockAuthserver;
+    use tokio::io::DuplexStream;
+
+    use super::*;
+
+    #[test]
+    fn test_resolve_location() {
+        let base = "https://authserver.sit.edu.cn/authserver/login?service=x";
+        let cases = [
+            ("https://jwxt.sit.edu.cn/jwxt/", "https://jwxt.sit.edu.cn/jwxt/"),
+            ("//jwxt.sit.edu.cn/jwxt/", "https://jwxt.sit.edu.cn/jwxt/"),
+            (
+                "/authserver/index.do",
+                "https://authserver.sit.edu.cn/authserver/index.do",
+            ),
+            ("index.do?a=1", "https://authserver.sit.edu.cn/authserver/index.do?a=1"),
+            ("../jwxt/./home.do", "https://authserver.sit.edu.cn/jwxt/home.do"),
+            ("?service=y", "https://authserver.sit.edu.cn/authserver/login?service=y"),
+        ];
+        for (location, expected) in cases {
+            assert_eq!(resolve_location(base, location).unwrap(), expected, "{}", location);
+        }
+        // Relative base is on authserver.
+        assert_eq!(
+            resolve_location("/authserver/login", "index.do").unwrap(),
+            "https://authserver.sit.edu.cn/authserver/index.do"
+        );
+        assert_eq!(
+            resolve_location("http://localhost:8080/a/b", "c").unwrap(),
+            "http://localhost:8080/a/c"
+        );
+    }
+
+    #[test]
+    fn test_decode_body() {
+        use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+        use flate2::Compression;
+
+        let text = "上应小风筝".repeat(10);
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(text.as_bytes()).unwrap();
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(text.as_bytes()).unwrap();
+        let mut deflate = DeflateEncoder::new(Vec::new(), Compression::default());
+        deflate.write_all(text.as_bytes()).unwrap();
+
+        let cases = [
+            (None, text.as_bytes().to_vec()),
+            (Some("gzip"), gzip.finish().unwrap()),
+            (Some("deflate"), zlib.finish().unwrap()),
+            (Some("deflate"), deflate.finish().unwrap()),
+        ];
+        for (encoding, content) in cases {
+            let decoded = decode_body(encoding, Bytes::from(content)).unwrap();
+            assert_eq!(decoded, text.as_bytes());
+        }
+        assert!(decode_body(Some("br"), Bytes::new()).is_err());
+    }
+
+    async fn session(server: &MockAuthserver) -> Session<DuplexStream> {
+        crate::authserver::tls_set_roots(authserver_mock::root_store());
+
+        let connector = tokio_rustls::TlsConnector::from(super::super::tls::get().clone());
+        let server_name = SERVER_NAME.try_into().unwrap();
+        let stream = connector.connect(server_name, server.connect()).await.unwrap();
+        let mut session = Session::create(stream).await.unwrap();
+        session.set_connector(Box::new(MockConnector(server.clone())));
+        session
+    }
+
+    #[tokio::test]
+    async fn test_redirect_across_hosts() {
+        let server = MockAuthserver::new();
+        let mut session = session(&server).await;
+
+        let target = utf8_percent_encode("https://jwxt.sit.edu.cn/jwxt/", NON_ALPHANUMERIC).to_string();
+        for status in [301, 302, 303, 307, 308] {
+            let url = format!("/authserver/redirect?status={}&target={}", status, target);
+            let response = session.get_with_redirection(&url, 5).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let text = String::from_utf8(response.body().to_vec()).unwrap();
+            assert!(text.contains(authserver_mock::JWXT_HOME_TITLE), "{}", text);
+        }
+        // Cookie of jwxt is not sent to authserver.
+        assert!(session
+            .cookie_jar
+            .header_value("jwxt.sit.edu.cn", "/jwxt/home.do", true)
+            .is_some_and(|v| v.contains("route=")));
+        assert!(session
+            .cookie_jar
+            .header_value(SERVER_NAME, "/jwxt/home.do", true)
+            .is_none());
+        // Endless redirection.
+        assert!(session.get_with_redirection("/authserver/loop", 3).await.is_err());
+    }
+}
@@ -0,0 +1,336 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Cookie storage following RFC 6265, which is enough for a login session.
+
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use http::header::SET_COOKIE;
+use http::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cookie {
+    name: String,
+    value: String,
+    /// Domain without leading dot, or the request host if `host_only`.
+    domain: String,
+    /// Whether `Domain` attribute is absent, then the cookie is sent only to the exact host.
+    host_only: bool,
+    path: String,
+    /// `None` for a session cookie.
+    expires: Option<DateTime<Utc>>,
+    secure: bool,
+}
+
+impl Cookie {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.is_some_and(|t| t <= now)
+    }
+
+    fn matches(&self, host: &str, path: &str, secure: bool) -> bool {
+        let domain_ok = if self.host_only {
+            self.domain == host
+        } else {
+            domain_match(host, &self.domain)
+        };
+
+        domain_ok && path_match(path, &self.path) && (secure || !self.secure)
+    }
+}
+
+/// RFC 6265 5.1.3. The host is not an IP address in our case.
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain || (host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.'))
+}
+
+/// RFC 6265 5.1.4.
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The directory of request path, RFC 6265 5.1.4.
+fn default_path(request_path: &str) -> String {
+    match request_path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(i) => request_path[..i].to_string(),
+    }
+}
+
+/// Parse cookie date like "Wed, 21 Oct 2015 07:28:00 GMT" or the older "Wed, 21-Oct-2015 07:28:00 GMT".
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    [
+        "%a, %d %b %Y %H:%M:%S GMT",
+        "%a, %d-%b-%Y %H:%M:%S GMT",
+        "%a, %d-%b-%y %H:%M:%S GMT",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(text.trim(), format).ok())
+    .map(|t| Utc.from_utc_datetime(&t))
+}
+
+/// Saved with campus sessions, so it is serializable.
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    /// Store all `Set-Cookie` headers in a response of `host` and `path`.
+    pub fn store_response(&mut self, host: &str, path: &str, headers: &HeaderMap) {
+        for value in headers.get_all(SET_COOKIE) {
+            if let Ok(line) = value.to_str() {
+                self.store(host, path, line);
+            }
+        }
+    }
+
+    /// Store a `Set-Cookie` header line, like
+    /// "JSESSIONID=xSiUKpqm0lmjhDXB41_hhyxiNUa69u4xMnHkFOFS61E6VZ6Osp7S!-1266297679; path=/; HttpOnly"
+    pub fn store(&mut self, host: &str, path: &str, line: &str) {
+        self.store_at(host, path, line, Utc::now())
+    }
+
+    fn store_at(&mut self, host: &str, path: &str, line: &str, now: DateTime<Utc>) {
+        let mut parts = line.split(';');
+        // A cookie without '=' is ignored, RFC 6265 5.2.
+        let Some((name, value)) = parts.next().and_then(|pair| pair.split_once('=')) else {
+            return;
+        };
+        let (name, value) = (name.trim(), value.trim());
+        if name.is_empty() {
+            return;
+        }
+
+        let mut cookie = Cookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            domain: host.to_string(),
+            host_only: true,
+            path: default_path(path),
+            expires: None,
+            secure: false,
+        };
+        let mut max_age = None;
+        for attribute in parts {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim();
+
+            match key.trim().to_ascii_lowercase().as_str() {
+                "expires" => {
+                    if let Some(t) = parse_date(value) {
+                        cookie.expires = Some(t);
+                    }
+                }
+                "max-age" => {
+                    if let Ok(seconds) = value.parse::<i64>() {
+                        max_age = Some(seconds);
+                    }
+                }
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                    // Reject cookies for other sites.
+                    if !domain_match(host, &domain) {
+                        return;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "secure" => cookie.secure = true,
+                _ => {}
+            }
+        }
+        // Max-Age takes precedence over Expires.
+        if let Some(seconds) = max_age {
+            cookie.expires = Some(if seconds <= 0 {
+                DateTime::<Utc>::MIN_UTC
+            } else {
+                now + Duration::seconds(seconds)
+            });
+        }
+
+        let old = self
+            .cookies
+            .iter()
+            .position(|c| c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path);
+        match (old, cookie.is_expired(now)) {
+            // An expired cookie only deletes the old one.
+            (Some(i), true) => {
+                self.cookies.remove(i);
+            }
+            // Keep the position, which is the order of creation.
+            (Some(i), false) => self.cookies[i] = cookie,
+            (None, true) => {}
+            (None, false) => self.cookies.push(cookie),
+        }
+    }
+
+    /// Value of `Cookie` header for a request to `host` and `path`. `None` if nothing to send.
+    pub fn header_value(&self, host: &str, path: &str, secure: bool) -> Option<String> {
+        self.header_value_at(host, path, secure, Utc::now())
+    }
+
+    fn header_value_at(&self, host: &str, path: &str, secure: bool, now: DateTime<Utc>) -> Option<String> {
+        let mut cookies: Vec<&Cookie> = self
+            .cookies
+            .iter()
+            .filter(|c| !c.is_expired(now) && c.matches(host, path, secure))
+            .collect();
+        if cookies.is_empty() {
+            return None;
+        }
+        // Cookies with longer paths are listed first, and the older first for the same length.
+        // The sort is stable and cookies are stored in order of creation.
+        cookies.sort_by_key(|c| std::cmp::Reverse(c.path.len()));
+
+        let pairs: Vec<String> = cookies.iter().map(|c| format!("{}={}", c.name, c.value)).collect();
+        Some(pairs.join("; "))
+    }
+
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+
+    /// Remove cookies set for `host` only, such as the login ticket of authserver.
+    pub fn remove_host(&mut self, host: &str) {
+        self.cookies.retain(|c| c.domain != host);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const HOST: &str = "authserver.sit.edu.cn";
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 9, 1, 8, 0, 0).unwrap()
+    }
+
+    fn jar(lines: &[&str]) -> CookieJar {
+        let mut jar = CookieJar::default();
+        for line in lines {
+            jar.store_at(HOST, "/authserver/login", line, now());
+        }
+        jar
+    }
+
+    fn header(jar: &CookieJar, host: &str, path: &str) -> Option<String> {
+        jar.header_value_at(host, path, true, now())
+    }
+
+    #[test]
+    fn test_multiple_cookies() {
+        let jar = jar(&[
+            "JSESSIONID=abc!-1266297679; path=/authserver; HttpOnly",
+            "route=9f0a",
+            "CASTGC=TGT-1-cas; path=/authserver/; Secure; HttpOnly",
+        ]);
+
+        // `route` has the default path "/authserver".
+        assert_eq!(
+            header(&jar, HOST, "/authserver/index.do").unwrap(),
+            "CASTGC=TGT-1-cas; JSESSIONID=abc!-1266297679; route=9f0a"
+        );
+        assert_eq!(header(&jar, HOST, "/"), None);
+        // Secure cookie is not sent over plain HTTP.
+        assert_eq!(
+            jar.header_value_at(HOST, "/authserver/index.do", false, now()).unwrap(),
+            "JSESSIONID=abc!-1266297679; route=9f0a"
+        );
+    }
+
+    #[test]
+    fn test_cookie_without_attributes() {
+        let jar = jar(&["a=1", "b=", "=ignored", "invalid"]);
+
+        assert_eq!(header(&jar, HOST, "/authserver/x").unwrap(), "a=1; b=");
+    }
+
+    #[test]
+    fn test_replace_and_delete() {
+        let mut jar = jar(&["JSESSIONID=old; path=/", "route=r1; path=/"]);
+
+        jar.store_at(HOST, "/", "JSESSIONID=new; Path=/", now());
+        assert_eq!(header(&jar, HOST, "/").unwrap(), "JSESSIONID=new; route=r1");
+
+        jar.store_at(HOST, "/", "route=; Path=/; Max-Age=0", now());
+        jar.store_at(
+            HOST,
+            "/",
+            "JSESSIONID=x; Path=/; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+            now(),
+        );
+        assert_eq!(header(&jar, HOST, "/"), None);
+    }
+
+    #[test]
+    fn test_expiry() {
+        let mut jar = jar(&[
+            "a=1; path=/; Max-Age=60",
+            "b=2; path=/; Expires=Fri, 01-Sep-2023 09:00:00 GMT",
+            // Max-Age wins.
+            "c=3; path=/; Expires=Fri, 01 Sep 2023 09:00:00 GMT; Max-Age=10",
+        ]);
+
+        assert_eq!(header(&jar, HOST, "/").unwrap(), "a=1; b=2; c=3");
+        let later = now() + Duration::seconds(30);
+        assert_eq!(jar.header_value_at(HOST, "/", true, later).unwrap(), "a=1; b=2");
+        let much_later = now() + Duration::hours(2);
+        assert_eq!(jar.header_value_at(HOST, "/", true, much_later), None);
+
+        jar.clear();
+        assert_eq!(header(&jar, HOST, "/"), None);
+    }
+
+    #[test]
+    fn test_domain() {
+        let jar = jar(&[
+            "host=1; path=/",
+            "campus=2; path=/; Domain=.sit.edu.cn",
+            "other=3; path=/; Domain=example.com",
+        ]);
+
+        assert_eq!(header(&jar, HOST, "/").unwrap(), "host=1; campus=2");
+        assert_eq!(header(&jar, "jwxt.sit.edu.cn", "/").unwrap(), "campus=2");
+        assert_eq!(header(&jar, "example.com", "/"), None);
+        assert_eq!(header(&jar, "notsit.edu.cn", "/"), None);
+    }
+
+    #[test]
+    fn test_remove_host() {
+        let mut jar = jar(&["CASTGC=TGT-1-cas; path=/", "campus=2; path=/; Domain=.sit.edu.cn"]);
+        jar.store_at("jwxt.sit.edu.cn", "/", "JSESSIONID=j; path=/", now());
+
+        jar.remove_host(HOST);
+        assert_eq!(header(&jar, HOST, "/").unwrap(), "campus=2");
+        assert_eq!(header(&jar, "jwxt.sit.edu.cn", "/").unwrap(), "campus=2; JSESSIONID=j");
+    }
+
+    #[test]
+    fn test_path_match() {
+        assert!(path_match("/authserver", "/authserver"));
+        assert!(path_match("/authserver/login", "/authserver"));
+        assert!(path_match("/authserver/login", "/authserver/"));
+        assert!(!path_match("/authserver2", "/authserver"));
+        assert_eq!(default_path("/authserver/login"), "/authserver");
+        assert_eq!(default_path("/login"), "/");
+    }
+}
@@ -0,0 +1,128 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Parse pages of authserver.
+//!
+//! Every function returns `ParseError` rather than panicking, if the page is not what we know.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{Html, Selector};
+
+/// The page doesn't have the expected element, usually because authserver is upgraded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// 登录页缺少元素
+    LoginPageChanged { missing: &'static str },
+    /// 个人主页缺少元素
+    HomePageChanged { missing: &'static str },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::LoginPageChanged { missing } => write!(f, "Unexpected login page, {} is missing", missing),
+            ParseError::HomePageChanged { missing } => write!(f, "Unexpected home page, {} is missing", missing),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+static NAME_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("#auth_siderbar > div.auth_username > span > span").unwrap());
+static HIDDEN_INPUT_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse(r#"input[type="hidden"]"#).unwrap());
+
+/// Parameters in login page, which are submitted with the login form.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LoginPage {
+    /// AES key to encrypt password.
+    pub salt: String,
+    /// Hidden fields of the login form, such as `lt` and `execution`.
+    pub fields: Vec<(String, String)>,
+}
+
+impl LoginPage {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Get the trimmed text of the first element matched, `None` if it's absent or empty.
+pub fn select_text(document: &Html, selector: &Selector) -> Option<String> {
+    document
+        .select(selector)
+        .next()
+        .map(|e| e.text().collect::<String>().trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Get the hidden inputs with name in `form`, `None` if the form is absent.
+pub fn parse_hidden_fields(document: &Html, form: &Selector) -> Option<Vec<(String, String)>> {
+    let form = document.select(form).next()?;
+    let fields = form
+        .select(&HIDDEN_INPUT_SELECTOR)
+        .filter_map(|e| {
+            let name = e.value().attr("name")?;
+            let value = e.value().attr("value").unwrap_or_default();
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect();
+    Some(fields)
+}
+
+/// Search in text by regex, and return the first group.
+pub fn find_first_group(text: &str, regex: &Regex) -> Option<String> {
+    regex.captures(text).map(|c| c[1].to_string())
+}
+
+/// Get person name in home page (`index.do`).
+pub fn parse_person_name(text: &str) -> Result<String, ParseError> {
+    let document = Html::parse_document(text);
+
+    select_text(&document, &NAME_SELECTOR).ok_or(ParseError::HomePageChanged {
+        missing: "auth_username",
+    })
+}
+
+/// Load a saved page in `fixtures/authserver`.
+#[cfg(test)]
+macro_rules! fixture {
+    ($name: literal) => {
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/authserver/", $name))
+    };
+}
+
+#[cfg(test)]
+pub(crate) use fixture;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_person_name() {
+        assert_eq!(parse_person_name(fixture!("index.html")).unwrap(), "张三");
+        assert_eq!(
+            parse_person_name(fixture!("login.html")),
+            Err(ParseError::HomePageChanged {
+                missing: "auth_username"
+            })
+        );
+    }
+}
@@ -16,15 +16,58 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use anyhow::Result;
-use base64::Engine;
-use http::StatusCode;
-use scraper::{Html, Selector};
+use anyhow::{bail, Result};
+use bytes::Bytes;
+use http::{Response, StatusCode, Uri};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use scraper::Html;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
+use crate::model::captcha_sample::{CaptchaSample, CaptchaSource};
+
+use super::client::resolve_location;
 use super::constants::*;
+use super::cookie::CookieJar;
+use super::parser::{self, LoginPage, ParseError};
+use super::solver::CaptchaSolver;
+use super::strategy::{self, CaptchaKind, LoginStrategy};
 use super::Session;
 
+/// Reasons why authserver refuses to login, which are reported to user.
+#[derive(Debug)]
+pub enum LoginError {
+    /// 用户名或密码错误, 以及账户被冻结等. 附带 authserver 给出的提示
+    Rejected(String),
+    /// 验证码识别错误
+    CaptchaFailed,
+    /// 需要无法自动完成的验证码, 如滑块验证码
+    UnsupportedCaptcha,
+    /// 页面结构与预期不符, 可能是学校更新了统一认证系统
+    PageChanged(ParseError),
+    /// 访问其他系统时没有登录, 或登录已过期
+    NotLoggedIn,
+}
+
+impl std::fmt::Display for LoginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoginError::Rejected(message) => write!(f, "{} (from authserver)", message),
+            LoginError::CaptchaFailed => write!(f, "Failed to recognize captcha"),
+            LoginError::UnsupportedCaptcha => write!(f, "Captcha is required but not supported"),
+            LoginError::PageChanged(e) => write!(f, "{}", e),
+            LoginError::NotLoggedIn => write!(f, "Not logged in, or the login has expired"),
+        }
+    }
+}
+
+impl std::error::Error for LoginError {}
+
+impl From<ParseError> for LoginError {
+    fn from(e: ParseError) -> Self {
+        LoginError::PageChanged(e)
+    }
+}
+
 #[derive(Clone)]
 pub struct Credential {
     /// 学号
@@ -39,13 +82,22 @@ impl Credential {
     }
 }
 
+/// OCR retries before asking the user, if `captcha_ocr_retry` is not configured.
+const DEFAULT_OCR_RETRY: u32 = 2;
+
 pub struct PortalConnector {
     credential: Option<Credential>,
+    solver: Option<Box<dyn CaptchaSolver>>,
+    ocr_retry: u32,
 }
 
 impl PortalConnector {
     pub fn new() -> Self {
-        Self { credential: None }
+        Self {
+            credential: None,
+            solver: None,
+            ocr_retry: DEFAULT_OCR_RETRY,
+        }
     }
 
     pub fn user(self, credential: Credential) -> Self {
@@ -55,6 +107,19 @@ impl PortalConnector {
         }
     }
 
+    /// Ask the solver when OCR fails, or the login fails with wrong captcha.
+    pub fn captcha_solver(self, solver: Box<dyn CaptchaSolver>) -> Self {
+        Self {
+            solver: Some(solver),
+            ..self
+        }
+    }
+
+    /// Times to retry OCR before asking the solver.
+    pub fn ocr_retry(self, ocr_retry: u32) -> Self {
+        Self { ocr_retry, ..self }
+    }
+
     pub async fn bind<T>(self, stream: T) -> Result<Portal<T>>
     where
         T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
@@ -64,17 +129,26 @@ impl PortalConnector {
         // Prepare client configuration which used to handshake
         let config = crate::authserver::tls_get().clone();
         let connector = tokio_rustls::TlsConnector::from(config);
-        let server_name = "authserver.sit.edu.cn".try_into().unwrap();
+        let server_name = SERVER_NAME.try_into().unwrap();
 
         // Bind IO with TLS config (do some TLS initializing operation)
         // Maybe the connect function should not be a async function?
-        let stream = connector.connect(server_name, stream).await.unwrap();
+        let stream = connector.connect(server_name, stream).await?;
         let session = Session::create(stream).await?;
 
-        Ok(Portal { credential, session })
+        Ok(Portal {
+            credential,
+            session,
+            solver: self.solver,
+            ocr_retry: self.ocr_retry,
+            captcha_samples: Vec::new(),
+        })
     }
 }
 
+/// Redirections in service login: authserver -> service with ticket -> service home page, and a few more.
+const SERVICE_MAX_REDIRECTION: u8 = 10;
+
 /// 统一认证模块
 pub struct Portal<T>
 where
@@ -83,20 +157,12 @@ where
     credential: Credential,
     /// 登录会话
     session: Session<T>,
-}
-
-/// Search in text by regex, and return the first group.
-#[macro_export]
-macro_rules! regex_find {
-    ($text: expr, $pattern: expr) => {{
-        let re = regex::Regex::new($pattern).unwrap();
-        re.captures($text).map(|r| r[1].to_string())
-    }};
-}
-
-struct IndexParameter {
-    aes_key: String,
-    lt: String,
+    /// OCR 失败后的验证码识别方式
+    solver: Option<Box<dyn CaptchaSolver>>,
+    /// OCR 重试次数
+    ocr_retry: u32,
+    /// 登录过程中提交的验证码
+    captcha_samples: Vec<CaptchaSample>,
 }
 
 impl<T> Portal<T>
@@ -104,17 +170,15 @@ where
     T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
 {
     /// Check whether captcha is need or not.
-    async fn check_need_captcha(&mut self, account: &str) -> Result<bool> {
-        let url = format!("{}?username={}&pwdEncrypt2=pwdEncryptSalt", NEED_CAPTCHA_URI, account);
-        let response = self.session.get(&url).await?;
+    async fn check_need_captcha(&mut self, strategy: &dyn LoginStrategy, account: &str) -> Result<CaptchaKind> {
+        let response = self.session.get(&strategy.need_captcha_uri(account)).await?;
 
-        let content = response.body();
-        Ok(content.eq_ignore_ascii_case(b"true"))
+        Ok(strategy.parse_need_captcha(response.body()))
     }
 
     /// Fetch captcha image.
-    async fn fetch_captcha(&mut self) -> Result<Vec<u8>> {
-        let response = self.session.get(CAPTCHA_URI).await?;
+    async fn fetch_captcha(&mut self, strategy: &dyn LoginStrategy) -> Result<Vec<u8>> {
+        let response = self.session.get(strategy.captcha_uri()).await?;
         let content = response.body();
         return Ok(content.to_vec());
     }
@@ -127,108 +191,148 @@ where
     pub async fn get_person_name(&mut self) -> Result<String> {
         let response = self.session.get_with_redirection(AUTH_SERVER_HOME_URI, 5).await?;
         let text = String::from_utf8(response.body().to_vec())?;
-        let document = Html::parse_document(&text);
 
-        let name: String = document
-            .select(&Selector::parse("#auth_siderbar > div.auth_username > span > span").unwrap())
-            .next()
-            .map(|e| e.text().collect())
-            .unwrap_or_default();
-        return Ok(name.trim().to_string());
+        Ok(parser::parse_person_name(&text).map_err(LoginError::from)?)
     }
 
-    async fn get_initial_parameters(&mut self) -> Result<IndexParameter> {
+    /// Fetch login page, and find the strategy to login.
+    async fn get_login_page(&mut self) -> Result<(&'static dyn LoginStrategy, LoginPage)> {
         self.session.clear_cookie();
 
         let response = self.session.get(LOGIN_URI).await?;
         let text = response.body().to_vec();
         let text = String::from_utf8(text)?;
 
-        fn get_aes_key(text: &str) -> String {
-            regex_find!(&text, r#"var pwdDefaultEncryptSalt = "(.*?)";"#).unwrap()
-        }
-
-        fn get_lt_field(text: &str) -> String {
-            regex_find!(&text, r#"<input type="hidden" name="lt" value="(.*?)"/>"#).unwrap()
-        }
-
-        let aes_key = get_aes_key(&text);
-        let lt = get_lt_field(&text);
-        Ok(IndexParameter { aes_key, lt })
+        let document = Html::parse_document(&text);
+        let strategy = strategy::detect(&document).map_err(LoginError::from)?;
+        let page = strategy.parse_login_page(&document).map_err(LoginError::from)?;
+        Ok((strategy, page))
     }
 
-    /// When submit password to `authserver.sit.edu.cn`, it's required to do AES and base64 algorithm with
-    /// origin password. We use a key from HTML (generated and changed by `JSESSIONID`) to help with.
-    fn generate_password_string(clear_password: &str, key: &str) -> String {
-        use base64::engine::general_purpose::STANDARD as base64_standard;
-        use block_modes::block_padding::Pkcs7;
-        use block_modes::{BlockMode, Cbc};
-        type Aes128Cbc = Cbc<aes::Aes128, Pkcs7>;
-
-        // Create an AES object.
-        let cipher = Aes128Cbc::new_var(key.as_bytes(), &[0u8; 16]).unwrap();
-        // Concat plaintext: 64 bytes random bytes and original password.
-        let mut content = Vec::new();
-        content.extend_from_slice(&[0u8; 64]);
-        content.extend_from_slice(clear_password.as_bytes());
-
-        // Encrypt with AES and use do base64 encoding.
-        let encrypted_password = cipher.encrypt_vec(&content);
-        base64_standard.encode(encrypted_password)
+    /// Get captcha answer from OCR, or the solver if OCR has failed `ocr_retry + 1` times.
+    async fn solve_captcha(&mut self, image: Vec<u8>, ocr_failures: u32) -> Result<(String, CaptchaSource)> {
+        if ocr_failures <= self.ocr_retry && captcha::is_ready() {
+            return Ok((self.recognize_captcha(image).await?, CaptchaSource::Ocr));
+        }
+        match self.solver.as_mut() {
+            Some(solver) => Ok((solver.solve(image).await?, CaptchaSource::User)),
+            None => Err(LoginError::CaptchaFailed.into()),
+        }
     }
 
-    fn parse_err_message(text: &str) -> String {
-        let document = Html::parse_document(text);
-        let selector = Selector::parse("#msg").unwrap();
-
-        document
-            .select(&selector)
-            .next()
-            .map(|e| e.text().collect())
-            .unwrap_or_default()
+    /// Take the captcha submitted in login with the result, so that they can be saved as samples.
+    pub fn take_captcha_samples(&mut self) -> Vec<CaptchaSample> {
+        std::mem::take(&mut self.captcha_samples)
     }
 
     /// Login on campus official auth-server with student id and password.
     /// Return session if done successfully.
+    ///
+    /// If the captcha is wrong, it starts over with a new login page and captcha, until OCR
+    /// and the solver both fail.
     pub async fn try_login(&mut self) -> Result<()> {
         let credential = self.credential.clone();
-        let IndexParameter { aes_key, lt } = self.get_initial_parameters().await?;
-        let encrypted_password = Self::generate_password_string(&credential.password, &aes_key);
-
-        /* Check if captcha is needed. */
-        let captcha = if self.check_need_captcha(&credential.account).await? {
-            let image = self.fetch_captcha().await?;
-            self.recognize_captcha(image).await?
-        } else {
-            String::new()
-        };
-
-        /* Send login request */
-        let form = vec![
-            ("username", credential.account.as_str()),
-            ("password", &encrypted_password),
-            ("dllt", "userNamePasswordLogin"),
-            ("execution", "e1s1"),
-            ("_eventId", "submit"),
-            ("rmShown", "1"),
-            ("captchaResponse", &captcha),
-            ("lt", &lt),
-        ];
-        // Login post is the last request.
-        // Send `Connection: close` to make the server close the connection actively,
-        // so will the without_shutdown method in the closure in Session::create return.
-        // Then original stream will be returned.
-        let header = vec![("Content-Type", "application/x-www-form-urlencoded")];
-        let response = self.session.post(LOGIN_URI, form, header).await?;
-        if response.status() == StatusCode::FOUND {
-            Ok(())
-        } else {
-            let body = response.body().to_vec();
-            let text = String::from_utf8(body)?;
-            Err(anyhow::anyhow!("{} (from authserver)", Self::parse_err_message(&text)))
+        let mut ocr_failures = 0;
+
+        loop {
+            let (strategy, page) = self.get_login_page().await?;
+            tracing::debug!("Login with {} strategy.", strategy.name());
+            let encrypted_password = strategy
+                .encrypt_password(&credential.password, &page.salt)
+                .map_err(LoginError::from)?;
+
+            /* Check if captcha is needed. */
+            let captcha = match self.check_need_captcha(strategy, &credential.account).await? {
+                CaptchaKind::None => None,
+                CaptchaKind::Image => {
+                    let image = self.fetch_captcha(strategy).await?;
+                    let (answer, source) = self.solve_captcha(image.clone(), ocr_failures).await?;
+                    Some(CaptchaSample {
+                        image,
+                        answer,
+                        source,
+                        correct: None,
+                    })
+                }
+                CaptchaKind::Slider => return Err(LoginError::UnsupportedCaptcha.into()),
+            };
+            let answer = captcha.as_ref().map(|c| c.answer.as_str()).unwrap_or_default();
+
+            /* Send login request */
+            let form = strategy.login_form(&page, &credential.account, &encrypted_password, answer);
+            let form = form.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+            // Login post is the last request.
+            // Send `Connection: close` to make the server close the connection actively,
+            // so will the without_shutdown method in the closure in Session::create return.
+            // Then original stream will be returned.
+            let header = vec![("Content-Type", "application/x-www-form-urlencoded")];
+            let response = self.session.post(LOGIN_URI, form, header).await?;
+
+            let error = if response.status() == StatusCode::FOUND {
+                None
+            } else {
+                let body = response.body().to_vec();
+                let text = String::from_utf8(body)?;
+                let message = strategy
+                    .parse_error_message(&Html::parse_document(&text))
+                    .map_err(LoginError::from)?;
+                Some(message)
+            };
+            let wrong_captcha = captcha.is_some() && error.as_ref().is_some_and(|m| m.contains("验证码"));
+
+            if let Some(mut sample) = captcha {
+                sample.correct = match &error {
+                    None => Some(true),
+                    Some(_) if wrong_captcha => Some(false),
+                    // Other errors such as wrong password, we don't know whether the captcha is right.
+                    Some(_) => None,
+                };
+                let source = sample.source;
+                self.captcha_samples.push(sample);
+
+                if wrong_captcha {
+                    if source == CaptchaSource::User {
+                        return Err(LoginError::CaptchaFailed.into());
+                    }
+                    ocr_failures += 1;
+                    continue;
+                }
+            }
+            return match error {
+                None => Ok(()),
+                Some(message) => Err(LoginError::Rejected(message).into()),
+            };
         }
     }
 
+    /// Login on a downstream campus system, such as jwxt, by CAS service ticket.
+    ///
+    /// `try_login` should be done before. Authserver issues a ticket and redirects to `service_url` with it,
+    /// then the system accepts the ticket and sets its own session cookie.
+    pub async fn service_login(&mut self, service_url: &str) -> Result<ServiceSession<'_, T>> {
+        let service = utf8_percent_encode(service_url, NON_ALPHANUMERIC);
+        let url = format!("{}?service={}", LOGIN_URI, service);
+        let (final_url, response) = self.session.navigate(&url, SERVICE_MAX_REDIRECTION).await?;
+
+        // Authserver shows login page if the TGT is absent or expired.
+        let target: Uri = final_url.parse()?;
+        if target.host().unwrap_or(SERVER_NAME) == SERVER_NAME && target.path() == LOGIN_URI {
+            return Err(LoginError::NotLoggedIn.into());
+        }
+        if !response.status().is_success() {
+            bail!("Service {} responded {} on login.", service_url, response.status());
+        }
+        Ok(ServiceSession {
+            session: &mut self.session,
+            base: final_url,
+        })
+    }
+
+    /// Cookies of campus systems logged in by `service_login`, without the login ticket of authserver.
+    pub fn export_cookies(&self) -> CookieJar {
+        self.session.export_cookies()
+    }
+
     pub async fn shutdown(mut self) -> Result<T> {
         self.session.request_close_connection().await?;
 
@@ -245,3 +349,104 @@ where
         }
     }
 }
+
+/// Session on a downstream campus system, which is logged in by `Portal::service_login`.
+///
+/// Relative URLs are resolved against the page where service login ends, and redirections are followed.
+pub struct ServiceSession<'a, T>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    session: &'a mut Session<T>,
+    base: String,
+}
+
+impl<'a, T> ServiceSession<'a, T>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    /// Continue a service session, on a session restored from saved cookies for example.
+    pub fn new(session: &'a mut Session<T>, base: String) -> Self {
+        ServiceSession { session, base }
+    }
+
+    /// The page where service login ends.
+    pub fn url(&self) -> &str {
+        &self.base
+    }
+
+    pub async fn get(&mut self, url: &str) -> Result<Response<Bytes>> {
+        let url = resolve_location(&self.base, url)?;
+        self.session.get_with_redirection(&url, SERVICE_MAX_REDIRECTION).await
+    }
+
+    pub async fn post(
+        &mut self,
+        url: &str,
+        form: Vec<(&str, &str)>,
+        header: Vec<(&str, &str)>,
+    ) -> Result<Response<Bytes>> {
+        let url = resolve_location(&self.base, url)?;
+        self.session.post(&url, form, header).await
+    }
+}
+
+/// Bind a portal to the mock authserver, and connect other hosts to it too.
+#[cfg(test)]
+pub async fn bind_mock(
+    server: &authserver_mock::MockAuthserver,
+    credential: Credential,
+) -> Portal<tokio::io::DuplexStream> {
+    crate::authserver::tls_set_roots(authserver_mock::root_store());
+
+    let mut portal = PortalConnector::new()
+        .user(credential)
+        .bind(server.connect())
+        .await
+        .unwrap();
+    let connector = super::client::MockConnector(server.clone());
+    portal.session.set_connector(Box::new(connector));
+    portal
+}
+
+#[cfg(test)]
+mod test {
+    use authserver_mock::MockAuthserver;
+    use tokio::io::DuplexStream;
+
+    use super::*;
+
+    const ACCOUNT: &str = "2112340101";
+    const PASSWORD: &str = "password";
+    const JWXT_SSO: &str = "https://jwxt.sit.edu.cn/jwxt/sso";
+
+    async fn connect(server: &MockAuthserver) -> Portal<DuplexStream> {
+        let credential = Credential::new(ACCOUNT.to_string(), PASSWORD.to_string());
+        bind_mock(server, credential).await
+    }
+
+    #[tokio::test]
+    async fn test_service_login() {
+        let server = MockAuthserver::new().user(ACCOUNT, PASSWORD, "张三");
+        let mut portal = connect(&server).await;
+        portal.try_login().await.unwrap();
+
+        let mut service = portal.service_login(JWXT_SSO).await.unwrap();
+        assert_eq!(service.url(), "https://jwxt.sit.edu.cn/jwxt/home.do");
+        let response = service.get("profile.do").await.unwrap();
+        let text = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(text.contains("张三"), "{}", text);
+
+        // The TGT is still valid for another service login.
+        assert!(portal.service_login(JWXT_SSO).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_service_login_without_login() {
+        let server = MockAuthserver::new().user(ACCOUNT, PASSWORD, "张三");
+        let mut portal = connect(&server).await;
+
+        let error = portal.service_login(JWXT_SSO).await.err().unwrap();
+        assert!(matches!(error.downcast::<LoginError>(), Ok(LoginError::NotLoggedIn)));
+    }
+}
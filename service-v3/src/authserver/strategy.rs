@@ -0,0 +1,138 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Login strategies for different versions of authserver.
+//!
+//! A strategy knows how to read the login page and build the login form of one version, while
+//! `Portal` drives the requests. When the campus upgrades authserver, add a strategy here.
+
+use base64::Engine;
+use scraper::Html;
+
+use super::parser::{LoginPage, ParseError};
+
+mod classic;
+mod ids;
+
+/// Captcha required before submitting the login form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptchaKind {
+    None,
+    /// 图片验证码, 可以识别
+    Image,
+    /// 滑块验证码, 暂不支持
+    Slider,
+}
+
+pub trait LoginStrategy: Send + Sync {
+    /// Name in log.
+    fn name(&self) -> &'static str;
+
+    /// Whether the login page is served by the version this strategy handles.
+    fn detect(&self, document: &Html) -> bool;
+
+    /// Read the salt and hidden fields from login page.
+    fn parse_login_page(&self, document: &Html) -> Result<LoginPage, ParseError>;
+
+    /// URI to ask whether captcha is needed for the account.
+    fn need_captcha_uri(&self, account: &str) -> String;
+
+    fn parse_need_captcha(&self, body: &[u8]) -> CaptchaKind;
+
+    /// URI of captcha image.
+    fn captcha_uri(&self) -> &'static str;
+
+    /// Encrypt password by the salt in login page, the same as `encrypt.js` does.
+    ///
+    /// The script concats 64 random characters and the password, and encrypts it with AES-128-CBC
+    /// and a random IV. Server drops the first 64 bytes after decryption, so zeros are OK here.
+    fn encrypt_password(&self, password: &str, salt: &str) -> Result<String, ParseError> {
+        use base64::engine::general_purpose::STANDARD as base64_standard;
+        use block_modes::block_padding::Pkcs7;
+        use block_modes::{BlockMode, Cbc};
+        type Aes128Cbc = Cbc<aes::Aes128, Pkcs7>;
+
+        // The salt must be 16 bytes, or the page is not what we know.
+        let cipher = Aes128Cbc::new_var(salt.as_bytes(), &[0u8; 16])
+            .map_err(|_| ParseError::LoginPageChanged { missing: "salt" })?;
+        let mut content = Vec::new();
+        content.extend_from_slice(&[0u8; 64]);
+        content.extend_from_slice(password.as_bytes());
+
+        let encrypted_password = cipher.encrypt_vec(&content);
+        Ok(base64_standard.encode(encrypted_password))
+    }
+
+    /// Build the login form with hidden fields in page. The password is encrypted already.
+    fn login_form(&self, page: &LoginPage, account: &str, password: &str, captcha: &str) -> Vec<(String, String)>;
+
+    /// Get the error message after a failed login.
+    fn parse_error_message(&self, document: &Html) -> Result<String, ParseError>;
+}
+
+/// Strategies in the order of detection.
+static STRATEGIES: &[&dyn LoginStrategy] = &[&classic::Classic, &ids::Ids];
+
+/// Find the strategy which can handle the login page.
+pub fn detect(document: &Html) -> Result<&'static dyn LoginStrategy, ParseError> {
+    STRATEGIES
+        .iter()
+        .find(|s| s.detect(document))
+        .copied()
+        .ok_or(ParseError::LoginPageChanged { missing: "login form" })
+}
+
+/// Replace the field if it exists in hidden fields, or append it.
+fn set_field(form: &mut Vec<(String, String)>, name: &str, value: &str) {
+    match form.iter_mut().find(|(k, _)| k == name) {
+        Some((_, v)) => *v = value.to_string(),
+        None => form.push((name.to_string(), value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::authserver::parser::fixture;
+
+    fn detect_name(text: &str) -> Option<&'static str> {
+        detect(&Html::parse_document(text)).ok().map(|s| s.name())
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect_name(fixture!("login.html")), Some("classic"));
+        assert_eq!(detect_name(fixture!("login_new_version.html")), Some("ids"));
+        assert_eq!(detect_name(fixture!("index.html")), None);
+    }
+
+    #[test]
+    fn test_set_field() {
+        let mut form = vec![("lt".to_string(), "LT-1".to_string())];
+
+        set_field(&mut form, "lt", "LT-2");
+        set_field(&mut form, "username", "2112340101");
+        assert_eq!(
+            form,
+            vec![
+                ("lt".to_string(), "LT-2".to_string()),
+                ("username".to_string(), "2112340101".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,150 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The login page used for years, with `pwdDefaultEncryptSalt` in script and `lt` in form.
+
+use once_cell::sync::Lazy;
+use regex_macro::regex;
+use scraper::{Html, Selector};
+
+use super::{set_field, CaptchaKind, LoginStrategy};
+use crate::authserver::constants::{CAPTCHA_URI, NEED_CAPTCHA_URI};
+use crate::authserver::parser::{find_first_group, parse_hidden_fields, select_text, LoginPage, ParseError};
+
+static FORM_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("#casLoginForm").unwrap());
+static SCRIPT_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("script").unwrap());
+static MESSAGE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("#msg").unwrap());
+
+pub struct Classic;
+
+impl LoginStrategy for Classic {
+    fn name(&self) -> &'static str {
+        "classic"
+    }
+
+    fn detect(&self, document: &Html) -> bool {
+        document.select(&FORM_SELECTOR).next().is_some()
+    }
+
+    fn parse_login_page(&self, document: &Html) -> Result<LoginPage, ParseError> {
+        let salt = document
+            .select(&SCRIPT_SELECTOR)
+            .find_map(|e| {
+                let script = e.text().collect::<String>();
+                find_first_group(&script, regex!(r#"pwdDefaultEncryptSalt\s*=\s*"(.*?)""#))
+            })
+            .ok_or(ParseError::LoginPageChanged {
+                missing: "pwdDefaultEncryptSalt",
+            })?;
+        let fields = parse_hidden_fields(document, &FORM_SELECTOR).ok_or(ParseError::LoginPageChanged {
+            missing: "#casLoginForm",
+        })?;
+        let page = LoginPage { salt, fields };
+
+        if page.field("lt").is_none() {
+            return Err(ParseError::LoginPageChanged { missing: "lt" });
+        }
+        Ok(page)
+    }
+
+    fn need_captcha_uri(&self, account: &str) -> String {
+        format!("{}?username={}&pwdEncrypt2=pwdEncryptSalt", NEED_CAPTCHA_URI, account)
+    }
+
+    fn parse_need_captcha(&self, body: &[u8]) -> CaptchaKind {
+        if body.eq_ignore_ascii_case(b"true") {
+            CaptchaKind::Image
+        } else {
+            CaptchaKind::None
+        }
+    }
+
+    fn captcha_uri(&self) -> &'static str {
+        CAPTCHA_URI
+    }
+
+    fn login_form(&self, page: &LoginPage, account: &str, password: &str, captcha: &str) -> Vec<(String, String)> {
+        let mut form = page.fields.clone();
+
+        set_field(&mut form, "username", account);
+        set_field(&mut form, "password", password);
+        set_field(&mut form, "captchaResponse", captcha);
+        form
+    }
+
+    fn parse_error_message(&self, document: &Html) -> Result<String, ParseError> {
+        // Make sure it's the login page, or an empty message may be mistaken for "no error".
+        if !self.detect(document) {
+            return Err(ParseError::LoginPageChanged {
+                missing: "#casLoginForm",
+            });
+        }
+        select_text(document, &MESSAGE_SELECTOR).ok_or(ParseError::LoginPageChanged { missing: "#msg" })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::authserver::parser::fixture;
+
+    fn parse(text: &str) -> Html {
+        Html::parse_document(text)
+    }
+
+    #[test]
+    fn test_parse_login_page() {
+        let page = Classic.parse_login_page(&parse(fixture!("login.html"))).unwrap();
+
+        assert_eq!(page.salt, "rjBFAaHsNkKAhpoi");
+        assert_eq!(page.field("lt"), Some("LT-8137926-3hZd0uE9sQ1mUtMpJmDlLqQxGnBcVv-cas"));
+        assert_eq!(page.field("execution"), Some("e1s1"));
+        assert_eq!(page.field("dllt"), Some("userNamePasswordLogin"));
+    }
+
+    #[test]
+    fn test_login_form() {
+        let page = Classic.parse_login_page(&parse(fixture!("login.html"))).unwrap();
+        let form = Classic.login_form(&page, "2112340101", "encrypted", "");
+
+        let field = |name: &str| form.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str());
+        assert_eq!(field("username"), Some("2112340101"));
+        assert_eq!(field("password"), Some("encrypted"));
+        assert_eq!(field("captchaResponse"), Some(""));
+        assert_eq!(field("_eventId"), Some("submit"));
+    }
+
+    #[test]
+    fn test_parse_error_message() {
+        let wrong_password = Classic.parse_error_message(&parse(fixture!("login_wrong_password.html")));
+        let wrong_captcha = Classic.parse_error_message(&parse(fixture!("login_wrong_captcha.html")));
+
+        assert_eq!(wrong_password.unwrap(), "您提供的用户名或者密码有误");
+        assert_eq!(wrong_captcha.unwrap(), "无效的验证码");
+        assert_eq!(
+            Classic.parse_error_message(&parse(fixture!("login.html"))),
+            Err(ParseError::LoginPageChanged { missing: "#msg" })
+        );
+        assert_eq!(
+            Classic.parse_error_message(&parse(fixture!("index.html"))),
+            Err(ParseError::LoginPageChanged {
+                missing: "#casLoginForm"
+            })
+        );
+    }
+}
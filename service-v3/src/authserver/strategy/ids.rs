@@ -0,0 +1,160 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The newer IDS login page, with `pwdEncryptSalt` and a long `execution` token in form, and
+//! slider captcha instead of image.
+
+use once_cell::sync::Lazy;
+use regex_macro::regex;
+use scraper::{Html, Selector};
+
+use super::{set_field, CaptchaKind, LoginStrategy};
+use crate::authserver::parser::{parse_hidden_fields, select_text, LoginPage, ParseError};
+
+/// 检查该用户登录是否需要验证码, 返回 `{"isNeed":false}`
+const NEED_CAPTCHA_URI: &str = "/authserver/checkNeedCaptcha.htl";
+/// 图片验证码
+const CAPTCHA_URI: &str = "/authserver/getCaptcha.htl";
+
+static FORM_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("#pwdFromId").unwrap());
+static SALT_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("#pwdEncryptSalt").unwrap());
+static MESSAGE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("#showErrorTip").unwrap());
+
+pub struct Ids;
+
+impl LoginStrategy for Ids {
+    fn name(&self) -> &'static str {
+        "ids"
+    }
+
+    fn detect(&self, document: &Html) -> bool {
+        document.select(&FORM_SELECTOR).next().is_some()
+    }
+
+    fn parse_login_page(&self, document: &Html) -> Result<LoginPage, ParseError> {
+        let salt = document
+            .select(&SALT_SELECTOR)
+            .next()
+            .and_then(|e| e.value().attr("value"))
+            .map(ToString::to_string)
+            .ok_or(ParseError::LoginPageChanged {
+                missing: "pwdEncryptSalt",
+            })?;
+        let fields = parse_hidden_fields(document, &FORM_SELECTOR)
+            .ok_or(ParseError::LoginPageChanged { missing: "#pwdFromId" })?;
+        let page = LoginPage { salt, fields };
+
+        if page.field("execution").is_none() {
+            return Err(ParseError::LoginPageChanged { missing: "execution" });
+        }
+        Ok(page)
+    }
+
+    fn need_captcha_uri(&self, account: &str) -> String {
+        format!("{}?username={}", NEED_CAPTCHA_URI, account)
+    }
+
+    fn parse_need_captcha(&self, body: &[u8]) -> CaptchaKind {
+        let body = String::from_utf8_lossy(body);
+
+        // The slider is shown whenever captcha is needed.
+        if regex!(r#""isNeed"\s*:\s*true"#).is_match(&body) {
+            CaptchaKind::Slider
+        } else {
+            CaptchaKind::None
+        }
+    }
+
+    fn captcha_uri(&self) -> &'static str {
+        CAPTCHA_URI
+    }
+
+    fn login_form(&self, page: &LoginPage, account: &str, password: &str, captcha: &str) -> Vec<(String, String)> {
+        let mut form = page.fields.clone();
+
+        set_field(&mut form, "username", account);
+        set_field(&mut form, "password", password);
+        set_field(&mut form, "captcha", captcha);
+        form
+    }
+
+    fn parse_error_message(&self, document: &Html) -> Result<String, ParseError> {
+        if !self.detect(document) {
+            return Err(ParseError::LoginPageChanged { missing: "#pwdFromId" });
+        }
+        select_text(document, &MESSAGE_SELECTOR).ok_or(ParseError::LoginPageChanged {
+            missing: "#showErrorTip",
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::authserver::parser::fixture;
+
+    fn parse(text: &str) -> Html {
+        Html::parse_document(text)
+    }
+
+    #[test]
+    fn test_parse_login_page() {
+        let page = Ids
+            .parse_login_page(&parse(fixture!("login_new_version.html")))
+            .unwrap();
+
+        assert_eq!(page.salt, "a1B2c3D4e5F6g7H8");
+        assert_eq!(
+            page.field("execution"),
+            Some("9f6d0c2e-2b0e-4b1a-8b7e-3b1f4f0a6e21_ZXlKaGJHY2lPaUpJVXpVeE1pSjkuZXlKcWRHa2lP")
+        );
+        assert_eq!(page.field("cllt"), Some("userNameLogin"));
+    }
+
+    #[test]
+    fn test_login_form() {
+        let page = Ids
+            .parse_login_page(&parse(fixture!("login_new_version.html")))
+            .unwrap();
+        let form = Ids.login_form(&page, "2112340101", "encrypted", "");
+
+        // The empty hidden password is replaced rather than duplicated.
+        assert_eq!(form.iter().filter(|(k, _)| k == "password").count(), 1);
+        assert!(form.contains(&("password".to_string(), "encrypted".to_string())));
+        assert!(form.contains(&("_eventId".to_string(), "submit".to_string())));
+    }
+
+    #[test]
+    fn test_parse_need_captcha() {
+        assert_eq!(Ids.parse_need_captcha(br#"{"isNeed":false}"#), CaptchaKind::None);
+        assert_eq!(Ids.parse_need_captcha(br#"{"isNeed": true}"#), CaptchaKind::Slider);
+    }
+
+    #[test]
+    fn test_parse_error_message() {
+        let message = Ids.parse_error_message(&parse(fixture!("login_new_version_wrong_password.html")));
+
+        assert_eq!(message.unwrap(), "该账号非首次登录，请输入正确的密码");
+        assert_eq!(
+            Ids.parse_error_message(&parse(fixture!("login_new_version.html"))),
+            Err(ParseError::LoginPageChanged {
+                missing: "#showErrorTip"
+            })
+        );
+    }
+}
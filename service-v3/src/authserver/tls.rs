@@ -23,26 +23,30 @@ use rustls::ClientConfig;
 
 static TLS_CONFIG: OnceCell<Arc<ClientConfig>> = OnceCell::new();
 
-fn generate_tls_config() -> ClientConfig {
-    fn default_cert_store() -> rustls::RootCertStore {
-        let mut store = rustls::RootCertStore::empty();
-
-        store.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
-            rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
-        }));
-        store
-    }
-
-    fn default_client_config() -> ClientConfig {
-        ClientConfig::builder()
-            .with_safe_defaults()
-            .with_root_certificates(default_cert_store())
-            .with_no_client_auth()
-    }
-
-    default_client_config()
+fn default_cert_store() -> rustls::RootCertStore {
+    let mut store = rustls::RootCertStore::empty();
+
+    store.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
+    }));
+    store
+}
+
+fn generate_tls_config(roots: rustls::RootCertStore) -> ClientConfig {
+    ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth()
+}
+
+/// Trust `roots` instead of the webpki roots, such as the CA of a mock authserver in tests.
+///
+/// It takes effect only if called before the first `get`, and returns false otherwise.
+#[cfg(test)]
+pub fn set_roots(roots: rustls::RootCertStore) -> bool {
+    TLS_CONFIG.set(Arc::new(generate_tls_config(roots))).is_ok()
 }
 
 pub fn get() -> &'static Arc<ClientConfig> {
-    TLS_CONFIG.get_or_init(|| Arc::new(generate_tls_config()))
+    TLS_CONFIG.get_or_init(|| Arc::new(generate_tls_config(default_cert_store())))
 }
@@ -0,0 +1,184 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Campus systems behind authserver, which are reached by `Portal::service_login`.
+//!
+//! Systems are logged in during kite login, over the authserver connection relayed by the app, and their
+//! cookies are saved. Later requests restore the sessions from kite-server, so OA password is never kept.
+
+use std::collections::HashMap;
+use std::future::Future;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use scraper::ElementRef;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::authserver::{CookieJar, LoginError, Portal, ServiceSession, Session, Transport};
+use crate::model::campus_session;
+
+pub mod card;
+pub mod jwxt;
+pub mod library;
+pub mod second_class;
+
+/// Systems logged in on kite login.
+const SERVICES: [&str; 4] = [
+    jwxt::SERVICE_URL,
+    library::SERVICE_URL,
+    card::SERVICE_URL,
+    second_class::SERVICE_URL,
+];
+
+/// Nothing is saved before the first refresh, which is reported as `not_found`.
+#[derive(Debug)]
+pub struct NotSaved;
+
+impl std::fmt::Display for NotSaved {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Nothing is saved, please refresh to fetch it")
+    }
+}
+
+impl std::error::Error for NotSaved {}
+
+/// Fetch from a campus system and save if `refresh`, or get the data saved last time, with the update time.
+pub async fn refresh_or_get<T>(
+    refresh: bool,
+    fetch: impl Future<Output = Result<(T, DateTime<Local>)>>,
+    get: impl Future<Output = Result<Option<(T, DateTime<Local>)>>>,
+) -> Result<(T, DateTime<Local>)> {
+    if refresh {
+        fetch.await
+    } else {
+        get.await?.ok_or_else(|| NotSaved.into())
+    }
+}
+
+/// Text in an element, trimmed.
+fn element_text(element: ElementRef) -> String {
+    element.text().collect::<String>().trim().to_string()
+}
+
+/// Load a saved response of a system in `fixtures`, like `fixture!("jwxt", "score.json")`.
+#[cfg(test)]
+macro_rules! fixture {
+    ($system: literal, $name: literal) => {
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/", $system, "/", $name))
+    };
+}
+
+#[cfg(test)]
+pub(crate) use fixture;
+
+#[derive(Default, Serialize, Deserialize)]
+struct SavedSession {
+    cookie_jar: CookieJar,
+    /// Service URL -> the page where service login ends.
+    services: HashMap<String, String>,
+}
+
+/// Login campus systems on a portal logged in, and save the sessions of `uid`.
+///
+/// A system failing to login is skipped, which reports `LoginError::NotLoggedIn` on use.
+pub async fn save_sessions<T>(db: &PgPool, uid: i32, portal: &mut Portal<T>) -> Result<()>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let mut services = HashMap::new();
+    for service_url in SERVICES {
+        match portal.service_login(service_url).await {
+            Ok(service) => {
+                services.insert(service_url.to_string(), service.url().to_string());
+            }
+            Err(e) => tracing::warn!("Failed to login {}: {:?}", service_url, e),
+        }
+    }
+    let saved = SavedSession {
+        cookie_jar: portal.export_cookies(),
+        services,
+    };
+    campus_session::save(db, uid, serde_json::to_value(saved)?).await
+}
+
+/// Sessions on campus systems restored for a user.
+pub struct CampusSession {
+    session: Session<Box<dyn Transport>>,
+    services: HashMap<String, String>,
+}
+
+impl CampusSession {
+    /// Restore the sessions saved on the last login of `uid`.
+    pub async fn load(db: &PgPool, uid: i32) -> Result<Self> {
+        let saved = campus_session::get(db, uid).await?.ok_or(LoginError::NotLoggedIn)?;
+
+        Ok(Self::restore(serde_json::from_value(saved)?))
+    }
+
+    fn restore(saved: SavedSession) -> Self {
+        CampusSession {
+            session: Session::restore(saved.cookie_jar),
+            services: saved.services,
+        }
+    }
+
+    /// Session on the system of `service_url`, which is one of `SERVICES`.
+    pub fn service(&mut self, service_url: &str) -> Result<ServiceSession<'_, Box<dyn Transport>>> {
+        let base = self.services.get(service_url).ok_or(LoginError::NotLoggedIn)?;
+
+        Ok(ServiceSession::new(&mut self.session, base.clone()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use authserver_mock::MockAuthserver;
+
+    use crate::authserver::{bind_mock, Credential, MockConnector};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_restore() {
+        let server = MockAuthserver::new().user("2112340101", "password", "张三");
+        let credential = Credential::new("2112340101".to_string(), "password".to_string());
+        let mut portal = bind_mock(&server, credential).await;
+        portal.try_login().await.unwrap();
+
+        let service = portal.service_login(jwxt::SERVICE_URL).await.unwrap();
+        let saved = SavedSession {
+            services: HashMap::from([(jwxt::SERVICE_URL.to_string(), service.url().to_string())]),
+            cookie_jar: portal.export_cookies(),
+        };
+        let saved = serde_json::from_value(serde_json::to_value(saved).unwrap()).unwrap();
+
+        let mut campus = CampusSession::restore(saved);
+        campus.session.set_connector(Box::new(MockConnector(server)));
+        let mut service = campus.service(jwxt::SERVICE_URL).unwrap();
+        let response = service.get("/jwxt/profile.do").await.unwrap();
+        let text = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(text.contains("张三"), "{}", text);
+
+        // Without the login ticket, systems not logged in can not be reached.
+        assert!(campus.service(library::SERVICE_URL).is_err());
+        let error = campus.session.get("/authserver/index.do").await.err().unwrap();
+        assert!(matches!(error.downcast::<LoginError>(), Ok(LoginError::NotLoggedIn)));
+    }
+}
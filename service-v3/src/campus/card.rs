@@ -0,0 +1,168 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! 校园卡系统 (card.sit.edu.cn)
+
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::authserver::ServiceSession;
+use crate::model::card::{categorize, Transaction};
+
+/// Service URL registered on authserver.
+pub const SERVICE_URL: &str = "https://card.sit.edu.cn/cassyno/index";
+
+pub const TRANSACTION_URI: &str = "/Report/GetPersonTrjn";
+
+/// Transactions per page when fetching.
+const PAGE_SIZE: usize = 100;
+/// Stop fetching after so many pages, in case the response is wrong.
+const MAX_PAGE: usize = 50;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct RawTransaction {
+    /// 发生时间, 如 "2023-03-06 11:45:12"
+    occtime: String,
+    /// 交易金额, 数字或字符串
+    tranamt: Value,
+    /// 卡余额
+    cardbal: Value,
+    /// 商户名称
+    #[serde(default)]
+    mercname: String,
+    /// 交易名称
+    #[serde(default)]
+    tranname: String,
+}
+
+#[derive(Deserialize)]
+struct TransactionResponse {
+    total: usize,
+    rows: Vec<RawTransaction>,
+}
+
+fn to_f32(value: &Value) -> Option<f32> {
+    match value {
+        Value::Number(n) => n.as_f64().map(|n| n as f32),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+impl TryFrom<RawTransaction> for Transaction {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawTransaction) -> Result<Self> {
+        let time = NaiveDateTime::parse_from_str(raw.occtime.trim(), "%Y-%m-%d %H:%M:%S")
+            .with_context(|| format!("Unexpected transaction time: {}", raw.occtime))?;
+        let ts = Local
+            .from_local_datetime(&time)
+            .earliest()
+            .with_context(|| format!("Invalid local time: {}", time))?;
+        let amount = to_f32(&raw.tranamt).with_context(|| format!("Unexpected amount: {}", raw.tranamt))?;
+        let balance = to_f32(&raw.cardbal).unwrap_or_default();
+
+        let merchant = raw.mercname.trim().to_string();
+        Ok(Transaction {
+            ts,
+            amount,
+            balance,
+            category: categorize(&merchant, amount).to_string(),
+            merchant,
+            kind: raw.tranname.trim().to_string(),
+        })
+    }
+}
+
+/// Parse the JSON response of `TRANSACTION_URI`, returning the transactions and the total count.
+pub fn parse(text: &str) -> Result<(Vec<Transaction>, usize)> {
+    let response: TransactionResponse = serde_json::from_str(text).context("Unexpected transaction response")?;
+    let transactions = response
+        .rows
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<Result<_>>()?;
+
+    Ok((transactions, response.total))
+}
+
+/// Fetch transactions between `from` and `to`, both inclusive.
+pub async fn fetch<T>(session: &mut ServiceSession<'_, T>, from: NaiveDate, to: NaiveDate) -> Result<Vec<Transaction>>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let (from, to) = (from.format("%Y-%m-%d").to_string(), to.format("%Y-%m-%d").to_string());
+    let rows = PAGE_SIZE.to_string();
+    let header = vec![("Content-Type", "application/x-www-form-urlencoded")];
+
+    let mut result = Vec::new();
+    for page in 1..=MAX_PAGE {
+        let page = page.to_string();
+        let form = vec![
+            ("sdate", from.as_str()),
+            ("edate", to.as_str()),
+            ("page", page.as_str()),
+            ("rows", rows.as_str()),
+        ];
+        let response = session.post(TRANSACTION_URI, form, header.clone()).await?;
+        let text = String::from_utf8(response.body().to_vec())?;
+
+        let (transactions, total) = parse(&text)?;
+        let is_empty = transactions.is_empty();
+        result.extend(transactions);
+        if is_empty || result.len() >= total {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::campus::fixture;
+
+    use super::*;
+    use crate::model::card::{CATEGORY_BATHHOUSE, CATEGORY_CANTEEN, CATEGORY_OTHER, CATEGORY_RECHARGE};
+
+    #[test]
+    fn test_parse() {
+        let text = fixture!("card", "transaction.json");
+        let (transactions, total) = parse(text).unwrap();
+
+        assert_eq!(total, 5);
+        assert_eq!(transactions.len(), 5);
+        assert_eq!(transactions[0].category, CATEGORY_BATHHOUSE);
+        assert_eq!(transactions[2].merchant, "一食堂二楼");
+        assert_eq!(transactions[2].amount, -12.5);
+        assert_eq!(transactions[2].category, CATEGORY_CANTEEN);
+        assert_eq!(
+            transactions[2].ts.naive_local(),
+            NaiveDateTime::parse_from_str("2023-03-06 11:45:12", "%Y-%m-%d %H:%M:%S").unwrap()
+        );
+        assert_eq!(transactions[3].category, CATEGORY_RECHARGE);
+        // Amounts in strings
+        assert_eq!(transactions[4].amount, -1.2);
+        assert_eq!(transactions[4].balance, 23.3);
+        assert_eq!(transactions[4].category, CATEGORY_OTHER);
+        assert!(parse("<html></html>").is_err());
+    }
+}
@@ -0,0 +1,42 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! 教务系统 (jwxt.sit.edu.cn)
+
+use anyhow::{bail, Result};
+
+pub mod exam;
+pub mod score;
+pub mod timetable;
+
+/// Service URL registered on authserver.
+pub const SERVICE_URL: &str = "https://jwxt.sit.edu.cn/sso/jziotlogin";
+
+/// Term should be 2000 - 2100, and semester 1 or 2.
+pub fn is_valid_term(year: i32, semester: i32) -> bool {
+    (2000..=2100).contains(&year) && [1, 2].contains(&semester)
+}
+
+/// Semester code used in forms, `xqm`.
+fn semester_code(semester: i32) -> Result<&'static str> {
+    match semester {
+        1 => Ok("3"),
+        2 => Ok("12"),
+        _ => bail!("Invalid semester: {}", semester),
+    }
+}
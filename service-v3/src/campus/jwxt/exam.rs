@@ -0,0 +1,148 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! 考试信息查询
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Deserialize;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::authserver::ServiceSession;
+use crate::model::classroom_browser::split_room_title;
+use crate::model::exam::Exam;
+
+pub const EXAM_URI: &str = "/jwglxt/kwgl/kscx_cxXsksxxIndex.html?doType=query&gnmkdm=N358105";
+
+#[derive(Deserialize)]
+struct RawExam {
+    /// 课程名称
+    kcmc: String,
+    /// 课程号
+    #[serde(default)]
+    kch: String,
+    /// 考试名称
+    #[serde(default)]
+    ksmc: String,
+    /// 考试时间, 如 "2023-06-20(09:00-11:00)"
+    #[serde(default)]
+    kssj: String,
+    /// 场地名称
+    #[serde(default)]
+    cdmc: String,
+    /// 场地校区名称
+    #[serde(default)]
+    cdxqmc: String,
+    /// 座位号
+    #[serde(default)]
+    zwh: String,
+}
+
+#[derive(Deserialize)]
+struct ExamResponse {
+    items: Vec<RawExam>,
+}
+
+/// Parse exam time like "2023-06-20(09:00-11:00)".
+pub fn parse_exam_time(s: &str) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let (date, time) = s.trim().trim_end_matches(')').split_once('(')?;
+    let (start, end) = time.split_once('-')?;
+
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    let start = NaiveTime::parse_from_str(start, "%H:%M").ok()?;
+    let end = NaiveTime::parse_from_str(end, "%H:%M").ok()?;
+    Some((date.and_time(start), date.and_time(end)))
+}
+
+impl From<RawExam> for Exam {
+    fn from(raw: RawExam) -> Self {
+        let time = parse_exam_time(&raw.kssj);
+        let room = split_room_title(&raw.cdmc);
+
+        Exam {
+            course_name: raw.kcmc,
+            course_code: raw.kch,
+            exam_name: raw.ksmc,
+            start: time.map(|t| t.0),
+            end: time.map(|t| t.1),
+            place: raw.cdmc,
+            campus: raw.cdxqmc,
+            building: room.as_ref().map(|r| r.0.clone()),
+            region: room.and_then(|r| r.1),
+            seat: raw.zwh,
+        }
+    }
+}
+
+/// Parse the JSON response of `EXAM_URI`.
+pub fn parse(text: &str) -> Result<Vec<Exam>> {
+    let response: ExamResponse = serde_json::from_str(text).context("Unexpected exam response")?;
+
+    Ok(response.items.into_iter().map(Into::into).collect())
+}
+
+pub async fn fetch<T>(session: &mut ServiceSession<'_, T>, year: i32, semester: i32) -> Result<Vec<Exam>>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let year = year.to_string();
+    let form = vec![
+        ("xnm", year.as_str()),
+        ("xqm", super::semester_code(semester)?),
+        ("queryModel.showCount", "5000"),
+        ("queryModel.currentPage", "1"),
+    ];
+    let header = vec![("Content-Type", "application/x-www-form-urlencoded")];
+    let response = session.post(EXAM_URI, form, header).await?;
+    let text = String::from_utf8(response.body().to_vec())?;
+
+    parse(&text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::campus::fixture;
+
+    #[test]
+    fn test_parse() {
+        let exams = parse(fixture!("jwxt", "exam.json")).unwrap();
+        let date = NaiveDate::from_ymd_opt(2023, 6, 20).unwrap();
+
+        assert_eq!(exams.len(), 2);
+        assert_eq!(
+            exams[0],
+            Exam {
+                course_name: "高等数学（工）2".to_string(),
+                course_code: "B1220012".to_string(),
+                exam_name: "2022-2023-2期末考试".to_string(),
+                start: Some(date.and_hms_opt(9, 0, 0).unwrap()),
+                end: Some(date.and_hms_opt(11, 0, 0).unwrap()),
+                place: "一教A101".to_string(),
+                campus: "奉贤校区".to_string(),
+                building: Some("一教".to_string()),
+                region: Some("A".to_string()),
+                seat: "12".to_string(),
+            }
+        );
+        // Not arranged yet
+        assert_eq!(exams[1].start, None);
+        assert_eq!(exams[1].building, None);
+        assert!(parse("<html></html>").is_err());
+    }
+}
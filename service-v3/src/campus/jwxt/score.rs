@@ -0,0 +1,136 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! 成绩查询
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::authserver::ServiceSession;
+use crate::model::score::{grade_point, Score};
+
+pub const SCORE_URI: &str = "/jwglxt/cjcx/cjcx_cxDgXscj.html?doType=query&gnmkdm=N305005";
+
+#[derive(Deserialize)]
+struct RawScore {
+    /// 课程名称
+    kcmc: String,
+    /// 课程号
+    #[serde(default)]
+    kch: String,
+    /// 教学班名称
+    #[serde(default)]
+    jxbmc: String,
+    /// 学分
+    #[serde(default)]
+    xf: String,
+    /// 成绩
+    cj: String,
+    /// 绩点, 可能为空
+    #[serde(default)]
+    jd: String,
+    /// 课程性质名称
+    #[serde(default)]
+    kcxzmc: String,
+    /// 考试性质
+    #[serde(default)]
+    ksxz: String,
+}
+
+#[derive(Deserialize)]
+struct ScoreResponse {
+    items: Vec<RawScore>,
+}
+
+impl From<RawScore> for Score {
+    fn from(raw: RawScore) -> Self {
+        // Grade point given by jwxt is preferred, which follows the school's rule.
+        let point = raw.jd.trim().parse().ok().or_else(|| grade_point(&raw.cj));
+
+        Score {
+            course_name: raw.kcmc,
+            course_code: raw.kch,
+            class_name: raw.jxbmc,
+            credit: raw.xf.trim().parse().unwrap_or_default(),
+            score: raw.cj.trim().to_string(),
+            grade_point: point,
+            course_type: raw.kcxzmc,
+            exam_type: raw.ksxz,
+        }
+    }
+}
+
+/// Parse the JSON response of `SCORE_URI`.
+pub fn parse(text: &str) -> Result<Vec<Score>> {
+    let response: ScoreResponse = serde_json::from_str(text).context("Unexpected score response")?;
+
+    Ok(response.items.into_iter().map(Into::into).collect())
+}
+
+pub async fn fetch<T>(session: &mut ServiceSession<'_, T>, year: i32, semester: i32) -> Result<Vec<Score>>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let year = year.to_string();
+    let form = vec![
+        ("xnm", year.as_str()),
+        ("xqm", super::semester_code(semester)?),
+        ("queryModel.showCount", "5000"),
+        ("queryModel.currentPage", "1"),
+    ];
+    let header = vec![("Content-Type", "application/x-www-form-urlencoded")];
+    let response = session.post(SCORE_URI, form, header).await?;
+    let text = String::from_utf8(response.body().to_vec())?;
+
+    parse(&text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::campus::fixture;
+    use crate::model::score::gpa;
+
+    #[test]
+    fn test_parse() {
+        let scores = parse(fixture!("jwxt", "score.json")).unwrap();
+
+        assert_eq!(scores.len(), 5);
+        assert_eq!(
+            scores[0],
+            Score {
+                course_name: "高等数学（工）2".to_string(),
+                course_code: "B1220012".to_string(),
+                class_name: "22级机械1班".to_string(),
+                credit: 5.0,
+                score: "92".to_string(),
+                grade_point: Some(4.2),
+                course_type: "必修".to_string(),
+                exam_type: "正常考试".to_string(),
+            }
+        );
+        // Grade point is absent in response.
+        assert_eq!(scores[1].grade_point, Some(3.5));
+        assert_eq!(scores[3].grade_point, Some(1.0));
+        assert_eq!(scores[4].grade_point, None);
+        // (4.2 * 5 + 3.5 * 3.5 + 1.0 * 2) / 10.5
+        assert!((gpa(&scores).unwrap() - 35.25 / 10.5).abs() < 1e-5);
+        assert!(parse("<html></html>").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! 个人课表查询
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::authserver::ServiceSession;
+use crate::model::classroom_browser::convert_range_string_to_binary;
+use crate::model::timetable::{parse_weeks, Course};
+
+pub const TIMETABLE_URI: &str = "/jwglxt/kbcx/xskbcx_cxXsgrkb.html?gnmkdm=N2151";
+
+/// A course in the response, with fields named in pinyin initials.
+#[derive(Deserialize)]
+struct RawCourse {
+    /// 课程名称
+    kcmc: String,
+    /// 课程号
+    #[serde(default)]
+    kch: String,
+    /// 教学班名称
+    #[serde(default)]
+    jxbmc: String,
+    /// 教师姓名, 以逗号分隔
+    #[serde(default)]
+    xm: String,
+    /// 场地名称
+    #[serde(default)]
+    cdmc: String,
+    /// 校区名称
+    #[serde(default)]
+    xqmc: String,
+    /// 周次, 如 "1-8周,10-16周(双)"
+    zcd: String,
+    /// 星期几
+    xqj: String,
+    /// 节次, 如 "1-2"
+    jcs: String,
+    /// 学分
+    #[serde(default)]
+    xf: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimetableResponse {
+    kb_list: Vec<RawCourse>,
+}
+
+fn convert(raw: RawCourse) -> Result<Course> {
+    let day = raw
+        .xqj
+        .trim()
+        .parse()
+        .ok()
+        .filter(|d| (1..=7).contains(d))
+        .ok_or_else(|| anyhow!("Invalid day {:?} of course {}", raw.xqj, raw.kcmc))?;
+    let teachers = raw
+        .xm
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(ToString::to_string)
+        .collect();
+
+    Ok(Course {
+        course_name: raw.kcmc,
+        course_code: raw.kch,
+        class_name: raw.jxbmc,
+        teachers,
+        place: raw.cdmc,
+        campus: raw.xqmc,
+        weeks: parse_weeks(&raw.zcd),
+        day,
+        time_index: convert_range_string_to_binary(&raw.jcs),
+        credit: raw.xf.trim().parse().unwrap_or_default(),
+    })
+}
+
+/// Parse the JSON response of `TIMETABLE_URI`.
+pub fn parse(text: &str) -> Result<Vec<Course>> {
+    let response: TimetableResponse = serde_json::from_str(text).context("Unexpected timetable response")?;
+
+    response.kb_list.into_iter().map(convert).collect()
+}
+
+pub async fn fetch<T>(session: &mut ServiceSession<'_, T>, year: i32, semester: i32) -> Result<Vec<Course>>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let year = year.to_string();
+    let form = vec![
+        ("xnm", year.as_str()),
+        ("xqm", super::semester_code(semester)?),
+        ("kzlx", "ck"),
+    ];
+    let header = vec![("Content-Type", "application/x-www-form-urlencoded")];
+    let response = session.post(TIMETABLE_URI, form, header).await?;
+    let text = String::from_utf8(response.body().to_vec())?;
+
+    parse(&text)
+}
+
+#[cfg(test)]
+mod test {
+    use authserver_mock::MockAuthserver;
+
+    use super::*;
+    use crate::authserver::{bind_mock, Credential};
+    use crate::campus::fixture;
+
+    #[test]
+    fn test_parse() {
+        let courses = parse(fixture!("jwxt", "timetable.json")).unwrap();
+
+        assert_eq!(courses.len(), 3);
+        assert_eq!(
+            courses[0],
+            Course {
+                course_name: "高等数学（工）2".to_string(),
+                course_code: "B1220012".to_string(),
+                class_name: "22级机械1班".to_string(),
+                teachers: vec!["王五".to_string()],
+                place: "一教A101".to_string(),
+                campus: "奉贤校区".to_string(),
+                weeks: parse_weeks("1-16周"),
+                day: 1,
+                time_index: 0b110,
+                credit: 5.0,
+            }
+        );
+        assert_eq!(courses[1].teachers, vec!["赵六", "钱七"]);
+        assert_eq!(courses[1].weeks, parse_weeks("1-15周(单)"));
+        assert_eq!(courses[2].time_index, 0b1110_0000_0000);
+        assert!(parse("<html></html>").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch() {
+        let server = MockAuthserver::new()
+            .user("2212340101", "password", "张三")
+            .jwxt_data("/jwglxt/kbcx/xskbcx_cxXsgrkb.html", fixture!("jwxt", "timetable.json"));
+        let credential = Credential::new("2212340101".to_string(), "password".to_string());
+        let mut portal = bind_mock(&server, credential).await;
+        portal.try_login().await.unwrap();
+
+        let mut session = portal.service_login(super::super::SERVICE_URL).await.unwrap();
+        let courses = fetch(&mut session, 2022, 2).await.unwrap();
+        assert_eq!(courses, parse(fixture!("jwxt", "timetable.json")).unwrap());
+        assert!(fetch(&mut session, 2022, 3).await.is_err());
+    }
+}
@@ -0,0 +1,269 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! 图书馆 (lib.sit.edu.cn)
+
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use once_cell::sync::Lazy;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde::Deserialize;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::authserver::{ServiceSession, Session};
+use crate::campus::element_text;
+use crate::model::library::{Book, Loan};
+
+/// Service URL registered on authserver.
+pub const SERVICE_URL: &str = "https://lib.sit.edu.cn/opac/reader/space";
+
+pub const LOAN_URI: &str = "/opac/loan/currentLoanList";
+pub const RENEW_URI: &str = "/opac/loan/renew";
+/// Catalog search, which is public.
+pub const SEARCH_URL: &str = "https://lib.sit.edu.cn/opac/search";
+
+/// Books per page of search results.
+const SEARCH_PAGE_SIZE: i32 = 10;
+
+static LOAN_TABLE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("#contentTable").unwrap());
+static LOAN_ROW_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("tbody > tr").unwrap());
+static TD_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("td").unwrap());
+static LINK_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("a").unwrap());
+static SEARCH_META_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("#search_meta strong").unwrap());
+static BOOK_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse(".resultTable td.bookmetaTD").unwrap());
+static BOOK_TITLE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse(".bookmetaTitle > a").unwrap());
+static BOOK_META_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse(".bookmeta > div").unwrap());
+static BOOK_COPY_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse(".bookcover_info").unwrap());
+static COPY_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"馆藏复本：(\d+)，可借复本：(\d+)").unwrap());
+
+fn parse_loan_row(row: ElementRef) -> Result<Option<Loan>> {
+    let cells: Vec<_> = row.select(&TD_SELECTOR).collect();
+    // "没有借阅记录" in one cell.
+    if cells.len() < 7 {
+        return Ok(None);
+    }
+    let date = |i: usize| {
+        let text = element_text(cells[i]);
+        NaiveDate::parse_from_str(&text, "%Y-%m-%d").with_context(|| format!("Unexpected date: {}", text))
+    };
+    // Title in the link, and author after '/'.
+    let title = cells[1]
+        .select(&LINK_SELECTOR)
+        .next()
+        .map(element_text)
+        .unwrap_or_default();
+    let author = element_text(cells[1])
+        .split_once('/')
+        .map(|(_, author)| author.trim().to_string())
+        .unwrap_or_default();
+
+    Ok(Some(Loan {
+        barcode: element_text(cells[0]),
+        title,
+        author,
+        call_number: element_text(cells[2]),
+        location: element_text(cells[3]),
+        loan_date: date(4)?,
+        due_date: date(5)?,
+        renew_count: element_text(cells[6]).parse().unwrap_or_default(),
+    }))
+}
+
+/// Parse the page of `LOAN_URI`.
+pub fn parse_loan(text: &str) -> Result<Vec<Loan>> {
+    let document = Html::parse_document(text);
+    let table = document
+        .select(&LOAN_TABLE_SELECTOR)
+        .next()
+        .context("Unexpected loan page, contentTable is missing")?;
+
+    let mut loans = Vec::new();
+    for row in table.select(&LOAN_ROW_SELECTOR) {
+        if let Some(loan) = parse_loan_row(row)? {
+            loans.push(loan);
+        }
+    }
+    Ok(loans)
+}
+
+fn parse_book(element: ElementRef) -> Option<Book> {
+    let title = element.select(&BOOK_TITLE_SELECTOR).next()?;
+    let book_id = title.value().attr("href")?.rsplit('/').next()?.to_string();
+
+    let (mut author, mut publisher, mut publish_date, mut call_number) =
+        (String::new(), String::new(), String::new(), String::new());
+    for meta in element.select(&BOOK_META_SELECTOR) {
+        let text = element_text(meta);
+        let Some((key, value)) = text.split_once('：') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "作者" => author = value,
+            "出版社" => publisher = value,
+            "出版日期" => publish_date = value,
+            "索书号" => call_number = value,
+            _ => {}
+        }
+    }
+    let copies = element.select(&BOOK_COPY_SELECTOR).next().and_then(|e| {
+        COPY_REGEX
+            .captures(&element_text(e))
+            .map(|c| (c[1].parse(), c[2].parse()))
+    });
+    let (copy_count, available_count) = match copies {
+        Some((Ok(copy), Ok(available))) => (copy, available),
+        _ => (0, 0),
+    };
+
+    Some(Book {
+        book_id,
+        title: element_text(title),
+        author,
+        publisher,
+        publish_date,
+        call_number,
+        copy_count,
+        available_count,
+    })
+}
+
+/// Parse the page of `SEARCH_URL`, returning the books and the total count.
+pub fn parse_search(text: &str) -> Result<(Vec<Book>, i32)> {
+    let document = Html::parse_document(text);
+    let books: Vec<_> = document.select(&BOOK_SELECTOR).filter_map(parse_book).collect();
+    let total = match document.select(&SEARCH_META_SELECTOR).next() {
+        Some(e) => element_text(e)
+            .parse()
+            .context("Unexpected total count of search results")?,
+        // Nothing found
+        None if books.is_empty() => 0,
+        None => bail!("Unexpected search page, search_meta is missing"),
+    };
+
+    Ok((books, total))
+}
+
+#[derive(Deserialize)]
+pub struct RenewResult {
+    pub success: bool,
+    #[serde(default)]
+    pub message: String,
+}
+
+pub async fn fetch_loan<T>(session: &mut ServiceSession<'_, T>) -> Result<Vec<Loan>>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let response = session.get(LOAN_URI).await?;
+    let text = String::from_utf8(response.body().to_vec())?;
+
+    parse_loan(&text)
+}
+
+pub async fn renew<T>(session: &mut ServiceSession<'_, T>, barcode: &str) -> Result<RenewResult>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let form = vec![("barcode", barcode)];
+    let header = vec![("Content-Type", "application/x-www-form-urlencoded")];
+    let response = session.post(RENEW_URI, form, header).await?;
+
+    serde_json::from_slice(response.body()).context("Unexpected renew response")
+}
+
+/// Search the catalog by keyword, and `page` starts from 1.
+pub async fn search<T>(session: &mut Session<T>, keyword: &str, page: i32) -> Result<(Vec<Book>, i32)>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let url = format!(
+        "{}?q={}&searchType=standard&rows={}&page={}",
+        SEARCH_URL,
+        utf8_percent_encode(keyword, NON_ALPHANUMERIC),
+        SEARCH_PAGE_SIZE,
+        page
+    );
+    let response = session.get(&url).await?;
+    if !response.status().is_success() {
+        bail!("Library search responded {}", response.status());
+    }
+    let text = String::from_utf8(response.body().to_vec())?;
+
+    parse_search(&text)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::campus::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_loan() {
+        let loans = parse_loan(fixture!("library", "loan.html")).unwrap();
+
+        assert_eq!(loans.len(), 2);
+        assert_eq!(
+            loans[0],
+            Loan {
+                barcode: "10563812".to_string(),
+                title: "Rust权威指南".to_string(),
+                author: "(美) 史蒂夫·克拉伯尼克".to_string(),
+                call_number: "TP312RU/5".to_string(),
+                location: "奉贤自然科学书库".to_string(),
+                loan_date: NaiveDate::from_ymd_opt(2023, 3, 1).unwrap(),
+                due_date: NaiveDate::from_ymd_opt(2023, 4, 30).unwrap(),
+                renew_count: 0,
+            }
+        );
+        assert_eq!(loans[1].title, "高等数学. 下册");
+        assert_eq!(loans[1].renew_count, 1);
+
+        assert!(parse_loan(fixture!("library", "loan_empty.html")).unwrap().is_empty());
+        assert!(parse_loan("<html></html>").is_err());
+    }
+
+    #[test]
+    fn test_parse_search() {
+        let (books, total) = parse_search(fixture!("library", "search.html")).unwrap();
+
+        assert_eq!(total, 32);
+        assert_eq!(books.len(), 2);
+        assert_eq!(
+            books[0],
+            Book {
+                book_id: "530120".to_string(),
+                title: "Rust权威指南".to_string(),
+                author: "(美) 史蒂夫·克拉伯尼克".to_string(),
+                publisher: "电子工业出版社".to_string(),
+                publish_date: "2020".to_string(),
+                call_number: "TP312RU/5".to_string(),
+                copy_count: 3,
+                available_count: 1,
+            }
+        );
+        assert_eq!(books[1].available_count, 0);
+        assert_eq!(
+            parse_search("<html><body>没有检索到结果</body></html>").unwrap(),
+            (vec![], 0)
+        );
+    }
+}
@@ -0,0 +1,202 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! 第二课堂 (sc.sit.edu.cn)
+
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::authserver::ServiceSession;
+use crate::campus::element_text;
+use crate::model::second_class::{Activity, CategoryScore, SecondClass, UpcomingActivity};
+
+/// Service URL registered on authserver.
+pub const SERVICE_URL: &str = "http://sc.sit.edu.cn/";
+
+pub const SCORE_URI: &str = "/public/pcenter/scoreDetail.action";
+pub const ACTIVITY_URI: &str = "/public/activity/activityList.action?pageNo=1&pageSize=20";
+
+static SUMMARY_ROW_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("#scoreSummary > tbody > tr").unwrap());
+static DETAIL_TABLE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("#scoreDetail").unwrap());
+static DETAIL_ROW_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("tbody > tr").unwrap());
+static TD_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("td").unwrap());
+static ACTIVITY_LIST_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("ul.activity-list").unwrap());
+static ACTIVITY_ITEM_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("li").unwrap());
+static LINK_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("a").unwrap());
+static CATEGORY_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse(".category").unwrap());
+static TIME_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse(".time").unwrap());
+static PLACE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse(".place").unwrap());
+static ACTIVITY_ID_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"activityId=(\d+)").unwrap());
+
+fn select_text(element: ElementRef, selector: &Selector) -> String {
+    element.select(selector).next().map(element_text).unwrap_or_default()
+}
+
+/// Time like "2023-03-08 14:00", and `None` for "待定" or empty.
+fn parse_time(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s.trim(), "%Y-%m-%d %H:%M").ok()
+}
+
+fn activity_id(element: ElementRef) -> String {
+    element
+        .select(&LINK_SELECTOR)
+        .next()
+        .and_then(|a| a.value().attr("href"))
+        .and_then(|href| ACTIVITY_ID_REGEX.captures(href))
+        .map(|c| c[1].to_string())
+        .unwrap_or_default()
+}
+
+/// Parse the page of `SCORE_URI`, returning scores by category and activities joined.
+pub fn parse_score(text: &str) -> Result<(Vec<CategoryScore>, Vec<Activity>)> {
+    let document = Html::parse_document(text);
+    let detail = document
+        .select(&DETAIL_TABLE_SELECTOR)
+        .next()
+        .context("Unexpected score page, scoreDetail is missing")?;
+
+    let scores = document
+        .select(&SUMMARY_ROW_SELECTOR)
+        .filter_map(|row| {
+            let cells: Vec<_> = row.select(&TD_SELECTOR).map(element_text).collect();
+            let [category, score, required] = cells.as_slice() else {
+                return None;
+            };
+            Some(CategoryScore {
+                category: category.clone(),
+                score: score.parse().unwrap_or_default(),
+                required: required.parse().unwrap_or_default(),
+            })
+        })
+        .collect();
+    let activities = detail
+        .select(&DETAIL_ROW_SELECTOR)
+        .filter_map(|row| {
+            let cells: Vec<_> = row.select(&TD_SELECTOR).map(element_text).collect();
+            let [activity_id, title, category, score, time, status] = cells.as_slice() else {
+                return None;
+            };
+            Some(Activity {
+                activity_id: activity_id.clone(),
+                title: title.clone(),
+                category: category.clone(),
+                score: score.parse().unwrap_or_default(),
+                time: parse_time(time),
+                status: status.clone(),
+            })
+        })
+        .collect();
+
+    Ok((scores, activities))
+}
+
+/// Parse the page of `ACTIVITY_URI`.
+pub fn parse_activity(text: &str) -> Result<Vec<UpcomingActivity>> {
+    let document = Html::parse_document(text);
+    let list = document
+        .select(&ACTIVITY_LIST_SELECTOR)
+        .next()
+        .context("Unexpected activity page, activity-list is missing")?;
+
+    let activities = list
+        .select(&ACTIVITY_ITEM_SELECTOR)
+        .map(|item| UpcomingActivity {
+            activity_id: activity_id(item),
+            title: select_text(item, &LINK_SELECTOR),
+            category: select_text(item, &CATEGORY_SELECTOR),
+            start: parse_time(&select_text(item, &TIME_SELECTOR)),
+            place: select_text(item, &PLACE_SELECTOR),
+        })
+        .collect();
+    Ok(activities)
+}
+
+async fn get_text<T>(session: &mut ServiceSession<'_, T>, url: &str) -> Result<String>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let response = session.get(url).await?;
+    String::from_utf8(response.body().to_vec()).map_err(Into::into)
+}
+
+pub async fn fetch<T>(session: &mut ServiceSession<'_, T>) -> Result<SecondClass>
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let (scores, activities) = parse_score(&get_text(session, SCORE_URI).await?)?;
+    let upcoming = parse_activity(&get_text(session, ACTIVITY_URI).await?)?;
+
+    Ok(SecondClass {
+        scores,
+        activities,
+        upcoming,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::campus::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_score() {
+        let (scores, activities) = parse_score(fixture!("second_class", "score.html")).unwrap();
+
+        assert_eq!(scores.len(), 6);
+        assert_eq!(
+            scores[1],
+            CategoryScore {
+                category: "社会实践".to_string(),
+                score: 0.5,
+                required: 2.0,
+            }
+        );
+        assert_eq!(activities.len(), 3);
+        assert_eq!(
+            activities[0],
+            Activity {
+                activity_id: "7024918".to_string(),
+                title: "“人工智能与未来”主题讲座".to_string(),
+                category: "主题报告".to_string(),
+                score: 0.5,
+                time: parse_time("2023-03-08 14:00"),
+                status: "已加分".to_string(),
+            }
+        );
+        assert_eq!(activities[2].time, None);
+        assert!(parse_score("<html></html>").is_err());
+    }
+
+    #[test]
+    fn test_parse_activity() {
+        let activities = parse_activity(fixture!("second_class", "activity.html")).unwrap();
+
+        assert_eq!(activities.len(), 2);
+        assert_eq!(activities[0].activity_id, "7026001");
+        assert_eq!(activities[0].title, "“碳中和”科普讲座");
+        assert_eq!(activities[0].category, "主题报告");
+        assert_eq!(activities[0].start, parse_time("2023-03-15 18:30"));
+        assert_eq!(activities[0].place, "奉贤校区 第一学科楼 A101");
+        assert_eq!(activities[1].start, None);
+    }
+}
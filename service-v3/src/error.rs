@@ -18,13 +18,28 @@
 
 use tonic::Status;
 
+use crate::authserver::LoginError;
+use crate::campus::NotSaved;
+use crate::limit::RateLimited;
+
 pub trait ToStatus {
     fn to_status(self) -> Status;
 }
 
 impl ToStatus for anyhow::Error {
     fn to_status(self) -> Status {
-        Status::internal(self.to_string())
+        // Login failures on campus systems are caused by user, and the message is shown to them.
+        if let Some(e) = self.downcast_ref::<RateLimited>() {
+            return Status::resource_exhausted(e.to_string());
+        }
+        if let Some(e) = self.downcast_ref::<NotSaved>() {
+            return Status::not_found(e.to_string());
+        }
+        match self.downcast_ref::<LoginError>() {
+            Some(e @ (LoginError::Rejected(_) | LoginError::NotLoggedIn)) => Status::unauthenticated(e.to_string()),
+            Some(e) => Status::failed_precondition(e.to_string()),
+            None => Status::internal(self.to_string()),
+        }
     }
 }
 
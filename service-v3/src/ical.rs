@@ -0,0 +1,196 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Write iCalendar (RFC 5545) files, which can be imported into calendar apps.
+
+use chrono::{NaiveDateTime, Utc};
+
+/// An event in calendar. Time is in local time of Shanghai.
+pub struct Event {
+    /// Unique id of the event, which is kept in exports so that calendar apps can update events.
+    pub uid: String,
+    pub summary: String,
+    pub location: String,
+    pub description: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// Remind some minutes before the event.
+    pub alarm: Option<i32>,
+}
+
+pub struct Calendar {
+    pub name: String,
+    pub events: Vec<Event>,
+}
+
+const TIME_ZONE: &str = "Asia/Shanghai";
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+/// Definition of `TIME_ZONE`, which is required for each TZID in use, see RFC 5545 section 3.2.19.
+/// China has no DST since 1991, so a fixed offset is enough.
+const VTIMEZONE: &[&str] = &[
+    "BEGIN:VTIMEZONE",
+    "TZID:Asia/Shanghai",
+    "BEGIN:STANDARD",
+    "DTSTART:19700101T000000",
+    "TZOFFSETFROM:+0800",
+    "TZOFFSETTO:+0800",
+    "TZNAME:CST",
+    "END:STANDARD",
+    "END:VTIMEZONE",
+];
+
+impl Calendar {
+    pub fn new(name: impl Into<String>) -> Self {
+        Calendar {
+            name: name.into(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, event: Event) {
+        self.events.push(event);
+    }
+}
+
+impl std::fmt::Display for Calendar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//SIT-kite//kite-server//CN".to_string(),
+            "CALSCALE:GREGORIAN".to_string(),
+            format!("X-WR-CALNAME:{}", escape(&self.name)),
+            format!("X-WR-TIMEZONE:{}", TIME_ZONE),
+        ];
+        lines.extend(VTIMEZONE.iter().map(ToString::to_string));
+        let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+
+        for event in &self.events {
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}", event.uid));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!(
+                "DTSTART;TZID={}:{}",
+                TIME_ZONE,
+                event.start.format(TIME_FORMAT)
+            ));
+            lines.push(format!("DTEND;TZID={}:{}", TIME_ZONE, event.end.format(TIME_FORMAT)));
+            lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+            if !event.location.is_empty() {
+                lines.push(format!("LOCATION:{}", escape(&event.location)));
+            }
+            if !event.description.is_empty() {
+                lines.push(format!("DESCRIPTION:{}", escape(&event.description)));
+            }
+            if let Some(minutes) = event.alarm {
+                lines.push("BEGIN:VALARM".to_string());
+                lines.push("ACTION:DISPLAY".to_string());
+                lines.push(format!("DESCRIPTION:{}", escape(&event.summary)));
+                lines.push(format!("TRIGGER:-PT{}M", minutes));
+                lines.push("END:VALARM".to_string());
+            }
+            lines.push("END:VEVENT".to_string());
+        }
+        lines.push("END:VCALENDAR".to_string());
+
+        for line in lines {
+            write!(f, "{}\r\n", fold(&line))?;
+        }
+        Ok(())
+    }
+}
+
+/// Escape text value, see RFC 5545 section 3.3.11.
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                result.push('\\');
+                result.push(c);
+            }
+            '\n' => result.push_str("\\n"),
+            '\r' => {}
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Fold lines longer than 75 octets, without breaking UTF-8 characters.
+fn fold(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            result.push_str("\r\n ");
+            // The leading space counts.
+            length = 1;
+        }
+        result.push(c);
+        length += c.len_utf8();
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn test_escape_and_fold() {
+        assert_eq!(escape("a,b;c\\d\ne"), r"a\,b\;c\\d\ne");
+
+        let line = format!("SUMMARY:{}", "高等数学".repeat(10));
+        let folded = fold(&line);
+        assert!(folded.split("\r\n").all(|l| l.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn test_calendar() {
+        let date = NaiveDate::from_ymd_opt(2023, 2, 20).unwrap();
+        let mut calendar = Calendar::new("课表");
+        calendar.push(Event {
+            uid: "course-1@kite".to_string(),
+            summary: "高等数学".to_string(),
+            location: "一教A101".to_string(),
+            description: String::new(),
+            start: date.and_hms_opt(8, 20, 0).unwrap(),
+            end: date.and_hms_opt(9, 55, 0).unwrap(),
+            alarm: Some(15),
+        });
+        let text = calendar.to_string();
+
+        assert!(text.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(text.ends_with("END:VCALENDAR\r\n"));
+        assert!(text.contains("BEGIN:VTIMEZONE\r\nTZID:Asia/Shanghai\r\n"));
+        assert!(text.contains("TZOFFSETTO:+0800\r\n"));
+        // The time zone is defined before events.
+        assert!(text.find("END:VTIMEZONE").unwrap() < text.find("BEGIN:VEVENT").unwrap());
+        assert!(text.contains("DTSTART;TZID=Asia/Shanghai:20230220T082000\r\n"));
+        assert!(text.contains("DTEND;TZID=Asia/Shanghai:20230220T095500\r\n"));
+        assert!(text.contains("LOCATION:一教A101\r\n"));
+        assert!(text.contains("TRIGGER:-PT15M\r\n"));
+        assert!(!text.contains("DESCRIPTION:\r\n"));
+    }
+}
@@ -0,0 +1,82 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Background jobs, which run periodically along with the gRPC server.
+
+use std::future::Future;
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::time::MissedTickBehavior;
+
+mod exam;
+mod library;
+mod score;
+
+/// Run `job` unless another kite-server instance is running the job of `name`.
+///
+/// The lock is held by a transaction, so that it is released when the transaction ends, even if `job` fails.
+async fn run_exclusive(db: &PgPool, name: &str, job: impl Future<Output = anyhow::Result<()>>) -> anyhow::Result<bool> {
+    let mut tx = db.begin().await?;
+    let (locked,): (bool,) = sqlx::query_as("SELECT pg_try_advisory_xact_lock(hashtext($1));")
+        .bind(name)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !locked {
+        return Ok(false);
+    }
+    job.await?;
+    tx.rollback().await?;
+    Ok(true)
+}
+
+/// Run `task` every `period` on one of kite-server instances. Errors are logged, and the job keeps running.
+fn spawn<F, Fut>(db: PgPool, name: &'static str, period: Duration, mut task: F)
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<()>> + Send,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            match run_exclusive(&db, name, task()).await {
+                Ok(true) => tracing::debug!("Job {} done.", name),
+                Ok(false) => tracing::debug!("Job {} is running on another instance.", name),
+                Err(e) => tracing::error!("Job {} failed: {}", name, e),
+            }
+        }
+    });
+}
+
+pub fn start(db: PgPool) {
+    let pool = db.clone();
+    spawn(db.clone(), "score", Duration::from_secs(600), move || {
+        score::find_new_scores(pool.clone())
+    });
+    let pool = db.clone();
+    spawn(db.clone(), "exam", Duration::from_secs(600), move || {
+        exam::remind_exams(pool.clone())
+    });
+    let pool = db.clone();
+    spawn(db.clone(), "library", Duration::from_secs(3600), move || {
+        library::remind_due_loans(pool.clone())
+    });
+}
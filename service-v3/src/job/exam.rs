@@ -0,0 +1,60 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Remind users of exams in the next day.
+
+use anyhow::Result;
+use chrono::{Duration, Local};
+use sqlx::PgPool;
+
+use crate::model::exam::{self, Exam};
+use crate::model::notification;
+
+/// Remind exams which start in the hours.
+const REMIND_IN_HOURS: i64 = 24;
+
+fn reminder_content(exam: &Exam) -> String {
+    let time = exam
+        .start
+        .map(|t| t.format("%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    let mut content = format!("{} 在 {} 考试", time, exam.place);
+    if !exam.seat.is_empty() {
+        content += &format!(", 座位号 {}", exam.seat);
+    }
+    content
+}
+
+/// One reminder for an exam, and another if the time changes.
+fn reminder_key(exam: &Exam) -> String {
+    let time = exam
+        .start
+        .map(|t| t.format("%Y%m%d%H%M").to_string())
+        .unwrap_or_default();
+    format!("{}-{}", exam.course_code, time)
+}
+
+pub async fn remind_exams(db: PgPool) -> Result<()> {
+    let now = Local::now().naive_local();
+
+    for (uid, exam) in exam::get_upcoming(&db, now, now + Duration::hours(REMIND_IN_HOURS)).await? {
+        let (key, content) = (reminder_key(&exam), reminder_content(&exam));
+        notification::create_once(&db, uid, exam::NOTIFICATION_KIND, &key, &exam.course_name, &content).await?;
+    }
+    Ok(())
+}
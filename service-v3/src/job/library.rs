@@ -0,0 +1,48 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Remind users of books due soon.
+
+use anyhow::Result;
+use chrono::{Duration, Local};
+use sqlx::PgPool;
+
+use crate::model::library::{self, Loan};
+use crate::model::notification;
+
+/// Remind loans which are due in the days.
+const REMIND_IN_DAYS: i64 = 3;
+
+fn reminder_content(loan: &Loan) -> String {
+    format!("请在 {} 前归还或续借", loan.due_date.format("%Y-%m-%d"))
+}
+
+/// One reminder for a loan, and another after it is renewed.
+fn reminder_key(loan: &Loan) -> String {
+    format!("{}-{}", loan.barcode, loan.due_date.format("%Y%m%d"))
+}
+
+pub async fn remind_due_loans(db: PgPool) -> Result<()> {
+    let today = Local::now().date_naive();
+
+    for (uid, loan) in library::get_due_soon(&db, today, today + Duration::days(REMIND_IN_DAYS)).await? {
+        let (key, content) = (reminder_key(&loan), reminder_content(&loan));
+        notification::create_once(&db, uid, library::NOTIFICATION_KIND, &key, &loan.title, &content).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Fetch scores with saved campus sessions, and compare score snapshots to find new grades.
+
+use anyhow::Result;
+use sqlx::PgPool;
+
+use crate::authserver::LoginError;
+use crate::campus::{jwxt, CampusSession};
+use crate::model::{campus_session, notification, score};
+
+const BATCH_SIZE: i64 = 100;
+
+async fn fetch_score(db: &PgPool, uid: i32, year: i32, semester: i32) -> Result<()> {
+    let mut campus = CampusSession::load(db, uid).await?;
+    let mut session = campus.service(jwxt::SERVICE_URL)?;
+
+    let scores = jwxt::score::fetch(&mut session, year, semester).await?;
+    score::save(db, uid, year, semester, &scores).await?;
+    Ok(())
+}
+
+/// Fetch the latest term of users who have campus sessions. A user failing doesn't stop the others.
+async fn fetch_scores(db: &PgPool) -> Result<()> {
+    for (uid, year, semester) in score::get_terms_to_check(db).await? {
+        let Err(e) = fetch_score(db, uid, year, semester).await else {
+            continue;
+        };
+        if let Some(LoginError::NotLoggedIn) = e.downcast_ref::<LoginError>() {
+            // The sessions have expired, and they are saved again on the next login.
+            campus_session::delete(db, uid).await?;
+        } else {
+            tracing::warn!("Failed to fetch scores of user {}: {:?}", uid, e);
+        }
+    }
+    Ok(())
+}
+
+pub async fn find_new_scores(db: PgPool) -> Result<()> {
+    fetch_scores(&db).await?;
+
+    loop {
+        let snapshots = score::get_unprocessed(&db, BATCH_SIZE).await?;
+
+        for snapshot in &snapshots {
+            // The first snapshot of a term is the baseline, rather than new grades.
+            let Some(previous) = score::get_previous(&db, snapshot).await? else {
+                score::set_processed(&db, snapshot.id).await?;
+                continue;
+            };
+            let mut tx = db.begin().await?;
+            for s in score::diff(&previous.scores, &snapshot.scores) {
+                notification::create(
+                    &mut *tx,
+                    snapshot.uid,
+                    score::NOTIFICATION_KIND,
+                    &s.course_name,
+                    &s.score,
+                )
+                .await?;
+            }
+            score::set_processed(&mut *tx, snapshot.id).await?;
+            tx.commit().await?;
+        }
+        if snapshots.len() < BATCH_SIZE as usize {
+            return Ok(());
+        }
+    }
+}
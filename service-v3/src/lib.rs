@@ -20,7 +20,11 @@ use kite::config;
 use kite::service::KiteModule;
 
 mod authserver;
+mod campus;
 mod error;
+mod ical;
+mod job;
+mod limit;
 mod model;
 mod service;
 
@@ -29,6 +33,18 @@ pub struct ServerV3 {}
 #[async_trait::async_trait]
 impl KiteModule for ServerV3 {
     async fn run() {
+        job::start(kite::get_db().clone());
         service::grpc_server().await
     }
 }
+
+/// Load `kite.toml` at the root of repository, once for all tests.
+#[cfg(test)]
+fn load_test_config() {
+    static ONCE: std::sync::Once = std::sync::Once::new();
+
+    ONCE.call_once(|| {
+        std::env::set_var("KITE_CONFIG", concat!(env!("CARGO_MANIFEST_DIR"), "/../kite.toml"));
+        config::initialize();
+    });
+}
@@ -0,0 +1,113 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Count requests in process, to protect campus systems and slow down guessing.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Counters are cleaned up when there are so many keys.
+const CLEANUP_THRESHOLD: usize = 4096;
+
+/// The error returned when a limit is exceeded, which is reported as `resource_exhausted`.
+#[derive(Debug)]
+pub struct RateLimited;
+
+impl std::fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Too many requests, please try again later")
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// Allow `limit` events of a key in a fixed window.
+pub struct RateLimiter<K> {
+    limit: u32,
+    window: Duration,
+    /// Key -> (start of the window, events in it)
+    counters: Mutex<HashMap<K, (Instant, u32)>>,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new(limit: u32, window: Duration) -> Self {
+        RateLimiter {
+            limit,
+            window,
+            counters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Forget events of `key`, such as failures before a success.
+    pub fn reset(&self, key: &K) {
+        self.counters.lock().unwrap().remove(key);
+    }
+
+    /// Count an event of `key` if the limit is not reached, or return `RateLimited`. It's checked and counted
+    /// under one lock, so concurrent callers can't exceed the limit.
+    pub fn acquire(&self, key: K) -> Result<(), RateLimited> {
+        let mut counters = self.counters.lock().unwrap();
+        if counters.len() >= CLEANUP_THRESHOLD {
+            counters.retain(|_, (start, _)| start.elapsed() < self.window);
+        }
+        let (start, count) = counters.entry(key).or_insert((Instant::now(), 0));
+        if start.elapsed() >= self.window {
+            (*start, *count) = (Instant::now(), 0);
+        }
+        if *count >= self.limit {
+            return Err(RateLimited);
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(2, Duration::from_millis(100));
+
+        assert!(limiter.acquire("a").is_ok());
+        assert!(limiter.acquire("a").is_ok());
+        assert!(limiter.acquire("a").is_err());
+        assert!(limiter.acquire("b").is_ok());
+
+        assert!(limiter.acquire("b").is_ok());
+        assert!(limiter.acquire("b").is_err());
+        limiter.reset(&"b");
+        assert!(limiter.acquire("b").is_ok());
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(limiter.acquire("a").is_ok());
+    }
+
+    #[test]
+    fn test_concurrent_acquire() {
+        let limiter = RateLimiter::new(5, Duration::from_secs(60));
+
+        let acquired = std::thread::scope(|s| {
+            let threads: Vec<_> = (0..50).map(|_| s.spawn(|| limiter.acquire("a").is_ok())).collect();
+            threads.into_iter().map(|t| t.join().unwrap()).filter(|ok| *ok).count()
+        });
+        assert_eq!(acquired, 5);
+    }
+}
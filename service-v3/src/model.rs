@@ -21,11 +21,12 @@ pub use kite::model::*;
 pub use template::*;
 
 pub mod convert {
-    use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Timelike};
+    use chrono::{DateTime, Local, NaiveDateTime, Offset, TimeZone, Timelike};
     use prost_types::Timestamp;
 
     pub trait ToDateTime {
-        fn timestamp(self) -> DateTime<Local>;
+        /// `None` if the timestamp is out of range.
+        fn timestamp(self) -> Option<DateTime<Local>>;
     }
 
     pub trait ToTimestamp {
@@ -33,11 +34,9 @@ pub mod convert {
     }
 
     impl ToDateTime for Timestamp {
-        fn timestamp(self) -> DateTime<Local> {
-            let (secs, nsecs) = (self.seconds, self.nanos);
-            let dt = NaiveDateTime::from_timestamp_opt(secs, nsecs as u32).unwrap();
-
-            Local::from_local_datetime(&Local, &dt).unwrap()
+        fn timestamp(self) -> Option<DateTime<Local>> {
+            let nsecs = u32::try_from(self.nanos).ok()?;
+            Local.timestamp_opt(self.seconds, nsecs).single()
         }
     }
 
@@ -50,4 +49,41 @@ pub mod convert {
             }
         }
     }
+
+    /// Local time, such as the time on campus systems. A time skipped by DST takes the offset around it.
+    impl ToTimestamp for NaiveDateTime {
+        fn datetime(self) -> Timestamp {
+            let time = Local.from_local_datetime(&self).earliest().unwrap_or_else(|| {
+                let offset = Local.offset_from_utc_datetime(&self).fix();
+                Local.from_utc_datetime(&(self - offset))
+            });
+            time.datetime()
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn test_timestamp() {
+            let now = Local::now();
+            assert_eq!(now.datetime().timestamp(), Some(now));
+
+            let utc = Timestamp {
+                seconds: 1_700_000_000,
+                nanos: 0,
+            };
+            assert_eq!(utc.timestamp().unwrap().timestamp(), 1_700_000_000);
+            assert_eq!(now.naive_local().datetime(), now.datetime());
+
+            let invalid = Timestamp {
+                seconds: i64::MAX,
+                nanos: 0,
+            };
+            assert_eq!(invalid.timestamp(), None);
+            let invalid = Timestamp { seconds: 0, nanos: -1 };
+            assert_eq!(invalid.timestamp(), None);
+        }
+    }
 }
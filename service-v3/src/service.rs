@@ -29,10 +29,20 @@ pub mod gen;
 mod badge;
 mod balance;
 mod board;
+mod cache_sync;
+mod calendar;
+mod campus_card;
 mod captcha;
 mod classroom_browser;
+mod exam;
+mod freshman;
+mod health;
+mod library;
 mod ping;
+mod score;
+mod second_class;
 mod template;
+mod timetable;
 mod user;
 
 #[derive(Clone)]
@@ -53,18 +63,87 @@ fn load_reflection() -> ServerReflectionServer<impl ServerReflection> {
 
 pub async fn grpc_server() {
     let addr = config::get().bind.clone();
+    auth::keyring::initialize();
     let server = KiteGrpcServer {
         db: kite::get_db().clone(),
     };
 
-    let ping = ping::gen::ping_service_server::PingServiceServer::new(server.clone());
-    let badge = badge::gen::badge_service_server::BadgeServiceServer::new(server.clone());
-    let balance = balance::gen::balance_service_server::BalanceServiceServer::new(server.clone());
-    let board = board::gen::board_service_server::BoardServiceServer::new(server.clone());
-    let classroom_browser =
-        classroom_browser::gen::classroom_browser_service_server::ClassroomBrowserServiceServer::new(server.clone());
-    let user = user::gen::user_service_server::UserServiceServer::new(server.clone());
-    let captcha = captcha::gen::captcha_service_server::CaptchaServiceServer::new(server.clone());
+    use health::Requires;
+    let mut registry = health::Registry::default();
+    let ping = registry.register(
+        ping::gen::ping_service_server::PingServiceServer::new(server.clone()),
+        Requires::NOTHING,
+    );
+    let badge = registry.register(
+        badge::gen::badge_service_server::BadgeServiceServer::new(server.clone()),
+        Requires::DATABASE,
+    );
+    let balance = registry.register(
+        balance::gen::balance_service_server::BalanceServiceServer::new(server.clone()),
+        Requires {
+            balance: true,
+            ..Requires::DATABASE
+        },
+    );
+    let board = registry.register(
+        board::gen::board_service_server::BoardServiceServer::new(server.clone()),
+        Requires::DATABASE,
+    );
+    let calendar = registry.register(
+        calendar::gen::calendar_service_server::CalendarServiceServer::new(server.clone()),
+        Requires::DATABASE,
+    );
+    let campus_card = registry.register(
+        campus_card::gen::campus_card_service_server::CampusCardServiceServer::new(server.clone()),
+        Requires::DATABASE,
+    );
+    let classroom_browser = registry.register(
+        classroom_browser::gen::classroom_browser_service_server::ClassroomBrowserServiceServer::new(server.clone()),
+        Requires::DATABASE,
+    );
+    let exam = registry.register(
+        exam::gen::exam_service_server::ExamServiceServer::new(server.clone()),
+        Requires::DATABASE,
+    );
+    let freshman = registry.register(
+        freshman::gen::welcome_service_server::WelcomeServiceServer::new(server.clone()),
+        Requires::DATABASE,
+    );
+    let library = registry.register(
+        library::gen::library_service_server::LibraryServiceServer::new(server.clone()),
+        Requires::DATABASE,
+    );
+    // Login recognizes the captcha of authserver and saves user profile.
+    let user = registry.register(
+        user::gen::user_service_server::UserServiceServer::new(server.clone()),
+        Requires {
+            captcha: true,
+            ..Requires::DATABASE
+        },
+    );
+    let captcha = registry.register(
+        captcha::gen::captcha_service_server::CaptchaServiceServer::new(server.clone()),
+        Requires {
+            captcha: true,
+            ..Requires::NOTHING
+        },
+    );
+    let score = registry.register(
+        score::gen::score_service_server::ScoreServiceServer::new(server.clone()),
+        Requires::DATABASE,
+    );
+    let second_class = registry.register(
+        second_class::gen::second_class_service_server::SecondClassServiceServer::new(server.clone()),
+        Requires::DATABASE,
+    );
+    let timetable = registry.register(
+        timetable::gen::timetable_service_server::TimetableServiceServer::new(server.clone()),
+        Requires::DATABASE,
+    );
+
+    let (health_reporter, health) = tonic_health::server::health_reporter();
+    tokio::spawn(health::health_check_task(health_reporter, server.db.clone(), registry));
+    tokio::spawn(cache_sync::erase_on_notification(server.db.clone()));
 
     use tower_http::trace::TraceLayer;
     let layer = tower::ServiceBuilder::new()
@@ -73,19 +152,29 @@ pub async fn grpc_server() {
                 tracing::info!("Incoming request: {:?}", req)
             }),
         )
+        .layer(auth::AuthLayer::new(server.db.clone()))
         .into_inner();
 
     tracing::info!("Listening on {}...", addr);
     let builder = Server::builder()
         .layer(layer)
         .add_service(load_reflection())
+        .add_service(health)
         .add_service(ping)
         .add_service(badge)
         .add_service(balance)
         .add_service(board)
+        .add_service(calendar)
+        .add_service(campus_card)
         .add_service(classroom_browser)
+        .add_service(exam)
+        .add_service(freshman)
+        .add_service(library)
         .add_service(user)
-        .add_service(captcha);
+        .add_service(captcha)
+        .add_service(score)
+        .add_service(second_class)
+        .add_service(timetable);
 
     // Unix socket
     let server = if addr.starts_with('/') || addr.starts_with('.') {
@@ -16,68 +16,125 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use chrono::{Duration, Local};
+use jsonwebtoken::{decode, decode_header, encode, Header, Validation};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tonic::{Request, Status};
 
-use crate::config;
+pub use layer::AuthLayer;
 
-#[derive(serde::Serialize, serde::Deserialize)]
+use crate::model::Uuid;
+
+pub mod keyring;
+mod layer;
+mod permission;
+pub mod store;
+
+/// `typ` in the header of user tokens, which is the default of `Header`, so tokens issued before also pass.
+const USER_TOKEN_TYPE: &str = "JWT";
+
+/// Lifetime of an access token in seconds. The client uses its refresh token to get a new one after that.
+const ACCESS_TOKEN_LIFETIME: i64 = 2 * 3600;
+
+/// 用户角色
+///
+/// Roles are ordered, a method which requires `User` is also available to `Admin`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// 未登录用户
+    Anonymous,
+    /// 普通用户
+    User,
+    /// 管理员
+    Admin,
+}
+
+impl Role {
+    /// Value stored in `user_account.role` and `JwtToken.role` for administrators.
+    pub const ADMIN_ROLE_VALUE: i32 = 1;
+
+    fn from_token_role(role: i32) -> Self {
+        if role == Self::ADMIN_ROLE_VALUE {
+            Role::Admin
+        } else {
+            Role::User
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct JwtToken {
     /// 用户 ID
     pub uid: i32,
     /// 用户角色
     pub role: i32,
+    /// 签发时间 (Unix 时间戳)
+    pub iat: i64,
+    /// 过期时间 (Unix 时间戳)
+    pub exp: i64,
+    /// 令牌 ID, 用于在过期前吊销该令牌
+    pub jti: Uuid,
 }
 
 impl JwtToken {
     pub fn new(uid: i32, role: i32) -> Self {
-        Self { uid, role }
+        let now = Local::now();
+
+        Self {
+            uid,
+            role,
+            iat: now.timestamp(),
+            exp: (now + Duration::seconds(ACCESS_TOKEN_LIFETIME)).timestamp(),
+            jti: Uuid::new_v4(),
+        }
     }
-    pub fn encode(&self) -> String {
-        let key = config::get().secret.as_str();
-        let encoding_key = EncodingKey::from_secret(key.as_ref());
 
-        encode(&Header::default(), &self, &encoding_key).unwrap()
+    pub fn encode(&self) -> String {
+        encode_claims(self, USER_TOKEN_TYPE)
     }
 
     pub fn decode(token: &str) -> Option<Self> {
-        let key = config::get().secret.as_str();
-        let decoding_key = DecodingKey::from_secret(key.as_ref());
-        let option = Validation::default();
-        let token_data = decode::<Self>(token, &decoding_key, &option);
-
-        token_data.ok().map(|t| t.claims)
+        decode_claims(token, USER_TOKEN_TYPE)
     }
-}
 
-pub fn get_token_from_request<T>(req: tonic::Request<T>) -> Result<JwtToken, tonic::Status> {
-    if let Some(token) = req.metadata().get("authorization") {
-        let token = token
-            .to_str()
-            .map_err(|e| tonic::Status::unauthenticated(format!("Failed to parse token to str: {:?}", e)))?;
-
-        JwtToken::decode(token).ok_or(tonic::Status::unauthenticated("Invalid token: May be expired?"))
-    } else {
-        Err(tonic::Status::unauthenticated(
-            "No authorization can be found in your request.",
-        ))
+    pub fn role(&self) -> Role {
+        Role::from_token_role(self.role)
     }
 }
 
-pub fn require_login<T>(req: tonic::Request<T>) -> Result<(), tonic::Status> {
-    get_token_from_request(req).map(|_| ())
+/// Sign claims by the active key in keyring. Tokens of different kinds are told apart by `typ` in the header,
+/// so that one can't be used as another.
+pub fn encode_claims<T: Serialize>(claims: &T, typ: &str) -> String {
+    let key = keyring::get().signing_key();
+    let mut header = Header::new(key.algorithm);
+    header.kid = key.kid.clone();
+    header.typ = Some(typ.to_string());
+
+    encode(&header, claims, &key.key).unwrap()
 }
 
-pub fn require_user<T>(req: tonic::Request<T>, uid: i32) -> Result<(), tonic::Status> {
-    match get_token_from_request(req) {
-        Ok(token) => {
-            if token.uid == uid {
-                Ok(())
-            } else {
-                Err(tonic::Status::permission_denied(
-                    "You are manipulating other's resource.",
-                ))
-            }
-        }
-        Err(e) => Err(tonic::Status::unauthenticated(e.to_string())),
+/// Verify a token of kind `typ` by the key it names, and get the claims.
+pub fn decode_claims<T: DeserializeOwned>(token: &str, typ: &str) -> Option<T> {
+    let header = decode_header(token).ok()?;
+    if header.typ.as_deref() != Some(typ) {
+        return None;
     }
+    let key = keyring::get().verification_key(header.kid.as_deref())?;
+    // Only the algorithm bound to the key is accepted, rather than the one claimed by the token.
+    let option = Validation::new(key.algorithm);
+    let token_data = decode::<T>(token, &key.key, &option);
+
+    token_data.ok().map(|t| t.claims)
+}
+
+/// Get the token which is validated and attached to the request by `AuthLayer`.
+///
+/// It returns `unauthenticated` only when the method is declared as anonymous in the permission
+/// table, where no token is attached.
+pub fn get_token_from_request<T>(req: &Request<T>) -> Result<JwtToken, Status> {
+    req.extensions()
+        .get::<JwtToken>()
+        .cloned()
+        .ok_or_else(|| Status::unauthenticated("No authorization can be found in your request."))
 }
@@ -0,0 +1,248 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! JWT keys loaded from configuration.
+//!
+//! New tokens are signed by the active key with its `kid` in the header, and a token is verified
+//! by the key it names. To rotate, add a new key, make it active, and remove the old one after
+//! all tokens signed by it expire. Tokens without `kid` were issued before keyring is introduced,
+//! they are verified by the legacy `secret` only if `accept_legacy` is on.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Result};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use once_cell::sync::OnceCell;
+
+use crate::config;
+use crate::config::{JwtConfig, JwtKeyConfig};
+
+static KEYRING: OnceCell<Keyring> = OnceCell::new();
+
+pub struct SigningKey {
+    pub kid: Option<String>,
+    pub algorithm: Algorithm,
+    pub key: EncodingKey,
+}
+
+pub struct VerificationKey {
+    pub algorithm: Algorithm,
+    pub key: DecodingKey,
+}
+
+pub struct Keyring {
+    signing: SigningKey,
+    verification: HashMap<String, VerificationKey>,
+    /// Key for tokens without `kid`.
+    legacy: Option<VerificationKey>,
+}
+
+fn read_pem(path: &Option<String>, kid: &str, field: &str) -> Result<Vec<u8>> {
+    let path = path
+        .as_ref()
+        .ok_or_else(|| anyhow!("{field} is required by key {kid}"))?;
+
+    std::fs::read(path).with_context(|| format!("Failed to read {field} of key {kid} from {path}"))
+}
+
+fn load_encoding_key(config: &JwtKeyConfig, algorithm: Algorithm) -> Result<EncodingKey> {
+    use Algorithm::*;
+
+    let kid = &config.kid;
+    let key = match algorithm {
+        HS256 | HS384 | HS512 => {
+            let secret = config
+                .secret
+                .as_ref()
+                .ok_or_else(|| anyhow!("secret is required by key {kid}"))?;
+            EncodingKey::from_secret(secret.as_bytes())
+        }
+        RS256 | RS384 | RS512 | PS256 | PS384 | PS512 => {
+            EncodingKey::from_rsa_pem(&read_pem(&config.private_key, kid, "private_key")?)?
+        }
+        ES256 | ES384 => EncodingKey::from_ec_pem(&read_pem(&config.private_key, kid, "private_key")?)?,
+        EdDSA => EncodingKey::from_ed_pem(&read_pem(&config.private_key, kid, "private_key")?)?,
+    };
+    Ok(key)
+}
+
+fn load_decoding_key(config: &JwtKeyConfig, algorithm: Algorithm) -> Result<DecodingKey> {
+    use Algorithm::*;
+
+    let kid = &config.kid;
+    let key = match algorithm {
+        HS256 | HS384 | HS512 => {
+            let secret = config
+                .secret
+                .as_ref()
+                .ok_or_else(|| anyhow!("secret is required by key {kid}"))?;
+            DecodingKey::from_secret(secret.as_bytes())
+        }
+        RS256 | RS384 | RS512 | PS256 | PS384 | PS512 => {
+            DecodingKey::from_rsa_pem(&read_pem(&config.public_key, kid, "public_key")?)?
+        }
+        ES256 | ES384 => DecodingKey::from_ec_pem(&read_pem(&config.public_key, kid, "public_key")?)?,
+        EdDSA => DecodingKey::from_ed_pem(&read_pem(&config.public_key, kid, "public_key")?)?,
+    };
+    Ok(key)
+}
+
+fn load_keyring(secret: &str, jwt: Option<&JwtConfig>) -> Result<Keyring> {
+    let legacy = VerificationKey {
+        algorithm: Algorithm::HS256,
+        key: DecodingKey::from_secret(secret.as_bytes()),
+    };
+
+    let Some(jwt) = jwt else {
+        // No keyring configured, keep the old behavior.
+        return Ok(Keyring {
+            signing: SigningKey {
+                kid: None,
+                algorithm: Algorithm::HS256,
+                key: EncodingKey::from_secret(secret.as_bytes()),
+            },
+            verification: HashMap::new(),
+            legacy: Some(legacy),
+        });
+    };
+
+    let mut signing = None;
+    let mut verification = HashMap::new();
+    for key in &jwt.keys {
+        let algorithm =
+            Algorithm::from_str(&key.algorithm).with_context(|| format!("Unknown algorithm of key {}", key.kid))?;
+
+        if key.kid == jwt.active {
+            signing = Some(SigningKey {
+                kid: Some(key.kid.clone()),
+                algorithm,
+                key: load_encoding_key(key, algorithm)?,
+            });
+        }
+        let decoding_key = VerificationKey {
+            algorithm,
+            key: load_decoding_key(key, algorithm)?,
+        };
+        if verification.insert(key.kid.clone(), decoding_key).is_some() {
+            bail!("Duplicated kid {}", key.kid);
+        }
+    }
+    let signing = signing.ok_or_else(|| anyhow!("Active key {} is not in the keyring", jwt.active))?;
+    Ok(Keyring {
+        signing,
+        verification,
+        legacy: jwt.accept_legacy.then_some(legacy),
+    })
+}
+
+impl Keyring {
+    /// Key for signing new tokens.
+    pub fn signing_key(&self) -> &SigningKey {
+        &self.signing
+    }
+
+    /// Key for verifying a token whose header carries `kid`.
+    pub fn verification_key(&self, kid: Option<&str>) -> Option<&VerificationKey> {
+        match kid {
+            Some(kid) => self.verification.get(kid),
+            None => self.legacy.as_ref(),
+        }
+    }
+}
+
+/// Load keys on startup, so that a bad configuration is found as early as possible.
+pub fn initialize() {
+    let config = config::get();
+    let keyring = load_keyring(&config.secret, config.jwt.as_ref())
+        .unwrap_or_else(|e| panic!("Failed to load JWT keyring: {:?}", e));
+
+    let _ = KEYRING.set(keyring);
+}
+
+pub fn get() -> &'static Keyring {
+    KEYRING.get().expect("JWT keyring is not initialized.")
+}
+
+/// Keyring with a single key, for tests which issue tokens.
+#[cfg(test)]
+pub fn initialize_for_test() {
+    KEYRING.get_or_init(|| load_keyring("test secret", None).unwrap());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hmac_key(kid: &str, secret: &str) -> JwtKeyConfig {
+        JwtKeyConfig {
+            kid: kid.to_string(),
+            algorithm: "HS256".to_string(),
+            secret: Some(secret.to_string()),
+            private_key: None,
+            public_key: None,
+        }
+    }
+
+    fn jwt_config(accept_legacy: bool) -> JwtConfig {
+        JwtConfig {
+            active: "2023-09".to_string(),
+            keys: vec![hmac_key("2023-09", "new secret"), hmac_key("2023-03", "old secret")],
+            accept_legacy,
+        }
+    }
+
+    #[test]
+    fn test_without_keyring() {
+        let keyring = load_keyring("secret", None).unwrap();
+
+        assert!(keyring.signing_key().kid.is_none());
+        assert!(keyring.verification_key(None).is_some());
+        assert!(keyring.verification_key(Some("2023-09")).is_none());
+    }
+
+    #[test]
+    fn test_keyring() {
+        let keyring = load_keyring("secret", Some(&jwt_config(false))).unwrap();
+
+        assert_eq!(keyring.signing_key().kid.as_deref(), Some("2023-09"));
+        assert!(keyring.verification_key(Some("2023-09")).is_some());
+        assert!(keyring.verification_key(Some("2023-03")).is_some());
+        assert!(keyring.verification_key(Some("2022-09")).is_none());
+        // Legacy tokens are rejected unless accepted explicitly.
+        assert!(keyring.verification_key(None).is_none());
+
+        let keyring = load_keyring("secret", Some(&jwt_config(true))).unwrap();
+        assert!(keyring.verification_key(None).is_some());
+    }
+
+    #[test]
+    fn test_bad_keyring() {
+        let mut config = jwt_config(false);
+        config.active = "2022-09".to_string();
+        assert!(load_keyring("secret", Some(&config)).is_err());
+
+        let mut config = jwt_config(false);
+        config.keys.push(hmac_key("2023-03", "another secret"));
+        assert!(load_keyring("secret", Some(&config)).is_err());
+
+        let mut config = jwt_config(false);
+        config.keys[0].secret = None;
+        assert!(load_keyring("secret", Some(&config)).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::task::{Context, Poll};
+
+use http::{HeaderMap, Request, Response};
+use sqlx::PgPool;
+use tonic::body::BoxBody;
+use tonic::codegen::BoxFuture;
+use tonic::Status;
+use tower::{Layer, Service};
+
+use super::permission::required_role;
+use super::{store, JwtToken, Role};
+
+/// Tower layer which validates the `authorization` metadata once for every RPC, checks it against
+/// the role declared in the permission table, and attaches the `JwtToken` to request extensions.
+///
+/// Tokens are also checked against the database, so that revoked tokens and blocked users are
+/// rejected before the access token expires.
+#[derive(Clone)]
+pub struct AuthLayer {
+    db: PgPool,
+}
+
+impl AuthLayer {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            db: self.db.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    db: PgPool,
+}
+
+/// Parse token in header. Return `Ok(None)` if the header is absent. The status is boxed since it's large.
+fn parse_token(headers: &HeaderMap) -> Result<Option<JwtToken>, Box<Status>> {
+    let Some(value) = headers.get("authorization") else {
+        return Ok(None);
+    };
+    let token = value
+        .to_str()
+        .map_err(|e| Status::unauthenticated(format!("Failed to parse token to str: {:?}", e)))?;
+
+    JwtToken::decode(token)
+        .map(Some)
+        .ok_or_else(|| Box::new(Status::unauthenticated("Invalid token: May be expired?")))
+}
+
+async fn authenticate(db: &PgPool, required: Role, headers: &HeaderMap) -> Result<Option<JwtToken>, Status> {
+    if required == Role::Anonymous {
+        // Anonymous methods never read the identity, so don't bother the database.
+        return Ok(None);
+    }
+    let mut token = parse_token(headers)
+        .map_err(|e| *e)?
+        .ok_or_else(|| Status::unauthenticated("No authorization can be found in your request."))?;
+
+    // The role in the database is authoritative, the one in token may be outdated.
+    let role = store::get_valid_role(db, &token)
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .ok_or_else(|| Status::unauthenticated("Token is revoked or the account is blocked."))?;
+    token.role = role;
+
+    if token.role() < required {
+        return Err(Status::permission_denied("Permission denied."));
+    }
+    Ok(Some(token))
+}
+
+impl<S, B> Service<Request<B>> for AuthService<S>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        // The inner service is ready, take it and leave a clone for the next request.
+        // See https://docs.rs/tower/latest/tower/trait.Service.html#be-careful-when-cloning-inner-services
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let db = self.db.clone();
+        let required = required_role(req.uri().path());
+
+        Box::pin(async move {
+            match authenticate(&db, required, req.headers()).await {
+                Ok(token) => {
+                    if let Some(token) = token {
+                        req.extensions_mut().insert(token);
+                    }
+                    inner.call(req).await
+                }
+                Err(status) => Ok(status.to_http()),
+            }
+        })
+    }
+}
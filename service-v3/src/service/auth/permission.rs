@@ -0,0 +1,104 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::Role;
+
+/// Role required by each RPC, declared in one place.
+///
+/// Methods not listed here require a logged-in user, so that a newly added RPC is protected
+/// even if the author forgets to declare it.
+const PERMISSION_TABLE: &[(&str, Role)] = &[
+    // Infrastructure
+    ("/grpc.health.v1.Health/Check", Role::Anonymous),
+    ("/grpc.health.v1.Health/Watch", Role::Anonymous),
+    (
+        "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+        Role::Anonymous,
+    ),
+    ("/ping.PingService/Ping", Role::Anonymous),
+    // Public data
+    ("/balance.BalanceService/GetRoomBalance", Role::Anonymous),
+    ("/balance.BalanceService/GetConsumptionRank", Role::Anonymous),
+    ("/balance.BalanceService/GetBill", Role::Anonymous),
+    ("/board.BoardService/GetPictureList", Role::Anonymous),
+    ("/calendar.CalendarService/GetTermList", Role::Anonymous),
+    ("/calendar.CalendarService/GetDayOverride", Role::Anonymous),
+    ("/calendar.CalendarService/GetTermDay", Role::Anonymous),
+    ("/calendar.CalendarService/GetClassPeriod", Role::Anonymous),
+    ("/captcha.CaptchaService/Recognize", Role::Anonymous),
+    (
+        "/classroom_browser.ClassroomBrowserService/GetAvailableClassroom",
+        Role::Anonymous,
+    ),
+    (
+        "/classroom_browser.ClassroomBrowserService/GetAvailableClassroomByTime",
+        Role::Anonymous,
+    ),
+    (
+        "/classroom_browser.ClassroomBrowserService/GetClassroomSchedule",
+        Role::Anonymous,
+    ),
+    ("/library.LibraryService/Search", Role::Anonymous),
+    // Freshman, authenticated by the token in request message
+    ("/freshman.WelcomeService/CheckCredential", Role::Anonymous),
+    ("/freshman.WelcomeService/QueryMySelf", Role::Anonymous),
+    ("/freshman.WelcomeService/GetRoommates", Role::Anonymous),
+    ("/freshman.WelcomeService/GetClassmates", Role::Anonymous),
+    ("/freshman.WelcomeService/GetPeopleMayKnow", Role::Anonymous),
+    ("/freshman.WelcomeService/GetPersonalAnalysis", Role::Anonymous),
+    // Login
+    ("/user.UserService/Login", Role::Anonymous),
+    ("/user.UserService/RefreshToken", Role::Anonymous),
+    ("/user.UserService/Logout", Role::User),
+    // User resource
+    ("/badge.BadgeService/GetUserCardStorage", Role::User),
+    ("/badge.BadgeService/AppendShareLog", Role::User),
+    ("/board.BoardService/GetMyUpload", Role::User),
+    ("/board.BoardService/Upload", Role::User),
+    ("/campus_card.CampusCardService/ImportTransaction", Role::User),
+    ("/campus_card.CampusCardService/GetTransactionList", Role::User),
+    ("/campus_card.CampusCardService/GetDailyStatistics", Role::User),
+    ("/campus_card.CampusCardService/GetMonthlyStatistics", Role::User),
+    ("/campus_card.CampusCardService/GetCategoryStatistics", Role::User),
+    ("/exam.ExamService/GetExamList", Role::User),
+    ("/exam.ExamService/ExportExam", Role::User),
+    ("/exam.ExamService/GetExamReminder", Role::User),
+    ("/exam.ExamService/WatchExamReminder", Role::User),
+    ("/library.LibraryService/GetLoanList", Role::User),
+    ("/library.LibraryService/Renew", Role::User),
+    ("/library.LibraryService/GetDueReminder", Role::User),
+    ("/score.ScoreService/GetScoreList", Role::User),
+    ("/score.ScoreService/GetScoreUpdate", Role::User),
+    ("/second_class.SecondClassService/GetScore", Role::User),
+    ("/second_class.SecondClassService/GetUpcomingActivity", Role::User),
+    ("/timetable.TimetableService/GetTimetable", Role::User),
+    ("/timetable.TimetableService/ExportTimetable", Role::User),
+    // Administration
+    ("/calendar.CalendarService/SetTerm", Role::Admin),
+    ("/calendar.CalendarService/SetDayOverride", Role::Admin),
+    ("/calendar.CalendarService/DeleteDayOverride", Role::Admin),
+];
+
+/// Get the role required by a gRPC method, whose path is like `/package.Service/Method`.
+pub fn required_role(path: &str) -> Role {
+    PERMISSION_TABLE
+        .iter()
+        .find(|(method, _)| *method == path)
+        .map(|(_, role)| *role)
+        .unwrap_or(Role::User)
+}
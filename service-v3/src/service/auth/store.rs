@@ -0,0 +1,142 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Persistence of refresh tokens and revoked access tokens.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, TimeZone};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::model::{ToTimestamp, Uuid};
+use crate::service::gen::token::UserToken;
+
+use super::JwtToken;
+
+/// Lifetime of a refresh token in seconds.
+const REFRESH_TOKEN_LIFETIME: i64 = 30 * 24 * 3600;
+
+fn to_user_token(access: &JwtToken, refresh: Option<Uuid>) -> UserToken {
+    let expire_time = Local.timestamp_opt(access.exp, 0).unwrap();
+
+    UserToken {
+        uid: access.uid,
+        jwt_string: access.encode(),
+        expire_time: Some(ToTimestamp::datetime(expire_time)),
+        refresh_token: refresh.map(|token| token.to_string()),
+    }
+}
+
+async fn create_refresh_token<'c>(executor: impl PgExecutor<'c>, uid: i32) -> Result<Uuid> {
+    let token = Uuid::new_v4();
+    let expire_time: DateTime<Local> = Local::now() + Duration::seconds(REFRESH_TOKEN_LIFETIME);
+
+    sqlx::query("INSERT INTO user_refresh_token (token, uid, expire_time) VALUES ($1, $2, $3);")
+        .bind(token)
+        .bind(uid)
+        .bind(expire_time)
+        .execute(executor)
+        .await?;
+    Ok(token)
+}
+
+/// Issue an access token after the user logged in, and a refresh token if `with_refresh` is set.
+pub async fn issue(db: &PgPool, uid: i32, role: i32, with_refresh: bool) -> Result<UserToken> {
+    let access = JwtToken::new(uid, role);
+    let refresh = if with_refresh {
+        Some(create_refresh_token(db, uid).await?)
+    } else {
+        None
+    };
+
+    Ok(to_user_token(&access, refresh))
+}
+
+/// Exchange a refresh token for a new token pair. The old refresh token is revoked.
+///
+/// Return `None` if the refresh token is unknown, expired, revoked, or the user is blocked.
+pub async fn refresh(db: &PgPool, refresh_token: Uuid) -> Result<Option<UserToken>> {
+    let mut transaction = db.begin().await?;
+
+    let user: Option<(i32, i32, bool)> = sqlx::query_as(
+        "UPDATE user_refresh_token AS t SET revoked = TRUE
+            FROM user_account AS u
+            WHERE t.token = $1 AND t.uid = u.uid AND t.revoked = FALSE AND t.expire_time > current_timestamp
+            RETURNING u.uid, u.role, u.is_block;",
+    )
+    .bind(refresh_token)
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    let result = match user {
+        Some((uid, role, false)) => {
+            let access = JwtToken::new(uid, role);
+            let refresh = create_refresh_token(&mut *transaction, uid).await?;
+
+            Some(to_user_token(&access, Some(refresh)))
+        }
+        _ => None,
+    };
+    transaction.commit().await?;
+    Ok(result)
+}
+
+/// Revoke an access token before it expires.
+pub async fn revoke_access_token(db: &PgPool, token: &JwtToken) -> Result<()> {
+    let expire_time = Local.timestamp_opt(token.exp, 0).unwrap();
+
+    // Clean the outdated records by the way, they are rejected by `exp` validation anyway.
+    sqlx::query("DELETE FROM user_token_revocation WHERE expire_time < current_timestamp;")
+        .execute(db)
+        .await?;
+    sqlx::query(
+        "INSERT INTO user_token_revocation (jti, uid, expire_time) VALUES ($1, $2, $3)
+            ON CONFLICT (jti) DO NOTHING;",
+    )
+    .bind(token.jti)
+    .bind(token.uid)
+    .bind(expire_time)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Revoke a refresh token of the user.
+pub async fn revoke_refresh_token(db: &PgPool, uid: i32, refresh_token: Uuid) -> Result<()> {
+    sqlx::query("UPDATE user_refresh_token SET revoked = TRUE WHERE token = $1 AND uid = $2;")
+        .bind(refresh_token)
+        .bind(uid)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Check the access token against the database, so that logout and blocking take effect
+/// immediately. Return the current role of the user if the token is still valid.
+pub async fn get_valid_role(db: &PgPool, token: &JwtToken) -> Result<Option<i32>> {
+    sqlx::query_as(
+        "SELECT role FROM user_account
+            WHERE uid = $1 AND is_block = FALSE
+                AND NOT EXISTS (SELECT 1 FROM user_token_revocation WHERE jti = $2);",
+    )
+    .bind(token.uid)
+    .bind(token.jti)
+    .fetch_optional(db)
+    .await
+    .map(|row: Option<(i32,)>| row.map(|(role,)| role))
+    .map_err(Into::into)
+}
@@ -61,14 +61,14 @@ impl gen::badge_service_server::BadgeService for super::KiteGrpcServer {
         &self,
         request: Request<EmptyRequest>,
     ) -> Result<Response<gen::CardListResponse>, Status> {
-        let token = get_token_from_request(request)?;
+        let token = get_token_from_request(&request)?;
         let result = get_cards_list(&self.db, token.uid).await.map_err(ToStatus::to_status)?;
 
         Ok(Response::new(gen::CardListResponse { card_list: result }))
     }
 
     async fn append_share_log(&self, request: Request<EmptyRequest>) -> Result<Response<Empty>, Status> {
-        let token = get_token_from_request(request)?;
+        let token = get_token_from_request(&request)?;
 
         append_share_log(&self.db, token.uid)
             .await
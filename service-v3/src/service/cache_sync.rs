@@ -0,0 +1,68 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Erase cached results on every instance when the data behind them is changed, by Postgres notifications.
+
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+
+use crate::model::{calendar, card, edu};
+
+/// Channels to listen, and the cache scope erased on notification.
+const CHANNELS: &[(&str, u8)] = &[
+    (edu::IMPORT_CHANNEL, kite::cache::SCOPE_CLASSROOM),
+    (calendar::CHANGE_CHANNEL, kite::cache::SCOPE_CALENDAR),
+    (card::IMPORT_CHANNEL, kite::cache::SCOPE_CARD),
+];
+
+fn scope_of(channel: &str) -> Option<u8> {
+    CHANNELS.iter().find(|(c, _)| *c == channel).map(|(_, scope)| *scope)
+}
+
+/// Listen to `CHANNELS`, and erase the scope of each notification.
+pub async fn erase_on_notification(db: PgPool) {
+    let mut listener = match PgListener::connect_with(&db).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to connect to listen cache changes: {e}");
+            return;
+        }
+    };
+    let channels = CHANNELS.iter().map(|(channel, _)| *channel);
+    if let Err(e) = listener.listen_all(channels).await {
+        tracing::error!("Failed to listen cache changes: {e}");
+        return;
+    }
+
+    loop {
+        // The listener reconnects by itself, and notifications are lost in the meantime.
+        match listener.recv().await {
+            Ok(notification) => {
+                let channel = notification.channel();
+                tracing::info!("Notified on {} ({}), erase cache.", channel, notification.payload());
+                if let Some(scope) = scope_of(channel) {
+                    kite::cache::get().erase_keys(scope);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to receive cache changes: {e}");
+                tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use chrono::{Datelike, Local, NaiveDate, Weekday};
+use tonic::{Request, Response, Status};
+
+use crate::campus::jwxt::is_valid_term;
+use crate::error::ToStatus;
+use crate::model::calendar as model;
+pub use crate::service::gen::calendar as gen;
+use crate::service::gen::template::{Empty, EmptyRequest};
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+impl From<model::Term> for gen::Term {
+    fn from(term: model::Term) -> Self {
+        gen::Term {
+            year: term.year,
+            semester: term.semester,
+            begin_date: format_date(term.begin_date),
+            end_date: format_date(term.end_date),
+        }
+    }
+}
+
+impl From<model::DayOverride> for gen::DayOverride {
+    fn from(day: model::DayOverride) -> Self {
+        gen::DayOverride {
+            date: format_date(day.date),
+            follow_date: day.follow_date.map(format_date),
+            name: day.name,
+        }
+    }
+}
+
+impl From<model::TermDay> for gen::TermDay {
+    fn from(day: model::TermDay) -> Self {
+        gen::TermDay {
+            year: day.year,
+            semester: day.semester,
+            week: day.week,
+            day: day.day,
+            has_class: day.has_class,
+            note: day.note,
+        }
+    }
+}
+
+impl From<model::ClassPeriod> for gen::ClassPeriod {
+    fn from(period: model::ClassPeriod) -> Self {
+        gen::ClassPeriod {
+            index: period.index,
+            begin_time: period.begin_time.format("%H:%M").to_string(),
+            end_time: period.end_time.format("%H:%M").to_string(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl gen::calendar_service_server::CalendarService for super::KiteGrpcServer {
+    async fn get_term_list(&self, _request: Request<EmptyRequest>) -> Result<Response<gen::TermListResponse>, Status> {
+        let terms = model::list_terms(&self.db).await.map_err(ToStatus::to_status)?;
+
+        Ok(Response::new(gen::TermListResponse {
+            term_list: terms.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_day_override(
+        &self,
+        request: Request<gen::TermRequest>,
+    ) -> Result<Response<gen::DayOverrideList>, Status> {
+        let request = request.into_inner();
+        let calendar = model::get_term_calendar(&self.db, request.year, request.semester)
+            .await
+            .map_err(ToStatus::to_status)?
+            .ok_or_else(|| Status::not_found("No such term."))?;
+
+        Ok(Response::new(gen::DayOverrideList {
+            override_list: calendar.overrides.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_term_day(&self, request: Request<gen::DateRequest>) -> Result<Response<gen::TermDay>, Status> {
+        let date = match request.into_inner().date {
+            Some(date) => parse_date(&date).ok_or_else(|| Status::invalid_argument("Date should be in yyyy-MM-dd."))?,
+            None => Local::now().date_naive(),
+        };
+        let day = model::get_term_calendar_by_date(&self.db, date)
+            .await
+            .map_err(ToStatus::to_status)?
+            .and_then(|calendar| calendar.resolve(date))
+            .ok_or_else(|| Status::not_found("The date is not in any term."))?;
+
+        Ok(Response::new(day.into()))
+    }
+
+    async fn get_class_period(
+        &self,
+        request: Request<gen::ClassPeriodRequest>,
+    ) -> Result<Response<gen::ClassPeriodList>, Status> {
+        let campus = request.into_inner().campus;
+        let periods = model::get_class_periods(&self.db, campus)
+            .await
+            .map_err(ToStatus::to_status)?;
+
+        Ok(Response::new(gen::ClassPeriodList {
+            period_list: periods.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn set_term(&self, request: Request<gen::Term>) -> Result<Response<Empty>, Status> {
+        let request = request.into_inner();
+        if !is_valid_term(request.year, request.semester) {
+            return Err(Status::invalid_argument("Invalid year or semester."));
+        }
+        let (Some(begin_date), Some(end_date)) = (parse_date(&request.begin_date), parse_date(&request.end_date))
+        else {
+            return Err(Status::invalid_argument("Date should be in yyyy-MM-dd."));
+        };
+        if begin_date.weekday() != Weekday::Mon || begin_date > end_date {
+            return Err(Status::invalid_argument(
+                "Term should begin on Monday, and end after that.",
+            ));
+        }
+
+        let term = model::Term {
+            year: request.year,
+            semester: request.semester,
+            begin_date,
+            end_date,
+        };
+        model::save_term(&self.db, &term).await.map_err(ToStatus::to_status)?;
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn set_day_override(&self, request: Request<gen::DayOverride>) -> Result<Response<Empty>, Status> {
+        let request = request.into_inner();
+        let date =
+            parse_date(&request.date).ok_or_else(|| Status::invalid_argument("Date should be in yyyy-MM-dd."))?;
+        let follow_date = match request.follow_date {
+            Some(follow) => {
+                Some(parse_date(&follow).ok_or_else(|| Status::invalid_argument("Date should be in yyyy-MM-dd."))?)
+            }
+            None => None,
+        };
+        if follow_date == Some(date) {
+            return Err(Status::invalid_argument("A make-up day should follow another day."));
+        }
+
+        let day = model::DayOverride {
+            date,
+            follow_date,
+            name: request.name,
+        };
+        model::save_override(&self.db, &day)
+            .await
+            .map_err(ToStatus::to_status)?;
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn delete_day_override(&self, request: Request<gen::DateRequest>) -> Result<Response<Empty>, Status> {
+        let date = request
+            .into_inner()
+            .date
+            .as_deref()
+            .and_then(parse_date)
+            .ok_or_else(|| Status::invalid_argument("Date should be in yyyy-MM-dd."))?;
+
+        if !model::delete_override(&self.db, date)
+            .await
+            .map_err(ToStatus::to_status)?
+        {
+            return Err(Status::not_found("No override on the date."));
+        }
+        Ok(Response::new(Empty {}))
+    }
+}
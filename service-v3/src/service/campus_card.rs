@@ -0,0 +1,175 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use chrono::{Duration, Local, NaiveDate};
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+
+use crate::campus::{card, CampusSession};
+use crate::error::ToStatus;
+use crate::model::card as model;
+use crate::model::ToTimestamp;
+use crate::service::auth::get_token_from_request;
+pub use crate::service::gen::campus_card as gen;
+use crate::service::template::ToPageView;
+
+/// Days of transactions imported by default.
+const DEFAULT_IMPORT_DAYS: i32 = 30;
+const MAX_IMPORT_DAYS: i32 = 180;
+/// Longest date range of statistics.
+const MAX_STATISTICS_DAYS: i64 = 366;
+
+impl From<model::Transaction> for gen::Transaction {
+    fn from(t: model::Transaction) -> Self {
+        gen::Transaction {
+            time: Some(ToTimestamp::datetime(t.ts)),
+            amount: t.amount,
+            balance: t.balance,
+            merchant: t.merchant,
+            kind: t.kind,
+            category: t.category,
+        }
+    }
+}
+
+impl From<model::PeriodStatistics> for gen::PeriodStatistics {
+    fn from(s: model::PeriodStatistics) -> Self {
+        gen::PeriodStatistics {
+            period: s.period,
+            income: s.income,
+            expense: s.expense,
+        }
+    }
+}
+
+impl From<model::CategoryStatistics> for gen::CategoryStatistics {
+    fn from(s: model::CategoryStatistics) -> Self {
+        gen::CategoryStatistics {
+            category: s.category,
+            expense: s.expense,
+            count: s.count,
+        }
+    }
+}
+
+/// Parse and validate the date range.
+fn date_range(range: gen::DateRange) -> Option<(NaiveDate, NaiveDate)> {
+    let from = NaiveDate::parse_from_str(&range.from, "%Y-%m-%d").ok()?;
+    let to = NaiveDate::parse_from_str(&range.to, "%Y-%m-%d").ok()?;
+    if from > to || (to - from).num_days() >= MAX_STATISTICS_DAYS {
+        return None;
+    }
+    Some((from, to))
+}
+
+async fn fetch_transaction(db: &PgPool, uid: i32, days: i32) -> anyhow::Result<Vec<model::Transaction>> {
+    let mut campus = CampusSession::load(db, uid).await?;
+    let mut session = campus.service(card::SERVICE_URL)?;
+
+    let today = Local::now().date_naive();
+    card::fetch(&mut session, today - Duration::days(days as i64 - 1), today).await
+}
+
+#[tonic::async_trait]
+impl gen::campus_card_service_server::CampusCardService for super::KiteGrpcServer {
+    async fn import_transaction(
+        &self,
+        request: Request<gen::ImportRequest>,
+    ) -> Result<Response<gen::ImportResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let request = request.into_inner();
+        let days = request.days.unwrap_or(DEFAULT_IMPORT_DAYS);
+        if !(1..=MAX_IMPORT_DAYS).contains(&days) {
+            return Err(Status::invalid_argument("Days should be 1 - 180."));
+        }
+
+        let transactions = fetch_transaction(&self.db, token.uid, days)
+            .await
+            .map_err(ToStatus::to_status)?;
+        let imported = model::save(&self.db, token.uid, &transactions)
+            .await
+            .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::ImportResponse {
+            imported: imported as i32,
+        }))
+    }
+
+    async fn get_transaction_list(
+        &self,
+        request: Request<gen::TransactionListRequest>,
+    ) -> Result<Response<gen::TransactionListResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let request = request.into_inner();
+        let page = request.page.map(ToPageView::page_option).unwrap_or_default();
+
+        let transactions = model::list(&self.db, token.uid, request.category.as_deref(), &page)
+            .await
+            .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::TransactionListResponse {
+            transaction_list: transactions.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_daily_statistics(
+        &self,
+        request: Request<gen::DateRange>,
+    ) -> Result<Response<gen::StatisticsResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let (from, to) =
+            date_range(request.into_inner()).ok_or_else(|| Status::invalid_argument("Invalid date range."))?;
+
+        let statistics = model::get_daily_statistics(&self.db, token.uid, from, to)
+            .await
+            .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::StatisticsResponse {
+            statistics_list: statistics.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_monthly_statistics(
+        &self,
+        request: Request<gen::DateRange>,
+    ) -> Result<Response<gen::StatisticsResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let (from, to) =
+            date_range(request.into_inner()).ok_or_else(|| Status::invalid_argument("Invalid date range."))?;
+
+        let statistics = model::get_monthly_statistics(&self.db, token.uid, from, to)
+            .await
+            .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::StatisticsResponse {
+            statistics_list: statistics.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_category_statistics(
+        &self,
+        request: Request<gen::DateRange>,
+    ) -> Result<Response<gen::CategoryStatisticsResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let (from, to) =
+            date_range(request.into_inner()).ok_or_else(|| Status::invalid_argument("Invalid date range."))?;
+
+        let statistics = model::get_category_statistics(&self.db, token.uid, from, to)
+            .await
+            .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::CategoryStatisticsResponse {
+            statistics_list: statistics.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
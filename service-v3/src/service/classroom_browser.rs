@@ -16,11 +16,15 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
+use chrono::{Duration, Local, NaiveTime};
 use sqlx::PgPool;
 use tonic::{Request, Response, Status};
 
 use crate::error::ToStatus;
+use crate::model::calendar;
 use crate::model::classroom_browser as model;
+use crate::model::edu;
+use crate::model::{ToDateTime, ToTimestamp};
 pub use crate::service::gen::classroom_browser as gen;
 
 impl Into<model::ClassroomQuery> for gen::ClassroomQuery {
@@ -32,6 +36,10 @@ impl Into<model::ClassroomQuery> for gen::ClassroomQuery {
             week: self.week,
             day: self.day,
             want_time: self.time_flag,
+            min_capacity: self.min_capacity,
+            room_type: self.room_type,
+            multimedia: self.multimedia,
+            floor: self.floor,
         }
     }
 }
@@ -39,21 +47,29 @@ impl Into<model::ClassroomQuery> for gen::ClassroomQuery {
 impl Into<gen::Classroom> for model::Classroom {
     fn into(self) -> gen::Classroom {
         gen::Classroom {
+            free_window: model::longest_free_window(self.busy_flag),
             title: self.title,
             busy_flag: self.busy_flag,
             capacity: self.capacity,
+            room_type: self.room_type,
+            multimedia: self.multimedia,
+            floor: self.floor,
         }
     }
 }
 
-#[kite::cache_result(timeout = 43200)]
+#[kite::cache_result(timeout = 43200, scope = "kite::cache::SCOPE_CLASSROOM")]
 pub async fn query_avail_classroom(
     db: &PgPool,
     query: &model::ClassroomQuery,
 ) -> anyhow::Result<Vec<model::Classroom>> {
     sqlx::query_as(
-        "SELECT room, busy_time::int, capacity::int \
-            FROM edu.query_available_classrooms($1, $2, $3, $4, $5, $6);",
+        "SELECT r.room AS title, r.busy_time::int AS busy_flag, r.capacity::int AS capacity, \
+                c.room_type, c.multimedia, c.floor \
+            FROM edu.query_available_classrooms($1, $2, $3, $4, $5, $6) AS r \
+            JOIN edu.classroom c ON c.title = r.room \
+            WHERE ($7::int IS NULL OR r.capacity >= $7) AND ($8::int IS NULL OR c.room_type = $8) \
+                AND (NOT $9 OR c.multimedia) AND ($10::int IS NULL OR c.floor = $10);",
     )
     .bind(&query.campus)
     .bind(&query.building)
@@ -61,29 +77,225 @@ pub async fn query_avail_classroom(
     .bind(query.week)
     .bind(query.day)
     .bind(query.want_time.unwrap_or(!0))
+    .bind(query.min_capacity)
+    .bind(query.room_type)
+    .bind(query.multimedia)
+    .bind(query.floor)
     .fetch_all(db)
     .await
     .map_err(Into::into)
 }
 
+#[kite::cache_result(timeout = 43200, scope = "kite::cache::SCOPE_CLASSROOM")]
+pub async fn query_buildings(db: &PgPool) -> anyhow::Result<Vec<edu::Building>> {
+    edu::list_buildings(db).await
+}
+
+/// Weekly occupancy of a room, or `None` if the room is unknown.
+#[kite::cache_result(timeout = 43200, scope = "kite::cache::SCOPE_CLASSROOM")]
+pub async fn query_classroom_schedule(
+    db: &PgPool,
+    room: String,
+    week: i32,
+    campus: Option<i32>,
+) -> anyhow::Result<Option<model::ClassroomSchedule>> {
+    let (building, region) = match model::split_room_title(&room) {
+        Some((building, region)) => (Some(building), region),
+        None => (None, None),
+    };
+    let mut capacity = None;
+    let mut busy_flags = [0; 7];
+    let mut found = false;
+
+    for day in 1..=7 {
+        let query = model::ClassroomQuery {
+            building: building.clone(),
+            region: region.clone(),
+            campus,
+            week,
+            day,
+            want_time: Some(0),
+            min_capacity: None,
+            room_type: None,
+            multimedia: false,
+            floor: None,
+        };
+        let classroom = query_avail_classroom(db, &query)
+            .await?
+            .into_iter()
+            .find(|classroom| classroom.title == room);
+        if let Some(classroom) = classroom {
+            found = true;
+            capacity = classroom.capacity;
+            busy_flags[day as usize - 1] = classroom.busy_flag;
+        }
+    }
+    if !found {
+        return Ok(None);
+    }
+
+    // Course names are looked up in the current term.
+    let today = Local::now().date_naive();
+    let courses = match calendar::get_term_calendar_by_date(db, today).await? {
+        Some(calendar) => {
+            let term = &calendar.term;
+            edu::get_courses_in_room(db, term.year, term.semester, &room, week).await?
+        }
+        None => vec![],
+    };
+
+    Ok(Some(model::ClassroomSchedule {
+        room: room.clone(),
+        capacity,
+        week,
+        days: model::build_schedule_grid(&busy_flags, &courses),
+    }))
+}
+
 #[tonic::async_trait]
 impl gen::classroom_browser_service_server::ClassroomBrowserService for super::KiteGrpcServer {
     async fn get_available_classroom(
         &self,
         request: Request<gen::ClassroomQuery>,
     ) -> Result<Response<gen::ClassroomListResponse>, Status> {
-        let query = request.into_inner().into();
+        let request = request.into_inner();
+        let ranking = request.ranking();
+        let near_building = request.near_building.clone();
+        let query = request.into();
 
-        // TODO: Add cache
-        query_avail_classroom(&self.db, &query)
+        let mut classroom_list = query_avail_classroom(&self.db, &query)
             .await
-            .map_err(ToStatus::to_status)
-            .map(|classroom_list| {
-                let results = classroom_list.into_iter().map(Into::into).collect();
+            .map_err(ToStatus::to_status)?;
+        match ranking {
+            gen::ClassroomRanking::Title => {}
+            gen::ClassroomRanking::FreeWindow => model::rank_by_free_window(&mut classroom_list),
+            gen::ClassroomRanking::Distance => {
+                let near_building =
+                    near_building.ok_or_else(|| Status::invalid_argument("near_building is required."))?;
+                let buildings = query_buildings(&self.db).await.map_err(ToStatus::to_status)?;
+                model::rank_by_distance(&mut classroom_list, &near_building, &buildings);
+            }
+        }
+
+        let results = classroom_list.into_iter().map(Into::into).collect();
+        Ok(Response::new(gen::ClassroomListResponse {
+            classroom_list: results,
+        }))
+    }
 
-                Response::new(gen::ClassroomListResponse {
-                    classroom_list: results,
-                })
+    async fn get_available_classroom_by_time(
+        &self,
+        request: Request<gen::TimeClassroomQuery>,
+    ) -> Result<Response<gen::FreeClassroomListResponse>, Status> {
+        let request = request.into_inner();
+        if !(0..=24 * 60).contains(&request.duration_minutes) {
+            return Err(Status::invalid_argument("duration_minutes should be 0 - 1440."));
+        }
+        let begin = match request.time {
+            Some(time) => time
+                .timestamp()
+                .ok_or_else(|| Status::invalid_argument("time is out of range."))?,
+            None => Local::now(),
+        };
+        let date = begin.date_naive();
+        // The range is cut at the end of the day.
+        let end = begin + Duration::minutes(request.duration_minutes as i64);
+        let end_time = if end.date_naive() == date {
+            end.time()
+        } else {
+            NaiveTime::from_hms_opt(23, 59, 59).unwrap()
+        };
+
+        let term_day = calendar::get_term_calendar_by_date(&self.db, date)
+            .await
+            .map_err(ToStatus::to_status)?
+            .and_then(|calendar| calendar.resolve(date))
+            .ok_or_else(|| Status::not_found("The time is not in any term."))?;
+        let periods = calendar::get_class_periods(&self.db, request.campus.unwrap_or(calendar::DEFAULT_CAMPUS))
+            .await
+            .map_err(ToStatus::to_status)?;
+        // No class on holidays, so every room is free.
+        let time_flag = if term_day.has_class {
+            model::convert_time_range_to_binary(&periods, begin.time(), end_time)
+        } else {
+            0
+        };
+
+        let query = model::ClassroomQuery {
+            building: request.building,
+            region: request.region,
+            campus: request.campus,
+            week: term_day.week,
+            day: term_day.day,
+            want_time: Some(time_flag),
+            min_capacity: None,
+            room_type: None,
+            multimedia: false,
+            floor: None,
+        };
+        let classroom_list = query_avail_classroom(&self.db, &query)
+            .await
+            .map_err(ToStatus::to_status)?;
+
+        let end_of_day = date.succ_opt().unwrap().and_time(NaiveTime::MIN);
+        let results = classroom_list
+            .into_iter()
+            .map(|room| {
+                let busy_flag = if term_day.has_class { room.busy_flag } else { 0 };
+                let free_until =
+                    model::calculate_free_until(busy_flag, &periods, begin.time()).map(|t| date.and_time(t));
+                let free_minutes = (free_until.unwrap_or(end_of_day) - begin.naive_local()).num_minutes();
+
+                gen::FreeClassroom {
+                    title: room.title,
+                    capacity: room.capacity,
+                    busy_flag,
+                    free_until: free_until.map(ToTimestamp::datetime),
+                    free_minutes: free_minutes as i32,
+                }
+            })
+            .collect();
+        Ok(Response::new(gen::FreeClassroomListResponse {
+            classroom_list: results,
+            week: term_day.week,
+            day: term_day.day,
+            time_flag,
+        }))
+    }
+
+    async fn get_classroom_schedule(
+        &self,
+        request: Request<gen::ScheduleQuery>,
+    ) -> Result<Response<gen::ClassroomScheduleResponse>, Status> {
+        let request = request.into_inner();
+        if !(1..=30).contains(&request.week) {
+            return Err(Status::invalid_argument("week should be 1 - 30."));
+        }
+
+        let schedule = query_classroom_schedule(&self.db, request.room, request.week, request.campus)
+            .await
+            .map_err(ToStatus::to_status)?
+            .ok_or_else(|| Status::not_found("No such classroom."))?;
+        let day_list = schedule
+            .days
+            .into_iter()
+            .zip(1..)
+            .map(|(slots, day)| gen::DaySchedule {
+                day,
+                slot_list: slots
+                    .into_iter()
+                    .map(|slot| gen::ScheduleSlot {
+                        busy: slot.busy,
+                        course_name: slot.course_name,
+                    })
+                    .collect(),
             })
+            .collect();
+        Ok(Response::new(gen::ClassroomScheduleResponse {
+            room: schedule.room,
+            capacity: schedule.capacity,
+            week: schedule.week,
+            day_list,
+        }))
     }
 }
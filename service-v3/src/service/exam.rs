@@ -0,0 +1,259 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::pin::Pin;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tonic::{Request, Response, Status};
+
+use crate::campus::{self, jwxt, CampusSession};
+use crate::error::ToStatus;
+use crate::ical::{Calendar, Event};
+use crate::model::ToTimestamp;
+use crate::model::{exam as model, notification};
+use crate::service::auth::get_token_from_request;
+pub use crate::service::gen::exam as gen;
+use crate::service::gen::template::EmptyRequest;
+
+/// Count of recent reminders returned.
+const REMINDER_LIMIT: i64 = 20;
+/// Interval to check new reminders for watchers.
+const WATCH_INTERVAL: Duration = Duration::from_secs(60);
+
+type ReminderStream = Pin<Box<dyn Stream<Item = Result<gen::ExamReminder, Status>> + Send>>;
+
+impl From<model::Exam> for gen::Exam {
+    fn from(exam: model::Exam) -> Self {
+        gen::Exam {
+            course_name: exam.course_name,
+            course_code: exam.course_code,
+            exam_name: exam.exam_name,
+            start_time: exam.start.map(ToTimestamp::datetime),
+            end_time: exam.end.map(ToTimestamp::datetime),
+            place: exam.place,
+            campus: exam.campus,
+            building: exam.building,
+            region: exam.region,
+            seat: exam.seat,
+        }
+    }
+}
+
+impl From<notification::Notification> for gen::ExamReminder {
+    fn from(n: notification::Notification) -> Self {
+        gen::ExamReminder {
+            id: n.id,
+            course_name: n.title,
+            content: n.content,
+            ts: Some(ToTimestamp::datetime(n.create_time)),
+        }
+    }
+}
+
+/// Fetch and save, returning the update time too.
+async fn fetch_exam(
+    db: &PgPool,
+    uid: i32,
+    year: i32,
+    semester: i32,
+) -> anyhow::Result<(Vec<model::Exam>, DateTime<Local>)> {
+    let mut campus = CampusSession::load(db, uid).await?;
+    let mut session = campus.service(jwxt::SERVICE_URL)?;
+
+    let exams = jwxt::exam::fetch(&mut session, year, semester).await?;
+    let update_time = model::save(db, uid, year, semester, &exams).await?;
+    Ok((exams, update_time))
+}
+
+fn to_calendar(exams: &[model::Exam], alarm: Option<i32>) -> Calendar {
+    let mut calendar = Calendar::new("考试安排");
+
+    for exam in exams {
+        // Exams not arranged yet are skipped.
+        let (Some(start), Some(end)) = (exam.start, exam.end) else {
+            continue;
+        };
+        let description = match exam.seat.as_str() {
+            "" => exam.exam_name.clone(),
+            seat => format!("{}\n座位号: {}", exam.exam_name, seat),
+        };
+        calendar.push(Event {
+            uid: format!(
+                "exam-{}-{}@kite.sit.edu.cn",
+                exam.course_code,
+                start.format("%Y%m%d%H%M")
+            ),
+            summary: format!("{} 考试", exam.course_name),
+            location: exam.place.clone(),
+            description,
+            start,
+            end,
+            alarm,
+        });
+    }
+    calendar
+}
+
+/// Send reminders created after `last_id` until the client disconnects.
+async fn watch_task(db: PgPool, uid: i32, mut last_id: i32, tx: mpsc::Sender<Result<gen::ExamReminder, Status>>) {
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+
+    while !tx.is_closed() {
+        interval.tick().await;
+        let reminders = match notification::list_after(&db, uid, model::NOTIFICATION_KIND, last_id).await {
+            Ok(reminders) => reminders,
+            Err(e) => {
+                let _ = tx.send(Err(e.to_status())).await;
+                return;
+            }
+        };
+        for reminder in reminders {
+            last_id = reminder.id;
+            if tx.send(Ok(reminder.into())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl gen::exam_service_server::ExamService for super::KiteGrpcServer {
+    async fn get_exam_list(
+        &self,
+        request: Request<gen::ExamRequest>,
+    ) -> Result<Response<gen::ExamListResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let request = request.into_inner();
+        let (year, semester) = (request.year, request.semester);
+        if !jwxt::is_valid_term(year, semester) {
+            return Err(Status::invalid_argument("Invalid year or semester."));
+        }
+
+        let (exams, update_time) = campus::refresh_or_get(
+            request.refresh,
+            fetch_exam(&self.db, token.uid, year, semester),
+            model::get(&self.db, token.uid, year, semester),
+        )
+        .await
+        .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::ExamListResponse {
+            exam_list: exams.into_iter().map(Into::into).collect(),
+            update_time: Some(ToTimestamp::datetime(update_time)),
+        }))
+    }
+
+    async fn export_exam(
+        &self,
+        request: Request<gen::ExportRequest>,
+    ) -> Result<Response<gen::ICalendarResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let request = request.into_inner();
+        if !jwxt::is_valid_term(request.year, request.semester) {
+            return Err(Status::invalid_argument("Invalid year or semester."));
+        }
+        if request.alarm_minutes.is_some_and(|m| m < 0) {
+            return Err(Status::invalid_argument("alarm_minutes should not be negative."));
+        }
+
+        let (exams, _) = model::get(&self.db, token.uid, request.year, request.semester)
+            .await
+            .map_err(ToStatus::to_status)?
+            .ok_or_else(|| Status::not_found("No exam is cached, please fetch it first."))?;
+        let calendar = to_calendar(&exams, request.alarm_minutes);
+
+        Ok(Response::new(gen::ICalendarResponse {
+            content: calendar.to_string(),
+        }))
+    }
+
+    async fn get_exam_reminder(
+        &self,
+        request: Request<EmptyRequest>,
+    ) -> Result<Response<gen::ExamReminderList>, Status> {
+        let token = get_token_from_request(&request)?;
+
+        let reminders = notification::list(&self.db, token.uid, model::NOTIFICATION_KIND, REMINDER_LIMIT)
+            .await
+            .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::ExamReminderList {
+            reminder_list: reminders.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    type WatchExamReminderStream = ReminderStream;
+
+    async fn watch_exam_reminder(
+        &self,
+        request: Request<EmptyRequest>,
+    ) -> Result<Response<Self::WatchExamReminderStream>, Status> {
+        let token = get_token_from_request(&request)?;
+
+        // Only reminders created after subscription are pushed, the earlier ones can be got by polling.
+        let last_id = notification::list(&self.db, token.uid, model::NOTIFICATION_KIND, 1)
+            .await
+            .map_err(ToStatus::to_status)?
+            .first()
+            .map(|n| n.id)
+            .unwrap_or_default();
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(watch_task(self.db.clone(), token.uid, last_id, tx));
+
+        Ok(Response::new(
+            Box::pin(ReceiverStream::new(rx)) as Self::WatchExamReminderStream
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn test_to_calendar() {
+        let date = NaiveDate::from_ymd_opt(2023, 6, 20).unwrap();
+        let exam = model::Exam {
+            course_name: "高等数学".to_string(),
+            course_code: "B1220012".to_string(),
+            exam_name: "期末考试".to_string(),
+            start: Some(date.and_hms_opt(9, 0, 0).unwrap()),
+            end: Some(date.and_hms_opt(11, 0, 0).unwrap()),
+            place: "一教A101".to_string(),
+            campus: "奉贤校区".to_string(),
+            building: Some("一教".to_string()),
+            region: Some("A".to_string()),
+            seat: "12".to_string(),
+        };
+        let unarranged = model::Exam {
+            start: None,
+            end: None,
+            ..exam.clone()
+        };
+        let calendar = to_calendar(&[exam, unarranged], Some(60));
+
+        assert_eq!(calendar.events.len(), 1);
+        assert_eq!(calendar.events[0].uid, "exam-B1220012-202306200900@kite.sit.edu.cn");
+        assert_eq!(calendar.events[0].description, "期末考试\n座位号: 12");
+    }
+}
@@ -0,0 +1,264 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::net::IpAddr;
+
+use chrono::{Duration, Local};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tonic::{Request, Response, Status};
+
+use crate::error::ToStatus;
+use crate::limit::RateLimiter;
+use crate::model::freshman as model;
+use crate::model::ToTimestamp;
+use crate::service::auth::{decode_claims, encode_claims};
+pub use crate::service::gen::freshman as gen;
+use crate::service::gen::token::UserToken;
+
+/// Lifetime of a freshman token in seconds. No refresh token is issued, the freshman checks credential again.
+const FRESHMAN_TOKEN_LIFETIME: i64 = 7 * 24 * 3600;
+
+/// `typ` in the header of freshman tokens, so that it can't be used as a user token, and vice versa.
+const FRESHMAN_TOKEN_TYPE: &str = "freshman+jwt";
+
+/// Credential checks of an account in 15 minutes, which are forgiven on success. The secret is short, so
+/// guessing is slowed down. Each check is counted before it's verified, so concurrent guesses are counted too.
+static ACCOUNT_CHECK_LIMITER: Lazy<RateLimiter<String>> =
+    Lazy::new(|| RateLimiter::new(5, std::time::Duration::from_secs(15 * 60)));
+/// Credential checks from a client in 15 minutes, across accounts.
+static CLIENT_CHECK_LIMITER: Lazy<RateLimiter<IpAddr>> =
+    Lazy::new(|| RateLimiter::new(20, std::time::Duration::from_secs(15 * 60)));
+/// `last_seen` is written at most once in 5 minutes by a freshman, and most visits don't reach the database.
+static TOUCH_LIMITER: Lazy<RateLimiter<i32>> =
+    Lazy::new(|| RateLimiter::new(1, std::time::Duration::from_secs(5 * 60)));
+
+/// Claims of the token issued to freshmen, who have no user account yet.
+#[derive(Serialize, Deserialize)]
+struct FreshmanClaims {
+    /// 新生 ID, `freshman.fid`
+    fid: i32,
+    entrance_year: i32,
+    iat: i64,
+    exp: i64,
+}
+
+fn issue_token(fid: i32, entrance_year: i32) -> UserToken {
+    let now = Local::now();
+    let expire_time = now + Duration::seconds(FRESHMAN_TOKEN_LIFETIME);
+    let claims = FreshmanClaims {
+        fid,
+        entrance_year,
+        iat: now.timestamp(),
+        exp: expire_time.timestamp(),
+    };
+
+    UserToken {
+        uid: fid,
+        jwt_string: encode_claims(&claims, FRESHMAN_TOKEN_TYPE),
+        expire_time: Some(ToTimestamp::datetime(expire_time)),
+        refresh_token: None,
+    }
+}
+
+/// Get fid from the token in request. The entrance year in request, if any, should be the one in the token.
+fn verify_token(request: &gen::FreshmanToken) -> Option<i32> {
+    let token = request.token.as_ref()?;
+    let claims = decode_claims::<FreshmanClaims>(&token.jwt_string, FRESHMAN_TOKEN_TYPE)?;
+    match &request.entrance_year {
+        Some(year) if year.parse() != Ok(claims.entrance_year) => None,
+        _ => Some(claims.fid),
+    }
+}
+
+impl From<model::Freshman> for gen::MySelf {
+    fn from(freshman: model::Freshman) -> Self {
+        gen::MySelf {
+            name: freshman.name,
+            student_id: freshman.student_id,
+            college: freshman.college,
+            major: freshman.major,
+            campus: freshman.campus,
+            building: freshman.building,
+            room: freshman.room,
+            bed_index: freshman.bed_index,
+            visible: freshman.visible,
+            contact: freshman.contact,
+        }
+    }
+}
+
+impl From<model::Freshman> for gen::Student {
+    fn from(freshman: model::Freshman) -> Self {
+        gen::Student {
+            college: freshman.college,
+            major: freshman.major,
+            name: freshman.name,
+            building: freshman.building,
+            room: freshman.room,
+            bed_index: freshman.bed_index,
+            gender: freshman.gender,
+            last_seen: freshman.last_seen.map(ToTimestamp::datetime),
+            contact: freshman.contact.unwrap_or_default(),
+            province: freshman.province,
+            city: freshman.city,
+        }
+    }
+}
+
+fn to_student_list(list: Vec<model::Freshman>) -> gen::StudentList {
+    gen::StudentList {
+        student_list: list.into_iter().map(Into::into).collect(),
+    }
+}
+
+impl super::KiteGrpcServer {
+    /// Verify the token and record the visit.
+    async fn get_fid(&self, request: &gen::FreshmanToken) -> Result<i32, Status> {
+        let fid = verify_token(request).ok_or_else(|| Status::unauthenticated("Invalid freshman token."))?;
+        if TOUCH_LIMITER.acquire(fid).is_ok() {
+            model::touch(&self.db, fid).await.map_err(ToStatus::to_status)?;
+        }
+        Ok(fid)
+    }
+}
+
+#[tonic::async_trait]
+impl gen::welcome_service_server::WelcomeService for super::KiteGrpcServer {
+    async fn check_credential(&self, request: Request<gen::FreshmanCredential>) -> Result<Response<UserToken>, Status> {
+        let client = request.remote_addr().map(|address| address.ip());
+        let request = request.into_inner();
+        let entrance_year = request
+            .entrance_year
+            .map(|year| year.parse::<i32>())
+            .transpose()
+            .map_err(|_| Status::invalid_argument("Invalid entrance_year."))?;
+
+        let account = request.account;
+        let too_many = |_| Status::resource_exhausted("Too many failures, please try again later.");
+        if let Some(ip) = client {
+            CLIENT_CHECK_LIMITER.acquire(ip).map_err(too_many)?;
+        }
+        ACCOUNT_CHECK_LIMITER.acquire(account.clone()).map_err(too_many)?;
+
+        let (fid, entrance_year) = model::authenticate(&self.db, &account, &request.secret, entrance_year)
+            .await
+            .map_err(ToStatus::to_status)?
+            .ok_or_else(|| Status::unauthenticated("Wrong account or secret."))?;
+        ACCOUNT_CHECK_LIMITER.reset(&account);
+        Ok(Response::new(issue_token(fid, entrance_year)))
+    }
+
+    async fn query_my_self(&self, request: Request<gen::FreshmanToken>) -> Result<Response<gen::MySelf>, Status> {
+        let fid = self.get_fid(request.get_ref()).await?;
+
+        model::get(&self.db, fid)
+            .await
+            .map_err(ToStatus::to_status)?
+            .map(|freshman| Response::new(freshman.into()))
+            .ok_or_else(|| Status::not_found("No such freshman."))
+    }
+
+    async fn get_roommates(&self, request: Request<gen::FreshmanToken>) -> Result<Response<gen::StudentList>, Status> {
+        let fid = self.get_fid(request.get_ref()).await?;
+
+        model::get_roommates(&self.db, fid)
+            .await
+            .map_err(ToStatus::to_status)
+            .map(|list| Response::new(to_student_list(list)))
+    }
+
+    async fn get_classmates(&self, request: Request<gen::FreshmanToken>) -> Result<Response<gen::StudentList>, Status> {
+        let fid = self.get_fid(request.get_ref()).await?;
+
+        model::get_classmates(&self.db, fid)
+            .await
+            .map_err(ToStatus::to_status)
+            .map(|list| Response::new(to_student_list(list)))
+    }
+
+    async fn get_people_may_know(
+        &self,
+        request: Request<gen::FreshmanToken>,
+    ) -> Result<Response<gen::StudentList>, Status> {
+        let fid = self.get_fid(request.get_ref()).await?;
+
+        model::get_people_may_know(&self.db, fid)
+            .await
+            .map_err(ToStatus::to_status)
+            .map(|list| Response::new(to_student_list(list)))
+    }
+
+    async fn get_personal_analysis(
+        &self,
+        request: Request<gen::FreshmanToken>,
+    ) -> Result<Response<gen::PersonalAnalysisResult>, Status> {
+        let fid = self.get_fid(request.get_ref()).await?;
+
+        let analysis = model::get_analysis(&self.db, fid)
+            .await
+            .map_err(ToStatus::to_status)?
+            .ok_or_else(|| Status::not_found("No such freshman."))?;
+        Ok(Response::new(gen::PersonalAnalysisResult {
+            same_name: analysis.same_name as u32,
+            same_city: analysis.same_city as u32,
+            same_high_school: analysis.same_high_school as u32,
+            college_count: analysis.college_count as u32,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::service::auth::{keyring, JwtToken};
+
+    use super::*;
+
+    fn request(token: UserToken, entrance_year: Option<&str>) -> gen::FreshmanToken {
+        gen::FreshmanToken {
+            token: Some(token),
+            entrance_year: entrance_year.map(ToString::to_string),
+        }
+    }
+
+    #[test]
+    fn test_freshman_token() {
+        keyring::initialize_for_test();
+        let token = issue_token(42, 2023);
+
+        assert_eq!(verify_token(&request(token.clone(), None)), Some(42));
+        assert_eq!(verify_token(&request(token.clone(), Some("2023"))), Some(42));
+        assert_eq!(verify_token(&request(token.clone(), Some("2022"))), None);
+        // A freshman token is not a user token.
+        assert!(JwtToken::decode(&token.jwt_string).is_none());
+    }
+
+    #[test]
+    fn test_user_token_as_freshman_token() {
+        keyring::initialize_for_test();
+        let user_token = UserToken {
+            uid: 42,
+            jwt_string: JwtToken::new(42, 0).encode(),
+            expire_time: None,
+            refresh_token: None,
+        };
+
+        assert!(JwtToken::decode(&user_token.jwt_string).is_some());
+        assert_eq!(verify_token(&request(user_token, None)), None);
+    }
+}
@@ -19,13 +19,20 @@
 pub mod badge;
 pub mod balance;
 pub mod board;
+pub mod calendar;
+pub mod campus_card;
 pub mod captcha;
 pub mod classroom_browser;
+pub mod exam;
 pub mod exception;
 pub mod freshman;
 pub mod game;
+pub mod library;
 pub mod ping;
+pub mod score;
+pub mod second_class;
 pub mod template;
+pub mod timetable;
 pub mod token;
 pub mod typing;
 pub mod user;
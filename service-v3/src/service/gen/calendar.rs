@@ -0,0 +1,441 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// 学期
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Term {
+    /// 学年起始年份，如 2023 表示 2023-2024 学年
+    #[prost(int32, tag = "1")]
+    pub year: i32,
+    /// 学期，1 或 2
+    #[prost(int32, tag = "2")]
+    pub semester: i32,
+    /// 第一周周一，格式为 yyyy-MM-dd
+    #[prost(string, tag = "3")]
+    pub begin_date: ::prost::alloc::string::String,
+    /// 学期最后一天，格式为 yyyy-MM-dd
+    #[prost(string, tag = "4")]
+    pub end_date: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TermListResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub term_list: ::prost::alloc::vec::Vec<Term>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TermRequest {
+    #[prost(int32, tag = "1")]
+    pub year: i32,
+    #[prost(int32, tag = "2")]
+    pub semester: i32,
+}
+/// 放假或调休
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DayOverride {
+    /// yyyy-MM-dd
+    #[prost(string, tag = "1")]
+    pub date: ::prost::alloc::string::String,
+    /// 调休补课时，按这一天的课表上课；为空表示放假
+    #[prost(string, optional, tag = "2")]
+    pub follow_date: ::core::option::Option<::prost::alloc::string::String>,
+    /// 名称，如 "国庆节"
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DayOverrideList {
+    #[prost(message, repeated, tag = "1")]
+    pub override_list: ::prost::alloc::vec::Vec<DayOverride>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DateRequest {
+    /// yyyy-MM-dd，为空时表示今天
+    #[prost(string, optional, tag = "1")]
+    pub date: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// 某一天在学期中的位置
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TermDay {
+    #[prost(int32, tag = "1")]
+    pub year: i32,
+    #[prost(int32, tag = "2")]
+    pub semester: i32,
+    /// 按哪一周、星期几的课表上课
+    #[prost(int32, tag = "3")]
+    pub week: i32,
+    #[prost(int32, tag = "4")]
+    pub day: i32,
+    /// 是否上课，放假时为 false
+    #[prost(bool, tag = "5")]
+    pub has_class: bool,
+    /// 节假日或调休的名称
+    #[prost(string, tag = "6")]
+    pub note: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClassPeriodRequest {
+    /// 校区，奉贤校区 = 1，徐汇校区 = 2
+    #[prost(int32, tag = "1")]
+    pub campus: i32,
+}
+/// 一节课的上下课时间
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClassPeriod {
+    /// 第几节，1 - 11
+    #[prost(int32, tag = "1")]
+    pub index: i32,
+    /// HH:mm
+    #[prost(string, tag = "2")]
+    pub begin_time: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub end_time: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClassPeriodList {
+    #[prost(message, repeated, tag = "1")]
+    pub period_list: ::prost::alloc::vec::Vec<ClassPeriod>,
+}
+/// Generated server implementations.
+pub mod calendar_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+
+    use tonic::codegen::*;
+
+    /// Generated trait containing gRPC methods that should be implemented for use with CalendarServiceServer.
+    #[async_trait]
+    pub trait CalendarService: Send + Sync + 'static {
+        /// 获取所有学期
+        async fn get_term_list(
+            &self,
+            request: tonic::Request<super::super::template::EmptyRequest>,
+        ) -> Result<tonic::Response<super::TermListResponse>, tonic::Status>;
+        /// 获取某学期的放假和调休安排
+        async fn get_day_override(
+            &self,
+            request: tonic::Request<super::TermRequest>,
+        ) -> Result<tonic::Response<super::DayOverrideList>, tonic::Status>;
+        /// 计算某天是第几周星期几
+        async fn get_term_day(
+            &self,
+            request: tonic::Request<super::DateRequest>,
+        ) -> Result<tonic::Response<super::TermDay>, tonic::Status>;
+        /// 获取校区的作息时间
+        async fn get_class_period(
+            &self,
+            request: tonic::Request<super::ClassPeriodRequest>,
+        ) -> Result<tonic::Response<super::ClassPeriodList>, tonic::Status>;
+        /// 以下为管理员接口
+        /// 添加或修改学期
+        async fn set_term(
+            &self,
+            request: tonic::Request<super::Term>,
+        ) -> Result<tonic::Response<super::super::template::Empty>, tonic::Status>;
+        /// 添加或修改放假、调休安排
+        async fn set_day_override(
+            &self,
+            request: tonic::Request<super::DayOverride>,
+        ) -> Result<tonic::Response<super::super::template::Empty>, tonic::Status>;
+        /// 删除某天的放假、调休安排
+        async fn delete_day_override(
+            &self,
+            request: tonic::Request<super::DateRequest>,
+        ) -> Result<tonic::Response<super::super::template::Empty>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct CalendarServiceServer<T: CalendarService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: CalendarService> CalendarServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for CalendarServiceServer<T>
+    where
+        T: CalendarService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/calendar.CalendarService/GetTermList" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTermListSvc<T: CalendarService>(pub Arc<T>);
+                    impl<T: CalendarService> tonic::server::UnaryService<super::super::template::EmptyRequest> for GetTermListSvc<T> {
+                        type Response = super::TermListResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::super::template::EmptyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_term_list(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetTermListSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/calendar.CalendarService/GetDayOverride" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetDayOverrideSvc<T: CalendarService>(pub Arc<T>);
+                    impl<T: CalendarService> tonic::server::UnaryService<super::TermRequest> for GetDayOverrideSvc<T> {
+                        type Response = super::DayOverrideList;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::TermRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_day_override(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetDayOverrideSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/calendar.CalendarService/GetTermDay" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTermDaySvc<T: CalendarService>(pub Arc<T>);
+                    impl<T: CalendarService> tonic::server::UnaryService<super::DateRequest> for GetTermDaySvc<T> {
+                        type Response = super::TermDay;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::DateRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_term_day(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetTermDaySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/calendar.CalendarService/GetClassPeriod" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetClassPeriodSvc<T: CalendarService>(pub Arc<T>);
+                    impl<T: CalendarService> tonic::server::UnaryService<super::ClassPeriodRequest> for GetClassPeriodSvc<T> {
+                        type Response = super::ClassPeriodList;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::ClassPeriodRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_class_period(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetClassPeriodSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/calendar.CalendarService/SetTerm" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetTermSvc<T: CalendarService>(pub Arc<T>);
+                    impl<T: CalendarService> tonic::server::UnaryService<super::Term> for SetTermSvc<T> {
+                        type Response = super::super::template::Empty;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::Term>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).set_term(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SetTermSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/calendar.CalendarService/SetDayOverride" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetDayOverrideSvc<T: CalendarService>(pub Arc<T>);
+                    impl<T: CalendarService> tonic::server::UnaryService<super::DayOverride> for SetDayOverrideSvc<T> {
+                        type Response = super::super::template::Empty;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::DayOverride>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).set_day_override(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SetDayOverrideSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/calendar.CalendarService/DeleteDayOverride" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteDayOverrideSvc<T: CalendarService>(pub Arc<T>);
+                    impl<T: CalendarService> tonic::server::UnaryService<super::DateRequest> for DeleteDayOverrideSvc<T> {
+                        type Response = super::super::template::Empty;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::DateRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).delete_day_override(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteDayOverrideSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => Box::pin(async move {
+                    Ok(http::Response::builder()
+                        .status(200)
+                        .header("grpc-status", "12")
+                        .header("content-type", "application/grpc")
+                        .body(empty_body())
+                        .unwrap())
+                }),
+            }
+        }
+    }
+    impl<T: CalendarService> Clone for CalendarServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: CalendarService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: CalendarService> tonic::server::NamedService for CalendarServiceServer<T> {
+        const NAME: &'static str = "calendar.CalendarService";
+    }
+}
@@ -0,0 +1,369 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// 校园卡交易记录
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Transaction {
+    /// 交易时间
+    #[prost(message, optional, tag = "1")]
+    pub time: ::core::option::Option<::prost_types::Timestamp>,
+    /// 金额，消费为负，充值为正
+    #[prost(float, tag = "2")]
+    pub amount: f32,
+    /// 交易后余额
+    #[prost(float, tag = "3")]
+    pub balance: f32,
+    /// 商户名称，如 "一食堂"
+    #[prost(string, tag = "4")]
+    pub merchant: ::prost::alloc::string::String,
+    /// 交易类型，如 "持卡人消费"
+    #[prost(string, tag = "5")]
+    pub kind: ::prost::alloc::string::String,
+    /// 分类: canteen, shop, bathhouse, recharge, other
+    #[prost(string, tag = "6")]
+    pub category: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportRequest {
+    /// 导入最近多少天的记录，默认 30 天，最多 180 天
+    #[prost(int32, optional, tag = "2")]
+    pub days: ::core::option::Option<i32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportResponse {
+    /// 新导入的记录数
+    #[prost(int32, tag = "1")]
+    pub imported: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TransactionListRequest {
+    #[prost(message, optional, tag = "1")]
+    pub page: ::core::option::Option<super::template::PageOption>,
+    /// 只列出某一分类的记录
+    #[prost(string, optional, tag = "2")]
+    pub category: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TransactionListResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub transaction_list: ::prost::alloc::vec::Vec<Transaction>,
+}
+/// 统计的日期范围，格式为 yyyy-MM-dd，包含首尾
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DateRange {
+    #[prost(string, tag = "1")]
+    pub from: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub to: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PeriodStatistics {
+    /// 日期 yyyy-MM-dd 或月份 yyyy-MM
+    #[prost(string, tag = "1")]
+    pub period: ::prost::alloc::string::String,
+    /// 充值金额
+    #[prost(float, tag = "2")]
+    pub income: f32,
+    /// 消费金额，为正数
+    #[prost(float, tag = "3")]
+    pub expense: f32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StatisticsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub statistics_list: ::prost::alloc::vec::Vec<PeriodStatistics>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CategoryStatistics {
+    #[prost(string, tag = "1")]
+    pub category: ::prost::alloc::string::String,
+    /// 消费金额，为正数
+    #[prost(float, tag = "2")]
+    pub expense: f32,
+    /// 交易笔数
+    #[prost(int32, tag = "3")]
+    pub count: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CategoryStatisticsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub statistics_list: ::prost::alloc::vec::Vec<CategoryStatistics>,
+}
+/// Generated server implementations.
+pub mod campus_card_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+
+    use tonic::codegen::*;
+
+    /// Generated trait containing gRPC methods that should be implemented for use with CampusCardServiceServer.
+    #[async_trait]
+    pub trait CampusCardService: Send + Sync + 'static {
+        /// 从校园卡系统导入交易记录
+        async fn import_transaction(
+            &self,
+            request: tonic::Request<super::ImportRequest>,
+        ) -> Result<tonic::Response<super::ImportResponse>, tonic::Status>;
+        /// 分页获取交易记录，按时间排序，最新的记录在前需指定 PageSort.Desc
+        async fn get_transaction_list(
+            &self,
+            request: tonic::Request<super::TransactionListRequest>,
+        ) -> Result<tonic::Response<super::TransactionListResponse>, tonic::Status>;
+        /// 按天统计收支
+        async fn get_daily_statistics(
+            &self,
+            request: tonic::Request<super::DateRange>,
+        ) -> Result<tonic::Response<super::StatisticsResponse>, tonic::Status>;
+        /// 按月统计收支
+        async fn get_monthly_statistics(
+            &self,
+            request: tonic::Request<super::DateRange>,
+        ) -> Result<tonic::Response<super::StatisticsResponse>, tonic::Status>;
+        /// 按分类统计消费
+        async fn get_category_statistics(
+            &self,
+            request: tonic::Request<super::DateRange>,
+        ) -> Result<tonic::Response<super::CategoryStatisticsResponse>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct CampusCardServiceServer<T: CampusCardService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: CampusCardService> CampusCardServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for CampusCardServiceServer<T>
+    where
+        T: CampusCardService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/campus_card.CampusCardService/ImportTransaction" => {
+                    #[allow(non_camel_case_types)]
+                    struct ImportTransactionSvc<T: CampusCardService>(pub Arc<T>);
+                    impl<T: CampusCardService> tonic::server::UnaryService<super::ImportRequest> for ImportTransactionSvc<T> {
+                        type Response = super::ImportResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::ImportRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).import_transaction(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ImportTransactionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/campus_card.CampusCardService/GetTransactionList" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTransactionListSvc<T: CampusCardService>(pub Arc<T>);
+                    impl<T: CampusCardService> tonic::server::UnaryService<super::TransactionListRequest> for GetTransactionListSvc<T> {
+                        type Response = super::TransactionListResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::TransactionListRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_transaction_list(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetTransactionListSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/campus_card.CampusCardService/GetDailyStatistics" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetDailyStatisticsSvc<T: CampusCardService>(pub Arc<T>);
+                    impl<T: CampusCardService> tonic::server::UnaryService<super::DateRange> for GetDailyStatisticsSvc<T> {
+                        type Response = super::StatisticsResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::DateRange>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_daily_statistics(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetDailyStatisticsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/campus_card.CampusCardService/GetMonthlyStatistics" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetMonthlyStatisticsSvc<T: CampusCardService>(pub Arc<T>);
+                    impl<T: CampusCardService> tonic::server::UnaryService<super::DateRange> for GetMonthlyStatisticsSvc<T> {
+                        type Response = super::StatisticsResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::DateRange>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_monthly_statistics(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetMonthlyStatisticsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/campus_card.CampusCardService/GetCategoryStatistics" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetCategoryStatisticsSvc<T: CampusCardService>(pub Arc<T>);
+                    impl<T: CampusCardService> tonic::server::UnaryService<super::DateRange> for GetCategoryStatisticsSvc<T> {
+                        type Response = super::CategoryStatisticsResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::DateRange>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_category_statistics(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetCategoryStatisticsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => Box::pin(async move {
+                    Ok(http::Response::builder()
+                        .status(200)
+                        .header("grpc-status", "12")
+                        .header("content-type", "application/grpc")
+                        .body(empty_body())
+                        .unwrap())
+                }),
+            }
+        }
+    }
+    impl<T: CampusCardService> Clone for CampusCardServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: CampusCardService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: CampusCardService> tonic::server::NamedService for CampusCardServiceServer<T> {
+        const NAME: &'static str = "campus_card.CampusCardService";
+    }
+}
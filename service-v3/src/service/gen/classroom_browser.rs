@@ -40,6 +40,23 @@ pub struct ClassroomQuery {
     /// 值 110b 表示希望 1-2 节课，即请求 8:20-9:55 空闲的教室，如果当前值省略，默认不筛选时间。
     #[prost(int32, optional, tag = "6")]
     pub time_flag: ::core::option::Option<i32>,
+    /// 最少座位数
+    #[prost(int32, optional, tag = "7")]
+    pub min_capacity: ::core::option::Option<i32>,
+    /// 教室类型
+    #[prost(enumeration = "RoomType", optional, tag = "8")]
+    pub room_type: ::core::option::Option<i32>,
+    /// 为 true 时只查询有多媒体设备的教室
+    #[prost(bool, tag = "9")]
+    pub multimedia: bool,
+    /// 楼层
+    #[prost(int32, optional, tag = "10")]
+    pub floor: ::core::option::Option<i32>,
+    #[prost(enumeration = "ClassroomRanking", tag = "11")]
+    pub ranking: i32,
+    /// 按距离排序时参照的教学楼，如 "一教"
+    #[prost(string, optional, tag = "12")]
+    pub near_building: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// 教室信息
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -54,6 +71,17 @@ pub struct Classroom {
     /// 教室容量，部分教室暂缺
     #[prost(int32, optional, tag = "3")]
     pub capacity: ::core::option::Option<i32>,
+    #[prost(enumeration = "RoomType", tag = "4")]
+    pub room_type: i32,
+    /// 是否有多媒体设备
+    #[prost(bool, tag = "5")]
+    pub multimedia: bool,
+    /// 楼层，部分教室暂缺
+    #[prost(int32, optional, tag = "6")]
+    pub floor: ::core::option::Option<i32>,
+    /// 当天最长连续空闲的节数
+    #[prost(int32, tag = "7")]
+    pub free_window: i32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -61,6 +89,164 @@ pub struct ClassroomListResponse {
     #[prost(message, repeated, tag = "1")]
     pub classroom_list: ::prost::alloc::vec::Vec<Classroom>,
 }
+/// 按时间查询空教室，周序号、星期和节次由服务端根据校历和作息时间计算
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TimeClassroomQuery {
+    #[prost(string, optional, tag = "1")]
+    pub building: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "2")]
+    pub region: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(enumeration = "super::typing::Campus", optional, tag = "3")]
+    pub campus: ::core::option::Option<i32>,
+    /// 开始时间，为空时表示现在
+    #[prost(message, optional, tag = "4")]
+    pub time: ::core::option::Option<::prost_types::Timestamp>,
+    /// 希望从开始时间起空闲的分钟数，为 0 时只要求开始时刻空闲
+    #[prost(int32, tag = "5")]
+    pub duration_minutes: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FreeClassroom {
+    #[prost(string, tag = "1")]
+    pub title: ::prost::alloc::string::String,
+    #[prost(int32, optional, tag = "2")]
+    pub capacity: ::core::option::Option<i32>,
+    /// 当天的使用情况，同 time_flag
+    #[prost(int32, tag = "3")]
+    pub busy_flag: i32,
+    /// 空闲到何时，为空表示当天之后都空闲
+    #[prost(message, optional, tag = "4")]
+    pub free_until: ::core::option::Option<::prost_types::Timestamp>,
+    /// 从开始时间起连续空闲的分钟数，当天之后都空闲时计算到当天结束
+    #[prost(int32, tag = "5")]
+    pub free_minutes: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FreeClassroomListResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub classroom_list: ::prost::alloc::vec::Vec<FreeClassroom>,
+    /// 查询时间对应的周序号、星期和节次
+    #[prost(int32, tag = "2")]
+    pub week: i32,
+    #[prost(int32, tag = "3")]
+    pub day: i32,
+    #[prost(int32, tag = "4")]
+    pub time_flag: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScheduleQuery {
+    /// 教室名称，同 Classroom.title
+    #[prost(string, tag = "1")]
+    pub room: ::prost::alloc::string::String,
+    #[prost(enumeration = "super::typing::Campus", optional, tag = "2")]
+    pub campus: ::core::option::Option<i32>,
+    /// 周序号
+    #[prost(int32, tag = "3")]
+    pub week: i32,
+}
+/// 一节课的占用情况
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScheduleSlot {
+    #[prost(bool, tag = "1")]
+    pub busy: bool,
+    /// 占用教室的课程名称，未知时为空
+    #[prost(string, optional, tag = "2")]
+    pub course_name: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DaySchedule {
+    /// 星期几，1 - 7
+    #[prost(int32, tag = "1")]
+    pub day: i32,
+    /// 第 1 - 11 节
+    #[prost(message, repeated, tag = "2")]
+    pub slot_list: ::prost::alloc::vec::Vec<ScheduleSlot>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClassroomScheduleResponse {
+    #[prost(string, tag = "1")]
+    pub room: ::prost::alloc::string::String,
+    #[prost(int32, optional, tag = "2")]
+    pub capacity: ::core::option::Option<i32>,
+    #[prost(int32, tag = "3")]
+    pub week: i32,
+    /// 星期一至星期日
+    #[prost(message, repeated, tag = "4")]
+    pub day_list: ::prost::alloc::vec::Vec<DaySchedule>,
+}
+/// 教室类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum RoomType {
+    /// 普通教室
+    Ordinary = 0,
+    /// 阶梯教室、报告厅
+    LectureHall = 1,
+    /// 实验室、机房
+    Lab = 2,
+}
+impl RoomType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            RoomType::Ordinary => "Ordinary",
+            RoomType::LectureHall => "LectureHall",
+            RoomType::Lab => "Lab",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "Ordinary" => Some(Self::Ordinary),
+            "LectureHall" => Some(Self::LectureHall),
+            "Lab" => Some(Self::Lab),
+            _ => None,
+        }
+    }
+}
+/// 空教室列表的排序方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ClassroomRanking {
+    /// 按教室名称
+    Title = 0,
+    /// 按当天最长连续空闲的节数，从长到短
+    FreeWindow = 1,
+    /// 按与 near_building 的距离，从近到远
+    Distance = 2,
+}
+impl ClassroomRanking {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ClassroomRanking::Title => "Title",
+            ClassroomRanking::FreeWindow => "FreeWindow",
+            ClassroomRanking::Distance => "Distance",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "Title" => Some(Self::Title),
+            "FreeWindow" => Some(Self::FreeWindow),
+            "Distance" => Some(Self::Distance),
+            _ => None,
+        }
+    }
+}
 /// Generated server implementations.
 pub mod classroom_browser_service_server {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
@@ -75,6 +261,16 @@ pub mod classroom_browser_service_server {
             &self,
             request: tonic::Request<super::ClassroomQuery>,
         ) -> Result<tonic::Response<super::ClassroomListResponse>, tonic::Status>;
+        /// 根据给定位置和时间段（或现在）获取空教室列表，并给出每间教室空闲到何时
+        async fn get_available_classroom_by_time(
+            &self,
+            request: tonic::Request<super::TimeClassroomQuery>,
+        ) -> Result<tonic::Response<super::FreeClassroomListResponse>, tonic::Status>;
+        /// 获取一间教室某一周的占用情况
+        async fn get_classroom_schedule(
+            &self,
+            request: tonic::Request<super::ScheduleQuery>,
+        ) -> Result<tonic::Response<super::ClassroomScheduleResponse>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct ClassroomBrowserServiceServer<T: ClassroomBrowserService> {
@@ -156,6 +352,60 @@ pub mod classroom_browser_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/classroom_browser.ClassroomBrowserService/GetAvailableClassroomByTime" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAvailableClassroomByTimeSvc<T: ClassroomBrowserService>(pub Arc<T>);
+                    impl<T: ClassroomBrowserService> tonic::server::UnaryService<super::TimeClassroomQuery>
+                        for GetAvailableClassroomByTimeSvc<T>
+                    {
+                        type Response = super::FreeClassroomListResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::TimeClassroomQuery>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_available_classroom_by_time(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetAvailableClassroomByTimeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/classroom_browser.ClassroomBrowserService/GetClassroomSchedule" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetClassroomScheduleSvc<T: ClassroomBrowserService>(pub Arc<T>);
+                    impl<T: ClassroomBrowserService> tonic::server::UnaryService<super::ScheduleQuery> for GetClassroomScheduleSvc<T> {
+                        type Response = super::ClassroomScheduleResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::ScheduleQuery>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_classroom_schedule(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetClassroomScheduleSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => Box::pin(async move {
                     Ok(http::Response::builder()
                         .status(200)
@@ -0,0 +1,348 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// 考试安排
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Exam {
+    /// 课程名称
+    #[prost(string, tag = "1")]
+    pub course_name: ::prost::alloc::string::String,
+    /// 课程代码
+    #[prost(string, tag = "2")]
+    pub course_code: ::prost::alloc::string::String,
+    /// 考试名称，如 "2022-2023-2期末考试"
+    #[prost(string, tag = "3")]
+    pub exam_name: ::prost::alloc::string::String,
+    /// 开始时间，未安排时为空
+    #[prost(message, optional, tag = "4")]
+    pub start_time: ::core::option::Option<::prost_types::Timestamp>,
+    /// 结束时间，未安排时为空
+    #[prost(message, optional, tag = "5")]
+    pub end_time: ::core::option::Option<::prost_types::Timestamp>,
+    /// 考场，如 "一教A101"
+    #[prost(string, tag = "6")]
+    pub place: ::prost::alloc::string::String,
+    /// 校区
+    #[prost(string, tag = "7")]
+    pub campus: ::prost::alloc::string::String,
+    /// 考场所在教学楼，如 "一教"，与 classroom_browser 中一致
+    #[prost(string, optional, tag = "8")]
+    pub building: ::core::option::Option<::prost::alloc::string::String>,
+    /// 考场所在区域，如 "A"
+    #[prost(string, optional, tag = "9")]
+    pub region: ::core::option::Option<::prost::alloc::string::String>,
+    /// 座位号
+    #[prost(string, tag = "10")]
+    pub seat: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExamRequest {
+    /// 学年起始年份，如 2022 表示 2022-2023 学年
+    #[prost(int32, tag = "1")]
+    pub year: i32,
+    /// 学期，1 或 2
+    #[prost(int32, tag = "2")]
+    pub semester: i32,
+    /// 是否从教务系统更新，否则返回缓存的结果。使用登录时保存的会话
+    #[prost(bool, tag = "4")]
+    pub refresh: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExamListResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub exam_list: ::prost::alloc::vec::Vec<Exam>,
+    /// 从教务系统获取的时间
+    #[prost(message, optional, tag = "2")]
+    pub update_time: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportRequest {
+    #[prost(int32, tag = "1")]
+    pub year: i32,
+    #[prost(int32, tag = "2")]
+    pub semester: i32,
+    /// 考前提醒的分钟数，不提供则不提醒
+    #[prost(int32, optional, tag = "3")]
+    pub alarm_minutes: ::core::option::Option<i32>,
+}
+/// iCalendar 文件
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ICalendarResponse {
+    #[prost(string, tag = "1")]
+    pub content: ::prost::alloc::string::String,
+}
+/// 考试提醒
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExamReminder {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+    #[prost(string, tag = "2")]
+    pub course_name: ::prost::alloc::string::String,
+    /// 提醒内容，包含时间和考场
+    #[prost(string, tag = "3")]
+    pub content: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "4")]
+    pub ts: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExamReminderList {
+    #[prost(message, repeated, tag = "1")]
+    pub reminder_list: ::prost::alloc::vec::Vec<ExamReminder>,
+}
+/// Generated server implementations.
+pub mod exam_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+
+    use tonic::codegen::*;
+
+    /// Generated trait containing gRPC methods that should be implemented for use with ExamServiceServer.
+    #[async_trait]
+    pub trait ExamService: Send + Sync + 'static {
+        /// 获取某学期的考试安排
+        async fn get_exam_list(
+            &self,
+            request: tonic::Request<super::ExamRequest>,
+        ) -> Result<tonic::Response<super::ExamListResponse>, tonic::Status>;
+        /// 将缓存的考试安排导出为 iCalendar 文件
+        async fn export_exam(
+            &self,
+            request: tonic::Request<super::ExportRequest>,
+        ) -> Result<tonic::Response<super::ICalendarResponse>, tonic::Status>;
+        /// 获取最近的考试提醒
+        async fn get_exam_reminder(
+            &self,
+            request: tonic::Request<super::super::template::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ExamReminderList>, tonic::Status>;
+        /// Server streaming response type for the WatchExamReminder method.
+        type WatchExamReminderStream: futures_core::Stream<Item = Result<super::ExamReminder, tonic::Status>>
+            + Send
+            + 'static;
+        /// 订阅考试提醒，连接期间产生的提醒会被推送
+        async fn watch_exam_reminder(
+            &self,
+            request: tonic::Request<super::super::template::EmptyRequest>,
+        ) -> Result<tonic::Response<Self::WatchExamReminderStream>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct ExamServiceServer<T: ExamService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: ExamService> ExamServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for ExamServiceServer<T>
+    where
+        T: ExamService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/exam.ExamService/GetExamList" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetExamListSvc<T: ExamService>(pub Arc<T>);
+                    impl<T: ExamService> tonic::server::UnaryService<super::ExamRequest> for GetExamListSvc<T> {
+                        type Response = super::ExamListResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::ExamRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_exam_list(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetExamListSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/exam.ExamService/ExportExam" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExportExamSvc<T: ExamService>(pub Arc<T>);
+                    impl<T: ExamService> tonic::server::UnaryService<super::ExportRequest> for ExportExamSvc<T> {
+                        type Response = super::ICalendarResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::ExportRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).export_exam(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ExportExamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/exam.ExamService/GetExamReminder" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetExamReminderSvc<T: ExamService>(pub Arc<T>);
+                    impl<T: ExamService> tonic::server::UnaryService<super::super::template::EmptyRequest> for GetExamReminderSvc<T> {
+                        type Response = super::ExamReminderList;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::super::template::EmptyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_exam_reminder(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetExamReminderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/exam.ExamService/WatchExamReminder" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchExamReminderSvc<T: ExamService>(pub Arc<T>);
+                    impl<T: ExamService> tonic::server::ServerStreamingService<super::super::template::EmptyRequest>
+                        for WatchExamReminderSvc<T>
+                    {
+                        type Response = super::ExamReminder;
+                        type ResponseStream = T::WatchExamReminderStream;
+                        type Future = BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::super::template::EmptyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).watch_exam_reminder(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WatchExamReminderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => Box::pin(async move {
+                    Ok(http::Response::builder()
+                        .status(200)
+                        .header("grpc-status", "12")
+                        .header("content-type", "application/grpc")
+                        .body(empty_body())
+                        .unwrap())
+                }),
+            }
+        }
+    }
+    impl<T: ExamService> Clone for ExamServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: ExamService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: ExamService> tonic::server::NamedService for ExamServiceServer<T> {
+        const NAME: &'static str = "exam.ExamService";
+    }
+}
@@ -0,0 +1,376 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// 在借图书
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Loan {
+    /// 条码号，续借时使用
+    #[prost(string, tag = "1")]
+    pub barcode: ::prost::alloc::string::String,
+    /// 题名
+    #[prost(string, tag = "2")]
+    pub title: ::prost::alloc::string::String,
+    /// 责任者
+    #[prost(string, tag = "3")]
+    pub author: ::prost::alloc::string::String,
+    /// 索书号
+    #[prost(string, tag = "4")]
+    pub call_number: ::prost::alloc::string::String,
+    /// 馆藏地点
+    #[prost(string, tag = "5")]
+    pub location: ::prost::alloc::string::String,
+    /// 借阅日期 yyyy-MM-dd
+    #[prost(string, tag = "6")]
+    pub loan_date: ::prost::alloc::string::String,
+    /// 应还日期 yyyy-MM-dd
+    #[prost(string, tag = "7")]
+    pub due_date: ::prost::alloc::string::String,
+    /// 已续借次数
+    #[prost(int32, tag = "8")]
+    pub renew_count: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoanRequest {
+    /// 是否从图书馆系统更新，否则返回最近一次获取的结果。使用登录时保存的会话
+    #[prost(bool, tag = "2")]
+    pub refresh: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoanListResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub loan_list: ::prost::alloc::vec::Vec<Loan>,
+    /// 从图书馆系统获取的时间
+    #[prost(message, optional, tag = "2")]
+    pub update_time: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RenewRequest {
+    /// 要续借的图书条码号
+    #[prost(string, repeated, tag = "2")]
+    pub barcode_list: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RenewResult {
+    #[prost(string, tag = "1")]
+    pub barcode: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub success: bool,
+    /// 图书馆系统返回的提示，如 "超过最大续借次数"
+    #[prost(string, tag = "3")]
+    pub message: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RenewResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub result_list: ::prost::alloc::vec::Vec<RenewResult>,
+    /// 续借后的在借图书
+    #[prost(message, repeated, tag = "2")]
+    pub loan_list: ::prost::alloc::vec::Vec<Loan>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchRequest {
+    #[prost(string, tag = "1")]
+    pub keyword: ::prost::alloc::string::String,
+    /// 页码，从 1 开始
+    #[prost(int32, tag = "2")]
+    pub page: i32,
+}
+/// 馆藏书目
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Book {
+    /// 图书馆系统中的书目记录号
+    #[prost(string, tag = "1")]
+    pub book_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub title: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub author: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub publisher: ::prost::alloc::string::String,
+    /// 出版日期，如 "2020"
+    #[prost(string, tag = "5")]
+    pub publish_date: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub call_number: ::prost::alloc::string::String,
+    /// 馆藏复本数
+    #[prost(int32, tag = "7")]
+    pub copy_count: i32,
+    /// 可借复本数
+    #[prost(int32, tag = "8")]
+    pub available_count: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub book_list: ::prost::alloc::vec::Vec<Book>,
+    /// 检索结果总数
+    #[prost(int32, tag = "2")]
+    pub total: i32,
+}
+/// 图书到期提醒
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DueReminder {
+    /// 题名
+    #[prost(string, tag = "1")]
+    pub title: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub content: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub ts: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DueReminderList {
+    #[prost(message, repeated, tag = "1")]
+    pub reminder_list: ::prost::alloc::vec::Vec<DueReminder>,
+}
+/// Generated server implementations.
+pub mod library_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+
+    use tonic::codegen::*;
+
+    /// Generated trait containing gRPC methods that should be implemented for use with LibraryServiceServer.
+    #[async_trait]
+    pub trait LibraryService: Send + Sync + 'static {
+        /// 获取在借图书及应还日期
+        async fn get_loan_list(
+            &self,
+            request: tonic::Request<super::LoanRequest>,
+        ) -> Result<tonic::Response<super::LoanListResponse>, tonic::Status>;
+        /// 续借图书
+        async fn renew(
+            &self,
+            request: tonic::Request<super::RenewRequest>,
+        ) -> Result<tonic::Response<super::RenewResponse>, tonic::Status>;
+        /// 检索馆藏书目，无需登录
+        async fn search(
+            &self,
+            request: tonic::Request<super::SearchRequest>,
+        ) -> Result<tonic::Response<super::SearchResponse>, tonic::Status>;
+        /// 获取最近的图书到期提醒
+        async fn get_due_reminder(
+            &self,
+            request: tonic::Request<super::super::template::EmptyRequest>,
+        ) -> Result<tonic::Response<super::DueReminderList>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct LibraryServiceServer<T: LibraryService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: LibraryService> LibraryServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for LibraryServiceServer<T>
+    where
+        T: LibraryService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/library.LibraryService/GetLoanList" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetLoanListSvc<T: LibraryService>(pub Arc<T>);
+                    impl<T: LibraryService> tonic::server::UnaryService<super::LoanRequest> for GetLoanListSvc<T> {
+                        type Response = super::LoanListResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::LoanRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_loan_list(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetLoanListSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/library.LibraryService/Renew" => {
+                    #[allow(non_camel_case_types)]
+                    struct RenewSvc<T: LibraryService>(pub Arc<T>);
+                    impl<T: LibraryService> tonic::server::UnaryService<super::RenewRequest> for RenewSvc<T> {
+                        type Response = super::RenewResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::RenewRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).renew(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RenewSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/library.LibraryService/Search" => {
+                    #[allow(non_camel_case_types)]
+                    struct SearchSvc<T: LibraryService>(pub Arc<T>);
+                    impl<T: LibraryService> tonic::server::UnaryService<super::SearchRequest> for SearchSvc<T> {
+                        type Response = super::SearchResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::SearchRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).search(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SearchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/library.LibraryService/GetDueReminder" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetDueReminderSvc<T: LibraryService>(pub Arc<T>);
+                    impl<T: LibraryService> tonic::server::UnaryService<super::super::template::EmptyRequest> for GetDueReminderSvc<T> {
+                        type Response = super::DueReminderList;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::super::template::EmptyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_due_reminder(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetDueReminderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => Box::pin(async move {
+                    Ok(http::Response::builder()
+                        .status(200)
+                        .header("grpc-status", "12")
+                        .header("content-type", "application/grpc")
+                        .body(empty_body())
+                        .unwrap())
+                }),
+            }
+        }
+    }
+    impl<T: LibraryService> Clone for LibraryServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: LibraryService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: LibraryService> tonic::server::NamedService for LibraryServiceServer<T> {
+        const NAME: &'static str = "library.LibraryService";
+    }
+}
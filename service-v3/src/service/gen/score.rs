@@ -0,0 +1,253 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// 课程成绩
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Score {
+    /// 课程名称
+    #[prost(string, tag = "1")]
+    pub course_name: ::prost::alloc::string::String,
+    /// 课程代码
+    #[prost(string, tag = "2")]
+    pub course_code: ::prost::alloc::string::String,
+    /// 教学班名称
+    #[prost(string, tag = "3")]
+    pub class_name: ::prost::alloc::string::String,
+    /// 学分
+    #[prost(float, tag = "4")]
+    pub credit: f32,
+    /// 成绩，可能是分数，也可能是 "优秀" 等等级
+    #[prost(string, tag = "5")]
+    pub score: ::prost::alloc::string::String,
+    /// 绩点，"合格" 等没有绩点的成绩为空
+    #[prost(float, optional, tag = "6")]
+    pub grade_point: ::core::option::Option<f32>,
+    /// 课程性质，如 "必修"
+    #[prost(string, tag = "7")]
+    pub course_type: ::prost::alloc::string::String,
+    /// 考试性质，如 "正常考试"、"补考"
+    #[prost(string, tag = "8")]
+    pub exam_type: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScoreRequest {
+    /// 学年起始年份，如 2022 表示 2022-2023 学年
+    #[prost(int32, tag = "1")]
+    pub year: i32,
+    /// 学期，1 或 2
+    #[prost(int32, tag = "2")]
+    pub semester: i32,
+    /// 是否从教务系统更新，否则返回最近一次获取的结果。使用登录时保存的会话
+    #[prost(bool, tag = "4")]
+    pub refresh: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScoreListResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub score_list: ::prost::alloc::vec::Vec<Score>,
+    /// 学期平均绩点，没有计入绩点的课程时为空
+    #[prost(float, optional, tag = "2")]
+    pub gpa: ::core::option::Option<f32>,
+    /// 从教务系统获取的时间
+    #[prost(message, optional, tag = "3")]
+    pub update_time: ::core::option::Option<::prost_types::Timestamp>,
+}
+/// 新出的成绩
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScoreUpdate {
+    #[prost(string, tag = "1")]
+    pub course_name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub score: ::prost::alloc::string::String,
+    /// 发现成绩的时间
+    #[prost(message, optional, tag = "3")]
+    pub ts: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScoreUpdateList {
+    #[prost(message, repeated, tag = "1")]
+    pub update_list: ::prost::alloc::vec::Vec<ScoreUpdate>,
+}
+/// Generated server implementations.
+pub mod score_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+
+    use tonic::codegen::*;
+
+    /// Generated trait containing gRPC methods that should be implemented for use with ScoreServiceServer.
+    #[async_trait]
+    pub trait ScoreService: Send + Sync + 'static {
+        /// 获取某学期的成绩和绩点
+        async fn get_score_list(
+            &self,
+            request: tonic::Request<super::ScoreRequest>,
+        ) -> Result<tonic::Response<super::ScoreListResponse>, tonic::Status>;
+        /// 获取最近新出的成绩
+        async fn get_score_update(
+            &self,
+            request: tonic::Request<super::super::template::EmptyRequest>,
+        ) -> Result<tonic::Response<super::ScoreUpdateList>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct ScoreServiceServer<T: ScoreService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: ScoreService> ScoreServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for ScoreServiceServer<T>
+    where
+        T: ScoreService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/score.ScoreService/GetScoreList" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetScoreListSvc<T: ScoreService>(pub Arc<T>);
+                    impl<T: ScoreService> tonic::server::UnaryService<super::ScoreRequest> for GetScoreListSvc<T> {
+                        type Response = super::ScoreListResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::ScoreRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_score_list(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetScoreListSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/score.ScoreService/GetScoreUpdate" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetScoreUpdateSvc<T: ScoreService>(pub Arc<T>);
+                    impl<T: ScoreService> tonic::server::UnaryService<super::super::template::EmptyRequest> for GetScoreUpdateSvc<T> {
+                        type Response = super::ScoreUpdateList;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::super::template::EmptyRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_score_update(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetScoreUpdateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => Box::pin(async move {
+                    Ok(http::Response::builder()
+                        .status(200)
+                        .header("grpc-status", "12")
+                        .header("content-type", "application/grpc")
+                        .body(empty_body())
+                        .unwrap())
+                }),
+            }
+        }
+    }
+    impl<T: ScoreService> Clone for ScoreServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: ScoreService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: ScoreService> tonic::server::NamedService for ScoreServiceServer<T> {
+        const NAME: &'static str = "score.ScoreService";
+    }
+}
@@ -0,0 +1,257 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// 课程
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Course {
+    /// 课程名称
+    #[prost(string, tag = "1")]
+    pub course_name: ::prost::alloc::string::String,
+    /// 课程代码
+    #[prost(string, tag = "2")]
+    pub course_code: ::prost::alloc::string::String,
+    /// 教学班名称
+    #[prost(string, tag = "3")]
+    pub class_name: ::prost::alloc::string::String,
+    /// 任课教师
+    #[prost(string, repeated, tag = "4")]
+    pub teachers: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// 上课地点，如 "一教A101"
+    #[prost(string, tag = "5")]
+    pub place: ::prost::alloc::string::String,
+    /// 校区
+    #[prost(string, tag = "6")]
+    pub campus: ::prost::alloc::string::String,
+    /// 上课周次。第 n 位为 1 表示第 n 周有课
+    #[prost(int32, tag = "7")]
+    pub weeks: i32,
+    /// 星期，1 - 7
+    #[prost(int32, tag = "8")]
+    pub day: i32,
+    /// 节次，第 n 位为 1 表示第 n 节有课，n 取 1 - 11
+    #[prost(int32, tag = "9")]
+    pub time_index: i32,
+    /// 学分
+    #[prost(float, tag = "10")]
+    pub credit: f32,
+}
+/// 学期
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TermRequest {
+    /// 学年起始年份，如 2022 表示 2022-2023 学年
+    #[prost(int32, tag = "1")]
+    pub year: i32,
+    /// 学期，1 或 2
+    #[prost(int32, tag = "2")]
+    pub semester: i32,
+    /// 是否从教务系统更新，否则返回缓存的结果。使用登录时保存的会话
+    #[prost(bool, tag = "4")]
+    pub refresh: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TimetableResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub course_list: ::prost::alloc::vec::Vec<Course>,
+    /// 从教务系统获取的时间
+    #[prost(message, optional, tag = "2")]
+    pub update_time: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportRequest {
+    #[prost(int32, tag = "1")]
+    pub year: i32,
+    #[prost(int32, tag = "2")]
+    pub semester: i32,
+    /// 学期第一周周一，格式 yyyy-MM-dd。为空时使用校历中的学期，并按放假和调休安排调整
+    #[prost(string, tag = "3")]
+    pub term_begin: ::prost::alloc::string::String,
+    /// 课前提醒的分钟数，不提供则不提醒
+    #[prost(int32, optional, tag = "4")]
+    pub alarm_minutes: ::core::option::Option<i32>,
+}
+/// iCalendar 文件
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ICalendarResponse {
+    #[prost(string, tag = "1")]
+    pub content: ::prost::alloc::string::String,
+}
+/// Generated server implementations.
+pub mod timetable_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+
+    use tonic::codegen::*;
+
+    /// Generated trait containing gRPC methods that should be implemented for use with TimetableServiceServer.
+    #[async_trait]
+    pub trait TimetableService: Send + Sync + 'static {
+        /// 获取课表
+        async fn get_timetable(
+            &self,
+            request: tonic::Request<super::TermRequest>,
+        ) -> Result<tonic::Response<super::TimetableResponse>, tonic::Status>;
+        /// 将缓存的课表导出为 iCalendar 文件
+        async fn export_timetable(
+            &self,
+            request: tonic::Request<super::ExportRequest>,
+        ) -> Result<tonic::Response<super::ICalendarResponse>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct TimetableServiceServer<T: TimetableService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: TimetableService> TimetableServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+            }
+        }
+        pub fn with_interceptor<F>(inner: T, interceptor: F) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for TimetableServiceServer<T>
+    where
+        T: TimetableService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/timetable.TimetableService/GetTimetable" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTimetableSvc<T: TimetableService>(pub Arc<T>);
+                    impl<T: TimetableService> tonic::server::UnaryService<super::TermRequest> for GetTimetableSvc<T> {
+                        type Response = super::TimetableResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::TermRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_timetable(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetTimetableSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/timetable.TimetableService/ExportTimetable" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExportTimetableSvc<T: TimetableService>(pub Arc<T>);
+                    impl<T: TimetableService> tonic::server::UnaryService<super::ExportRequest> for ExportTimetableSvc<T> {
+                        type Response = super::ICalendarResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::ExportRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).export_timetable(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ExportTimetableSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => Box::pin(async move {
+                    Ok(http::Response::builder()
+                        .status(200)
+                        .header("grpc-status", "12")
+                        .header("content-type", "application/grpc")
+                        .body(empty_body())
+                        .unwrap())
+                }),
+            }
+        }
+    }
+    impl<T: TimetableService> Clone for TimetableServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+            }
+        }
+    }
+    impl<T: TimetableService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: TimetableService> tonic::server::NamedService for TimetableServiceServer<T> {
+        const NAME: &'static str = "timetable.TimetableService";
+    }
+}
@@ -6,4 +6,10 @@ pub struct UserToken {
     pub uid: i32,
     #[prost(string, tag = "2")]
     pub jwt_string: ::prost::alloc::string::String,
+    /// 访问令牌过期时间，过期后使用 refresh_token 换取新的令牌
+    #[prost(message, optional, tag = "3")]
+    pub expire_time: ::core::option::Option<::prost_types::Timestamp>,
+    /// 刷新令牌，仅在登录或刷新时下发
+    #[prost(string, optional, tag = "4")]
+    pub refresh_token: ::core::option::Option<::prost::alloc::string::String>,
 }
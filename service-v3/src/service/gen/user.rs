@@ -41,12 +41,15 @@ pub struct OaCredential {
     /// OA 密码
     #[prost(string, tag = "2")]
     pub password: ::prost::alloc::string::String,
+    /// 是否需要刷新令牌，用于长期保持登录
+    #[prost(bool, tag = "3")]
+    pub need_refresh_token: bool,
 }
 /// 登录过程， client -> kite-server 流数据
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ClientStream {
-    #[prost(oneof = "client_stream::Payload", tags = "1, 2")]
+    #[prost(oneof = "client_stream::Payload", tags = "1, 2, 3")]
     pub payload: ::core::option::Option<client_stream::Payload>,
 }
 /// Nested message and enum types in `ClientStream`.
@@ -60,13 +63,16 @@ pub mod client_stream {
         /// 来自 authserver 的 TLS 流数据，经由 client 转发到 kite-server
         #[prost(bytes, tag = "2")]
         TlsStream(::prost::alloc::vec::Vec<u8>),
+        /// 用户输入的验证码，仅在收到 captcha_image 后发送
+        #[prost(string, tag = "3")]
+        CaptchaAnswer(::prost::alloc::string::String),
     }
 }
 /// 登录过程， kite-server -> app
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ServerStream {
-    #[prost(oneof = "server_stream::Payload", tags = "1, 2, 3")]
+    #[prost(oneof = "server_stream::Payload", tags = "1, 2, 3, 4, 5")]
     pub payload: ::core::option::Option<server_stream::Payload>,
 }
 /// Nested message and enum types in `ServerStream`.
@@ -83,8 +89,30 @@ pub mod server_stream {
         /// 用户登录失败的错误提示
         #[prost(string, tag = "3")]
         Message(::prost::alloc::string::String),
+        /// 登录成功后下发的访问令牌，紧随 user 之后发送
+        #[prost(message, tag = "4")]
+        Token(super::super::token::UserToken),
+        /// 自动识别失败时，需要用户识别的验证码图片
+        #[prost(bytes, tag = "5")]
+        CaptchaImage(::prost::alloc::vec::Vec<u8>),
     }
 }
+/// 刷新令牌请求
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RefreshTokenRequest {
+    /// 登录时下发的刷新令牌
+    #[prost(string, tag = "1")]
+    pub refresh_token: ::prost::alloc::string::String,
+}
+/// 退出登录请求
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LogoutRequest {
+    /// 需要一并作废的刷新令牌
+    #[prost(string, optional, tag = "1")]
+    pub refresh_token: ::core::option::Option<::prost::alloc::string::String>,
+}
 /// Generated server implementations.
 pub mod user_service_server {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
@@ -105,6 +133,16 @@ pub mod user_service_server {
             &self,
             request: tonic::Request<tonic::Streaming<super::ClientStream>>,
         ) -> Result<tonic::Response<Self::LoginStream>, tonic::Status>;
+        /// 使用刷新令牌换取新的访问令牌，原刷新令牌随之作废
+        async fn refresh_token(
+            &self,
+            request: tonic::Request<super::RefreshTokenRequest>,
+        ) -> Result<tonic::Response<super::super::token::UserToken>, tonic::Status>;
+        /// 退出登录，作废当前访问令牌和刷新令牌
+        async fn logout(
+            &self,
+            request: tonic::Request<super::LogoutRequest>,
+        ) -> Result<tonic::Response<super::super::template::Empty>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct UserServiceServer<T: UserService> {
@@ -189,6 +227,58 @@ pub mod user_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/user.UserService/RefreshToken" => {
+                    #[allow(non_camel_case_types)]
+                    struct RefreshTokenSvc<T: UserService>(pub Arc<T>);
+                    impl<T: UserService> tonic::server::UnaryService<super::RefreshTokenRequest> for RefreshTokenSvc<T> {
+                        type Response = super::super::token::UserToken;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::RefreshTokenRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).refresh_token(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RefreshTokenSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/user.UserService/Logout" => {
+                    #[allow(non_camel_case_types)]
+                    struct LogoutSvc<T: UserService>(pub Arc<T>);
+                    impl<T: UserService> tonic::server::UnaryService<super::LogoutRequest> for LogoutSvc<T> {
+                        type Response = super::super::template::Empty;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::LogoutRequest>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).logout(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = LogoutSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(accept_compression_encodings, send_compression_encodings);
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => Box::pin(async move {
                     Ok(http::Response::builder()
                         .status(200)
@@ -0,0 +1,115 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use kite::health::HealthReport;
+use sqlx::PgPool;
+use tonic::transport::NamedService;
+use tonic_health::server::HealthReporter;
+
+/// Interval between two dependency checks.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Dependencies which a service relies on.
+#[derive(Debug, Clone, Copy)]
+pub struct Requires {
+    pub database: bool,
+    pub balance: bool,
+    pub captcha: bool,
+}
+
+impl Requires {
+    pub const NOTHING: Requires = Requires {
+        database: false,
+        balance: false,
+        captcha: false,
+    };
+    pub const DATABASE: Requires = Requires {
+        database: true,
+        ..Self::NOTHING
+    };
+
+    fn is_met(&self, report: &HealthReport) -> bool {
+        (!self.database || report.database) && (!self.balance || report.balance) && (!self.captcha || report.captcha)
+    }
+}
+
+/// Services added to the server, with what each of them relies on.
+#[derive(Default)]
+pub struct Registry(Vec<(&'static str, Requires)>);
+
+impl Registry {
+    /// Record the service, and return it to be added to the server.
+    pub fn register<S: NamedService>(&mut self, service: S, requires: Requires) -> S {
+        self.0.push((S::NAME, requires));
+        service
+    }
+}
+
+/// Check dependencies periodically and publish the result to `grpc.health.v1.Health`.
+///
+/// The empty service name stands for the whole server, which is serving only if all
+/// dependencies are available. Each registered service is marked by what it actually relies on.
+pub async fn health_check_task(mut reporter: HealthReporter, db: PgPool, registry: Registry) {
+    use tonic_health::ServingStatus;
+
+    fn to_status(ok: bool) -> ServingStatus {
+        if ok {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        }
+    }
+
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let report = kite::health::check(&db, ::captcha::is_ready()).await;
+        tracing::debug!("Health check: {:?}", report);
+
+        reporter.set_service_status("", to_status(report.is_ready())).await;
+        for (name, requires) in &registry.0 {
+            reporter
+                .set_service_status(name, to_status(requires.is_met(&report)))
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_requires() {
+        let report = HealthReport {
+            database: true,
+            captcha: false,
+            balance: true,
+        };
+        assert!(Requires::NOTHING.is_met(&report));
+        assert!(Requires::DATABASE.is_met(&report));
+        let login = Requires {
+            captcha: true,
+            ..Requires::DATABASE
+        };
+        assert!(!login.is_met(&report));
+    }
+}
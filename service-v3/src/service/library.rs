@@ -0,0 +1,211 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+
+use crate::authserver::Session;
+use crate::campus::{self, library, CampusSession};
+use crate::error::ToStatus;
+use crate::limit::RateLimiter;
+use crate::model::ToTimestamp;
+use crate::model::{library as model, notification};
+use crate::service::auth::get_token_from_request;
+pub use crate::service::gen::library as gen;
+use crate::service::gen::template::EmptyRequest;
+
+/// Count of recent reminders returned.
+const REMINDER_LIMIT: i64 = 20;
+/// Books renewed in a request at most.
+const MAX_RENEW_COUNT: usize = 20;
+
+/// Searches of a client in a minute.
+static CLIENT_SEARCH_LIMITER: Lazy<RateLimiter<IpAddr>> = Lazy::new(|| RateLimiter::new(20, Duration::from_secs(60)));
+/// Searches sent to the library in a minute, from all clients.
+static LIBRARY_SEARCH_LIMITER: Lazy<RateLimiter<()>> = Lazy::new(|| RateLimiter::new(120, Duration::from_secs(60)));
+
+impl From<model::Loan> for gen::Loan {
+    fn from(loan: model::Loan) -> Self {
+        gen::Loan {
+            barcode: loan.barcode,
+            title: loan.title,
+            author: loan.author,
+            call_number: loan.call_number,
+            location: loan.location,
+            loan_date: loan.loan_date.format("%Y-%m-%d").to_string(),
+            due_date: loan.due_date.format("%Y-%m-%d").to_string(),
+            renew_count: loan.renew_count,
+        }
+    }
+}
+
+impl From<model::Book> for gen::Book {
+    fn from(book: model::Book) -> Self {
+        gen::Book {
+            book_id: book.book_id,
+            title: book.title,
+            author: book.author,
+            publisher: book.publisher,
+            publish_date: book.publish_date,
+            call_number: book.call_number,
+            copy_count: book.copy_count,
+            available_count: book.available_count,
+        }
+    }
+}
+
+impl From<notification::Notification> for gen::DueReminder {
+    fn from(n: notification::Notification) -> Self {
+        gen::DueReminder {
+            title: n.title,
+            content: n.content,
+            ts: Some(ToTimestamp::datetime(n.create_time)),
+        }
+    }
+}
+
+/// Fetch and save, returning the update time too.
+async fn fetch_loan(db: &PgPool, uid: i32) -> anyhow::Result<(Vec<model::Loan>, DateTime<Local>)> {
+    let mut campus = CampusSession::load(db, uid).await?;
+    let mut session = campus.service(library::SERVICE_URL)?;
+
+    let loans = library::fetch_loan(&mut session).await?;
+    let update_time = model::save(db, uid, &loans).await?;
+    Ok((loans, update_time))
+}
+
+/// Results are cached, so that only new searches reach the library.
+#[kite::cache_result(timeout = 600)]
+async fn search_catalog(keyword: String, page: i32) -> anyhow::Result<(Vec<model::Book>, i32)> {
+    LIBRARY_SEARCH_LIMITER.acquire(())?;
+
+    let mut session = Session::anonymous();
+    library::search(&mut session, &keyword, page).await
+}
+
+/// Renew books one by one, and fetch loans with new due dates. A failed book doesn't stop the others.
+async fn renew(
+    db: &PgPool,
+    uid: i32,
+    barcode_list: Vec<String>,
+) -> anyhow::Result<(Vec<gen::RenewResult>, Vec<model::Loan>)> {
+    let mut campus = CampusSession::load(db, uid).await?;
+    let mut session = campus.service(library::SERVICE_URL)?;
+
+    let mut results = Vec::new();
+    for barcode in barcode_list {
+        let result = match library::renew(&mut session, &barcode).await {
+            Ok(result) => gen::RenewResult {
+                barcode,
+                success: result.success,
+                message: result.message,
+            },
+            Err(e) => {
+                tracing::warn!("Failed to renew {}: {:?}", barcode, e);
+                gen::RenewResult {
+                    barcode,
+                    success: false,
+                    message: e.to_string(),
+                }
+            }
+        };
+        results.push(result);
+    }
+    let loans = library::fetch_loan(&mut session).await?;
+    Ok((results, loans))
+}
+
+#[tonic::async_trait]
+impl gen::library_service_server::LibraryService for super::KiteGrpcServer {
+    async fn get_loan_list(
+        &self,
+        request: Request<gen::LoanRequest>,
+    ) -> Result<Response<gen::LoanListResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let request = request.into_inner();
+
+        let (loans, update_time) = campus::refresh_or_get(
+            request.refresh,
+            fetch_loan(&self.db, token.uid),
+            model::get(&self.db, token.uid),
+        )
+        .await
+        .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::LoanListResponse {
+            loan_list: loans.into_iter().map(Into::into).collect(),
+            update_time: Some(ToTimestamp::datetime(update_time)),
+        }))
+    }
+
+    async fn renew(&self, request: Request<gen::RenewRequest>) -> Result<Response<gen::RenewResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let request = request.into_inner();
+        if request.barcode_list.is_empty() || request.barcode_list.len() > MAX_RENEW_COUNT {
+            return Err(Status::invalid_argument("Barcode list should have 1 - 20 items."));
+        }
+
+        let (results, loans) = renew(&self.db, token.uid, request.barcode_list)
+            .await
+            .map_err(ToStatus::to_status)?;
+        model::save(&self.db, token.uid, &loans)
+            .await
+            .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::RenewResponse {
+            result_list: results,
+            loan_list: loans.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn search(&self, request: Request<gen::SearchRequest>) -> Result<Response<gen::SearchResponse>, Status> {
+        if let Some(address) = request.remote_addr() {
+            CLIENT_SEARCH_LIMITER
+                .acquire(address.ip())
+                .map_err(|e| Status::resource_exhausted(e.to_string()))?;
+        }
+        let request = request.into_inner();
+        let keyword = request.keyword.trim();
+        if keyword.is_empty() {
+            return Err(Status::invalid_argument("Keyword is required."));
+        }
+        let page = request.page.max(1);
+
+        let (books, total) = search_catalog(keyword.to_string(), page)
+            .await
+            .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::SearchResponse {
+            book_list: books.into_iter().map(Into::into).collect(),
+            total,
+        }))
+    }
+
+    async fn get_due_reminder(&self, request: Request<EmptyRequest>) -> Result<Response<gen::DueReminderList>, Status> {
+        let token = get_token_from_request(&request)?;
+
+        let reminders = notification::list(&self.db, token.uid, model::NOTIFICATION_KIND, REMINDER_LIMIT)
+            .await
+            .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::DueReminderList {
+            reminder_list: reminders.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
@@ -0,0 +1,114 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use chrono::{DateTime, Local};
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+
+use crate::campus::{self, jwxt, CampusSession};
+use crate::error::ToStatus;
+use crate::model::ToTimestamp;
+use crate::model::{notification, score as model};
+use crate::service::auth::get_token_from_request;
+pub use crate::service::gen::score as gen;
+use crate::service::gen::template::EmptyRequest;
+
+/// Count of recent new grades returned.
+const UPDATE_LIMIT: i64 = 20;
+
+impl From<model::Score> for gen::Score {
+    fn from(score: model::Score) -> Self {
+        gen::Score {
+            course_name: score.course_name,
+            course_code: score.course_code,
+            class_name: score.class_name,
+            credit: score.credit,
+            score: score.score,
+            grade_point: score.grade_point,
+            course_type: score.course_type,
+            exam_type: score.exam_type,
+        }
+    }
+}
+
+impl From<notification::Notification> for gen::ScoreUpdate {
+    fn from(n: notification::Notification) -> Self {
+        gen::ScoreUpdate {
+            course_name: n.title,
+            score: n.content,
+            ts: Some(ToTimestamp::datetime(n.create_time)),
+        }
+    }
+}
+
+/// Fetch and save, returning the update time too.
+async fn fetch_score(
+    db: &PgPool,
+    uid: i32,
+    year: i32,
+    semester: i32,
+) -> anyhow::Result<(Vec<model::Score>, DateTime<Local>)> {
+    let mut campus = CampusSession::load(db, uid).await?;
+    let mut session = campus.service(jwxt::SERVICE_URL)?;
+
+    let scores = jwxt::score::fetch(&mut session, year, semester).await?;
+    let update_time = model::save(db, uid, year, semester, &scores).await?;
+    Ok((scores, update_time))
+}
+
+#[tonic::async_trait]
+impl gen::score_service_server::ScoreService for super::KiteGrpcServer {
+    async fn get_score_list(
+        &self,
+        request: Request<gen::ScoreRequest>,
+    ) -> Result<Response<gen::ScoreListResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let request = request.into_inner();
+        let (year, semester) = (request.year, request.semester);
+        if !jwxt::is_valid_term(year, semester) {
+            return Err(Status::invalid_argument("Invalid year or semester."));
+        }
+
+        let (scores, update_time) = campus::refresh_or_get(
+            request.refresh,
+            fetch_score(&self.db, token.uid, year, semester),
+            async {
+                let snapshot = model::get_latest(&self.db, token.uid, year, semester).await?;
+                Ok(snapshot.map(|s| (s.scores.0, s.update_time)))
+            },
+        )
+        .await
+        .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::ScoreListResponse {
+            gpa: model::gpa(&scores),
+            score_list: scores.into_iter().map(Into::into).collect(),
+            update_time: Some(ToTimestamp::datetime(update_time)),
+        }))
+    }
+
+    async fn get_score_update(&self, request: Request<EmptyRequest>) -> Result<Response<gen::ScoreUpdateList>, Status> {
+        let token = get_token_from_request(&request)?;
+
+        let updates = notification::list(&self.db, token.uid, model::NOTIFICATION_KIND, UPDATE_LIMIT)
+            .await
+            .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::ScoreUpdateList {
+            update_list: updates.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
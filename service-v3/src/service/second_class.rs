@@ -0,0 +1,118 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use chrono::{DateTime, Local};
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+
+use crate::campus::{self, second_class, CampusSession};
+use crate::error::ToStatus;
+use crate::model::second_class as model;
+use crate::model::ToTimestamp;
+use crate::service::auth::get_token_from_request;
+pub use crate::service::gen::second_class as gen;
+
+impl From<model::CategoryScore> for gen::CategoryScore {
+    fn from(score: model::CategoryScore) -> Self {
+        gen::CategoryScore {
+            category: score.category,
+            score: score.score,
+            required: score.required,
+        }
+    }
+}
+
+impl From<model::Activity> for gen::Activity {
+    fn from(activity: model::Activity) -> Self {
+        gen::Activity {
+            activity_id: activity.activity_id,
+            title: activity.title,
+            category: activity.category,
+            score: activity.score,
+            time: activity.time.map(ToTimestamp::datetime),
+            status: activity.status,
+        }
+    }
+}
+
+impl From<model::UpcomingActivity> for gen::UpcomingActivity {
+    fn from(activity: model::UpcomingActivity) -> Self {
+        gen::UpcomingActivity {
+            activity_id: activity.activity_id,
+            title: activity.title,
+            category: activity.category,
+            start: activity.start.map(ToTimestamp::datetime),
+            place: activity.place,
+        }
+    }
+}
+
+/// Fetch and save, returning the update time too.
+async fn fetch_second_class(db: &PgPool, uid: i32) -> anyhow::Result<(model::SecondClass, DateTime<Local>)> {
+    let mut campus = CampusSession::load(db, uid).await?;
+    let mut session = campus.service(second_class::SERVICE_URL)?;
+
+    let data = second_class::fetch(&mut session).await?;
+    let update_time = model::save(db, uid, &data).await?;
+    Ok((data, update_time))
+}
+
+/// Shared by both requests, which show different parts of the data.
+async fn get_second_class(
+    db: &PgPool,
+    uid: i32,
+    refresh: bool,
+) -> anyhow::Result<(model::SecondClass, DateTime<Local>)> {
+    campus::refresh_or_get(refresh, fetch_second_class(db, uid), model::get(db, uid)).await
+}
+
+#[tonic::async_trait]
+impl gen::second_class_service_server::SecondClassService for super::KiteGrpcServer {
+    async fn get_score(
+        &self,
+        request: Request<gen::SecondClassRequest>,
+    ) -> Result<Response<gen::ScoreResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let (data, update_time) = get_second_class(&self.db, token.uid, request.into_inner().refresh)
+            .await
+            .map_err(ToStatus::to_status)?;
+
+        Ok(Response::new(gen::ScoreResponse {
+            total: data.total(),
+            category_list: data.scores.into_iter().map(Into::into).collect(),
+            activity_list: data.activities.into_iter().map(Into::into).collect(),
+            update_time: Some(ToTimestamp::datetime(update_time)),
+        }))
+    }
+
+    async fn get_upcoming_activity(
+        &self,
+        request: Request<gen::SecondClassRequest>,
+    ) -> Result<Response<gen::UpcomingActivityResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let (data, update_time) = get_second_class(&self.db, token.uid, request.into_inner().refresh)
+            .await
+            .map_err(ToStatus::to_status)?;
+
+        let upcoming = data.upcoming_after(Local::now().naive_local());
+        Ok(Response::new(gen::UpcomingActivityResponse {
+            activity_list: upcoming.into_iter().map(Into::into).collect(),
+            update_time: Some(ToTimestamp::datetime(update_time)),
+        }))
+    }
+}
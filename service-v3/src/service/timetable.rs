@@ -0,0 +1,243 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local, NaiveDate};
+use sqlx::PgPool;
+use tonic::{Request, Response, Status};
+
+use crate::campus::{self, jwxt, CampusSession};
+use crate::error::ToStatus;
+use crate::ical::{Calendar, Event};
+use crate::model::calendar::{self, ClassPeriod, TermCalendar};
+use crate::model::edu;
+use crate::model::timetable as model;
+use crate::model::ToTimestamp;
+use crate::service::auth::get_token_from_request;
+pub use crate::service::gen::timetable as gen;
+
+/// Weeks of a term at most, which is the range of `Course::weeks`.
+const MAX_WEEKS: i32 = 30;
+
+impl From<model::Course> for gen::Course {
+    fn from(course: model::Course) -> Self {
+        gen::Course {
+            course_name: course.course_name,
+            course_code: course.course_code,
+            class_name: course.class_name,
+            teachers: course.teachers,
+            place: course.place,
+            campus: course.campus,
+            weeks: course.weeks,
+            day: course.day,
+            time_index: course.time_index,
+            credit: course.credit,
+        }
+    }
+}
+
+/// Fetch and save, returning the update time too.
+async fn fetch_timetable(
+    db: &PgPool,
+    uid: i32,
+    year: i32,
+    semester: i32,
+) -> anyhow::Result<(Vec<model::Course>, DateTime<Local>)> {
+    let mut campus = CampusSession::load(db, uid).await?;
+    let mut session = campus.service(jwxt::SERVICE_URL)?;
+
+    let courses = jwxt::timetable::fetch(&mut session, year, semester).await?;
+    let update_time = model::save(db, uid, year, semester, &courses).await?;
+    Ok((courses, update_time))
+}
+
+fn campus_of(course: &model::Course) -> i32 {
+    edu::parse_campus(&course.campus).unwrap_or(calendar::DEFAULT_CAMPUS)
+}
+
+/// Class periods of each campus which the courses are on.
+async fn load_class_periods(db: &PgPool, courses: &[model::Course]) -> anyhow::Result<HashMap<i32, Vec<ClassPeriod>>> {
+    let mut periods = HashMap::new();
+    for campus in courses.iter().map(campus_of) {
+        if let Entry::Vacant(entry) = periods.entry(campus) {
+            entry.insert(calendar::get_class_periods(db, campus).await?);
+        }
+    }
+    Ok(periods)
+}
+
+/// Expand each course to the events in every week, on the dates given by the term calendar and the class
+/// periods of its campus.
+fn to_calendar(
+    courses: &[model::Course],
+    term: &TermCalendar,
+    periods: &HashMap<i32, Vec<ClassPeriod>>,
+    alarm: Option<i32>,
+) -> Calendar {
+    let mut calendar = Calendar::new("课表");
+
+    for course in courses {
+        let ranges = model::split_time_index(course.time_index);
+        let periods = periods.get(&campus_of(course)).map(Vec::as_slice).unwrap_or_default();
+        let period = |index: i32| periods.iter().find(|p| p.index == index);
+        let teachers = course.teachers.join(", ");
+
+        for week in (1..=MAX_WEEKS).filter(|w| course.weeks & (1 << w) != 0) {
+            for date in term.dates_of(week, course.day) {
+                for &(begin, end) in &ranges {
+                    let (Some(first), Some(last)) = (period(begin), period(end)) else {
+                        continue;
+                    };
+                    calendar.push(Event {
+                        uid: format!(
+                            "{}-{}-{}-{}@kite.sit.edu.cn",
+                            course.course_code,
+                            course.class_name,
+                            date.format("%Y%m%d"),
+                            begin
+                        ),
+                        summary: course.course_name.clone(),
+                        location: course.place.clone(),
+                        description: format!("教师: {}\n教学班: {}", teachers, course.class_name),
+                        start: date.and_time(first.begin_time),
+                        end: date.and_time(last.end_time),
+                        alarm,
+                    });
+                }
+            }
+        }
+    }
+    calendar
+}
+
+#[tonic::async_trait]
+impl gen::timetable_service_server::TimetableService for super::KiteGrpcServer {
+    async fn get_timetable(
+        &self,
+        request: Request<gen::TermRequest>,
+    ) -> Result<Response<gen::TimetableResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let request = request.into_inner();
+        let (year, semester) = (request.year, request.semester);
+        if !jwxt::is_valid_term(year, semester) {
+            return Err(Status::invalid_argument("Invalid year or semester."));
+        }
+
+        let (courses, update_time) = campus::refresh_or_get(
+            request.refresh,
+            fetch_timetable(&self.db, token.uid, year, semester),
+            model::get(&self.db, token.uid, year, semester),
+        )
+        .await
+        .map_err(ToStatus::to_status)?;
+        Ok(Response::new(gen::TimetableResponse {
+            course_list: courses.into_iter().map(Into::into).collect(),
+            update_time: Some(ToTimestamp::datetime(update_time)),
+        }))
+    }
+
+    async fn export_timetable(
+        &self,
+        request: Request<gen::ExportRequest>,
+    ) -> Result<Response<gen::ICalendarResponse>, Status> {
+        let token = get_token_from_request(&request)?;
+        let request = request.into_inner();
+        if !jwxt::is_valid_term(request.year, request.semester) {
+            return Err(Status::invalid_argument("Invalid year or semester."));
+        }
+        if request.alarm_minutes.is_some_and(|m| m < 0) {
+            return Err(Status::invalid_argument("alarm_minutes should not be negative."));
+        }
+        let term = if request.term_begin.is_empty() {
+            calendar::get_term_calendar(&self.db, request.year, request.semester)
+                .await
+                .map_err(ToStatus::to_status)?
+                .ok_or_else(|| Status::not_found("The term is not in the calendar, please provide term_begin."))?
+        } else {
+            let term_begin = NaiveDate::parse_from_str(&request.term_begin, "%Y-%m-%d")
+                .map_err(|_| Status::invalid_argument("term_begin should be in yyyy-MM-dd."))?;
+            TermCalendar::from_begin_date(request.year, request.semester, term_begin, MAX_WEEKS)
+        };
+
+        let (courses, _) = model::get(&self.db, token.uid, request.year, request.semester)
+            .await
+            .map_err(ToStatus::to_status)?
+            .ok_or_else(|| Status::not_found("No timetable is cached, please fetch it first."))?;
+        let periods = load_class_periods(&self.db, &courses)
+            .await
+            .map_err(ToStatus::to_status)?;
+        let calendar = to_calendar(&courses, &term, &periods, request.alarm_minutes);
+
+        Ok(Response::new(gen::ICalendarResponse {
+            content: calendar.to_string(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_calendar() {
+        let course = model::Course {
+            course_name: "高等数学".to_string(),
+            course_code: "B1220012".to_string(),
+            class_name: "22级机械1班".to_string(),
+            teachers: vec!["王五".to_string()],
+            place: "一教A101".to_string(),
+            campus: "奉贤校区".to_string(),
+            // Week 1 and 3
+            weeks: 0b1010,
+            day: 3,
+            // Class 1-2 and 5-6
+            time_index: 0b1100110,
+            credit: 5.0,
+        };
+        let term_begin = NaiveDate::from_ymd_opt(2023, 2, 20).unwrap();
+        let term = TermCalendar::from_begin_date(2022, 2, term_begin, MAX_WEEKS);
+        let mut periods = HashMap::from([(calendar::DEFAULT_CAMPUS, calendar::default_class_periods())]);
+        let calendar = to_calendar(std::slice::from_ref(&course), &term, &periods, None);
+        let events = &calendar.events;
+
+        assert_eq!(events.len(), 4);
+        let date = NaiveDate::from_ymd_opt(2023, 2, 22).unwrap();
+        assert_eq!(events[0].start, date.and_hms_opt(8, 20, 0).unwrap());
+        assert_eq!(events[0].end, date.and_hms_opt(9, 55, 0).unwrap());
+        assert_eq!(events[1].start, date.and_hms_opt(13, 0, 0).unwrap());
+        let date = NaiveDate::from_ymd_opt(2023, 3, 8).unwrap();
+        assert_eq!(events[3].end, date.and_hms_opt(14, 35, 0).unwrap());
+
+        // Class 5-6 starts later on this campus, and there is no class 1-2.
+        let afternoon = |index, hour| ClassPeriod {
+            index,
+            begin_time: chrono::NaiveTime::from_hms_opt(hour, 0, 0).unwrap(),
+            end_time: chrono::NaiveTime::from_hms_opt(hour, 45, 0).unwrap(),
+        };
+        periods.insert(calendar::DEFAULT_CAMPUS, vec![afternoon(5, 14), afternoon(6, 15)]);
+        let calendar = to_calendar(&[course], &term, &periods, None);
+        let events = &calendar.events;
+
+        assert_eq!(events.len(), 2);
+        let date = NaiveDate::from_ymd_opt(2023, 2, 22).unwrap();
+        assert_eq!(events[0].start, date.and_hms_opt(14, 0, 0).unwrap());
+        assert_eq!(events[0].end, date.and_hms_opt(15, 45, 0).unwrap());
+    }
+}
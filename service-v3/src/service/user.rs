@@ -16,7 +16,9 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::future::Future;
 use std::pin::Pin;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use sqlx::PgPool;
@@ -28,9 +30,18 @@ use tonic::{Request, Response, Status, Streaming};
 
 pub use stream::VirtualStream;
 
-use crate::authserver::{Credential, PortalConnector};
+use crate::authserver::{CaptchaSolver, Credential, LoginError, PortalConnector};
+use crate::campus;
+use crate::config;
+use crate::error::ToStatus;
+use crate::model::campus_session;
+use crate::model::captcha_sample;
 use crate::model::user;
 use crate::model::user::validate;
+use crate::model::Uuid;
+use crate::service::auth::{get_token_from_request, store};
+use crate::service::gen::template::Empty;
+use crate::service::gen::token::UserToken;
 pub use crate::service::gen::user as gen;
 use crate::service::gen::user::ClientStream;
 use crate::service::user::gen::User;
@@ -40,6 +51,9 @@ type RpcServerPayload = gen::server_stream::Payload;
 type LoginResult<T> = Result<Response<T>, Status>;
 type ResponseStream = Pin<Box<dyn Stream<Item = Result<gen::ServerStream, Status>> + Send>>;
 
+#[cfg(test)]
+mod test;
+
 mod stream {
     use std::future::Future;
     use std::pin::Pin;
@@ -96,16 +110,22 @@ mod stream {
                 return Poll::Ready(Ok(()));
             }
             // Try to poll from underlying channel :D
-            match self.rx.poll_recv(cx) {
-                Poll::Ready(payload) => {
-                    if let Some(RpcClientPayload::TlsStream(content)) = payload {
+            loop {
+                match self.rx.poll_recv(cx) {
+                    Poll::Ready(Some(RpcClientPayload::TlsStream(content))) => {
+                        // Returning without any byte means EOF, so skip empty frames.
                         if !content.is_empty() {
                             copy_buffer(&mut self.rx_buffer, content, buf);
+                            return Poll::Ready(Ok(()));
                         }
                     }
-                    Poll::Ready(Ok(()))
+                    // Credential is expected only once at the beginning, and captcha answers are
+                    // dispatched to the solver before here, ignore them.
+                    Poll::Ready(Some(RpcClientPayload::Credential(_) | RpcClientPayload::CaptchaAnswer(_))) => {}
+                    // Client closed its sending side.
+                    Poll::Ready(None) => return Poll::Ready(Ok(())),
+                    Poll::Pending => return Poll::Pending,
                 }
-                Poll::Pending => Poll::Pending,
             }
         }
     }
@@ -137,105 +157,256 @@ mod stream {
     }
 }
 
-async fn stream_translation_task(
-    db: PgPool,
-    stream_in: Streaming<gen::ClientStream>,
-    channel_out: mpsc::Sender<Result<gen::ServerStream, Status>>,
-) {
-    async fn stream_translation_task_inner(
-        db: PgPool,
-        stream_in: Streaming<gen::ClientStream>,
-        channel_out: mpsc::Sender<Result<gen::ServerStream, Status>>,
-    ) -> Result<()> {
-        // Send message from here to login_task through this channel.
-        let (tx_sender, tx_receiver) = mpsc::channel::<RpcClientPayload>(16);
-        // Receive message here from login_task through this channel.
-        let (rx_sender, mut rx_receiver) = mpsc::channel::<RpcServerPayload>(16);
-
-        fn mapping_inbound_stream(element: Result<ClientStream, Status>) -> Result<RpcClientPayload> {
-            match element {
-                Ok(stream) => stream
-                    .payload
-                    .ok_or_else(|| anyhow!("Expect client payload from in_stream but received None.")),
-                Err(status) => Err(status.into()),
+/// Deadline of each step in login, so that a silent client or authserver can't hold the task forever.
+mod deadline {
+    use std::time::Duration;
+
+    /// Wait for the credential after stream opened.
+    pub const CREDENTIAL: Duration = Duration::from_secs(30);
+    /// TLS handshake with authserver, relayed by client.
+    pub const HANDSHAKE: Duration = Duration::from_secs(20);
+    /// Fetch login page, recognize captcha and post the form.
+    pub const LOGIN: Duration = Duration::from_secs(40);
+    /// Wait for the user to type captcha, which is added to `LOGIN` if a solver is available.
+    pub const CAPTCHA_ANSWER: Duration = Duration::from_secs(120);
+    /// Fetch user profile and close the connection.
+    pub const FINISH: Duration = Duration::from_secs(20);
+    /// Login all campus systems.
+    pub const CAMPUS: Duration = Duration::from_secs(40);
+}
+
+/// 登录失败的原因, 以 `Message` 的形式发给客户端.
+#[derive(Debug)]
+enum LoginFailure {
+    /// 客户端发送的消息不符合预期
+    BadRequest(&'static str),
+    /// 某一步骤超时
+    Timeout(&'static str),
+    /// authserver 拒绝登录, 或无法完成登录流程
+    Portal(LoginError),
+    /// 账户已被禁用
+    Blocked,
+    /// 其他错误, 如网络或数据库错误
+    Internal(anyhow::Error),
+}
+
+impl std::fmt::Display for LoginFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoginFailure::BadRequest(reason) => write!(f, "请求无效: {}", reason),
+            LoginFailure::Timeout(step) => write!(f, "{}超时, 请检查网络后重试", step),
+            LoginFailure::Portal(LoginError::Rejected(message)) => write!(f, "{}", message),
+            LoginFailure::Portal(LoginError::CaptchaFailed) => write!(f, "验证码识别失败, 请稍后重试"),
+            LoginFailure::Portal(LoginError::UnsupportedCaptcha) => {
+                write!(f, "需要完成滑块验证, 请先在浏览器中登录一次统一认证后重试")
             }
+            LoginFailure::Portal(LoginError::PageChanged(_)) => write!(f, "统一认证页面已变更, 暂时无法登录"),
+            LoginFailure::Portal(LoginError::NotLoggedIn) => write!(f, "登录已过期, 请重新登录"),
+            LoginFailure::Blocked => write!(f, "该账户已被禁用"),
+            LoginFailure::Internal(_) => write!(f, "服务器内部错误, 请稍后重试"),
         }
+    }
+}
 
-        // Launch login_task, go!!!
-        tokio::spawn(login_task(db, rx_sender, tx_receiver));
-
-        let mut in_stream = stream_in.map(mapping_inbound_stream);
-        loop {
-            tokio::select! {
-                v = in_stream.next() => {
-                    let v: Result<_> = v.unwrap();
-                    // maybe it's unnecessary to handle error returned by channel
-                    let _ = tx_sender.send(v?).await?;
-                },
-                v = rx_receiver.recv() => {
-                    let payload_to_outer = gen::ServerStream {payload: v};
-                    channel_out.send(Ok(payload_to_outer)).await?;
-                },
-            }
+impl From<anyhow::Error> for LoginFailure {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast::<LoginError>() {
+            Ok(e) => LoginFailure::Portal(e),
+            Err(e) => LoginFailure::Internal(e),
         }
     }
+}
 
-    let result = stream_translation_task_inner(db, stream_in, channel_out).await;
-    if let Err(e) = result {
-        tracing::trace!("stream_translation_task exits with error {}, ", e);
+/// Run a step of login within its deadline.
+async fn step<T, E>(
+    name: &'static str,
+    deadline: Duration,
+    future: impl Future<Output = std::result::Result<T, E>>,
+) -> std::result::Result<T, LoginFailure>
+where
+    E: Into<anyhow::Error>,
+{
+    match tokio::time::timeout(deadline, future).await {
+        Ok(result) => result.map_err(|e| LoginFailure::from(e.into())),
+        Err(_) => Err(LoginFailure::Timeout(name)),
+    }
+}
+
+async fn stream_translation_task<S>(
+    db: PgPool,
+    stream_in: S,
+    channel_out: mpsc::Sender<Result<gen::ServerStream, Status>>,
+) where
+    S: Stream<Item = Result<gen::ClientStream, Status>> + Unpin,
+{
+    // Send message from here to login_task through this channel.
+    let (tx_sender, tx_receiver) = mpsc::channel::<RpcClientPayload>(16);
+    // Receive message here from login_task through this channel.
+    let (rx_sender, mut rx_receiver) = mpsc::channel::<RpcServerPayload>(16);
+    // Captcha answers are sent to the solver rather than mixed into the TLS stream.
+    let (answer_sender, answer_receiver) = mpsc::channel::<String>(1);
+
+    // Launch login_task, go!!!
+    let login = tokio::spawn(login_task(db, rx_sender, tx_receiver, answer_receiver));
+
+    let mut in_stream = stream_in;
+    let mut tx_sender = Some(tx_sender);
+    loop {
+        tokio::select! {
+            v = in_stream.next(), if tx_sender.is_some() => {
+                match v {
+                    Some(Ok(ClientStream { payload: Some(RpcClientPayload::CaptchaAnswer(answer)) })) => {
+                        // Drop the answer if captcha is not asked.
+                        let _ = answer_sender.try_send(answer);
+                    }
+                    Some(Ok(ClientStream { payload: Some(payload) })) => {
+                        // The login task may exit early, then the payload is useless.
+                        let _ = tx_sender.as_ref().unwrap().send(payload).await;
+                    }
+                    Some(Ok(ClientStream { payload: None })) => {}
+                    Some(Err(status)) => {
+                        tracing::trace!("Login stream is broken by client: {}", status);
+                        break;
+                    }
+                    None => {
+                        // Client finishes sending, which the login task will see as EOF, but we still
+                        // have to send the result back.
+                        tx_sender = None;
+                    }
+                }
+            },
+            v = rx_receiver.recv() => {
+                // The login task exits and all messages are sent, so close the stream.
+                let Some(payload) = v else { break };
+                let payload_to_outer = gen::ServerStream { payload: Some(payload) };
+                if channel_out.send(Ok(payload_to_outer)).await.is_err() {
+                    tracing::trace!("Client closed the login stream.");
+                    break;
+                }
+            },
+        }
     }
+    // Nobody is listening now, stop talking to authserver.
+    login.abort();
 }
 
-async fn login_task(db: PgPool, tx: mpsc::Sender<RpcServerPayload>, rx: mpsc::Receiver<RpcClientPayload>) {
+/// Send captcha image to the app, and wait for the answer typed by user.
+struct StreamCaptchaSolver {
+    tx: mpsc::Sender<RpcServerPayload>,
+    answer_rx: mpsc::Receiver<String>,
+}
+
+#[async_trait::async_trait]
+impl CaptchaSolver for StreamCaptchaSolver {
+    async fn solve(&mut self, image: Vec<u8>) -> Result<String> {
+        self.tx.send(RpcServerPayload::CaptchaImage(image)).await?;
+
+        // The deadline of login step applies here.
+        self.answer_rx
+            .recv()
+            .await
+            .ok_or_else(|| anyhow!("Client leaves before answering captcha."))
+    }
+}
+
+async fn login_task(
+    db: PgPool,
+    tx: mpsc::Sender<RpcServerPayload>,
+    rx: mpsc::Receiver<RpcClientPayload>,
+    answer_rx: mpsc::Receiver<String>,
+) {
     async fn login_task_inner(
         db: PgPool,
         tx: mpsc::Sender<RpcServerPayload>,
         mut rx: mpsc::Receiver<RpcClientPayload>,
-    ) -> Result<()> {
+        answer_rx: mpsc::Receiver<String>,
+    ) -> Result<(), LoginFailure> {
         // Step 1: Get user credential from client
-        let credential = if let Some(RpcClientPayload::Credential(oa)) = rx.recv().await {
-            Credential::new(oa.account, oa.password)
-        } else {
-            return Err(anyhow!(
-                "Unknown message received, RpcClientPayload::Credential expected."
-            ));
+        let payload = tokio::time::timeout(deadline::CREDENTIAL, rx.recv())
+            .await
+            .map_err(|_| LoginFailure::Timeout("等待账号密码"))?;
+        let Some(RpcClientPayload::Credential(oa)) = payload else {
+            return Err(LoginFailure::BadRequest("请先发送账号和密码"));
         };
+        let credential = Credential::new(oa.account, oa.password);
 
         if !validate::check_username(&credential.account) {
-            return Err(anyhow!("Only student and staff ID is supported."));
+            return Err(LoginFailure::BadRequest("仅支持使用学号或工号登录"));
         }
 
         // Step 2: Create virtual stream, merge tx & rx -> stream
+        let solver = StreamCaptchaSolver {
+            tx: tx.clone(),
+            answer_rx,
+        };
         let stream = VirtualStream::new(rx, tx);
-        let mut portal = PortalConnector::new().user(credential.clone()).bind(stream).await?;
+        let ocr_retry = config::get().captcha_ocr_retry;
+        let connector = PortalConnector::new()
+            .user(credential.clone())
+            .captcha_solver(Box::new(solver));
+        let connector = match ocr_retry {
+            Some(n) => connector.ocr_retry(n),
+            None => connector,
+        };
+        let mut portal = step("连接统一认证服务", deadline::HANDSHAKE, connector.bind(stream)).await?;
 
         // Step 3: Do login
-        portal.try_login().await?;
+        let result = step("登录", deadline::LOGIN + deadline::CAPTCHA_ANSWER, portal.try_login()).await;
+        let samples = portal.take_captcha_samples();
+        if let Err(e) = captcha_sample::save(&db, &samples).await {
+            tracing::warn!("Failed to save captcha samples: {:?}", e);
+        }
+        result?;
 
         // Step 4: Query database, (maybe register new account), get user profile.
         let user = if let Some(u) = user::query(&db, &credential.account).await? {
             u
         } else {
-            let person_name = portal.get_person_name().await?;
+            let person_name = step("获取用户信息", deadline::FINISH, portal.get_person_name()).await?;
             user::create(&db, &credential.account, &person_name).await?
         };
+        if user.is_block {
+            return Err(LoginFailure::Blocked);
+        }
+
+        // Step 5: Login campus systems, whose sessions are used by other services. Kite login goes on on failure.
+        let result = step(
+            "登录校园系统",
+            deadline::CAMPUS,
+            campus::save_sessions(&db, user.uid, &mut portal),
+        )
+        .await;
+        if let Err(e) = result {
+            tracing::warn!("Failed to save campus sessions: {:?}", e);
+        }
 
-        // Step 5: Recycle virtual stream
-        let stream = portal.shutdown().await?;
+        // Step 6: Recycle virtual stream
+        let stream = step("断开统一认证连接", deadline::FINISH, portal.shutdown()).await?;
         let (_rx, tx) = stream.split();
 
+        // Step 7: Issue token
+        let token = store::issue(&db, user.uid, user.role, oa.need_refresh_token).await?;
+
         use crate::model::ToTimestamp;
-        tx.send(RpcServerPayload::User(User {
+        let user = User {
             uid: user.uid,
             account: user.account,
             create_time: Some(ToTimestamp::datetime(user.create_time)),
-        }))
-        .await?;
+        };
+        // The receiver is gone only if the client leaves, nothing to do then.
+        let _ = tx.send(RpcServerPayload::User(user)).await;
+        let _ = tx.send(RpcServerPayload::Token(token)).await;
         Ok(())
     }
 
-    if let Err(e) = login_task_inner(db, tx, rx).await {
-        tracing::error!("Login task failed with error: {}", e);
+    // Keep a sender, so that the failure can be reported after the virtual stream is dropped.
+    let message_tx = tx.clone();
+    if let Err(e) = login_task_inner(db, tx, rx, answer_rx).await {
+        match &e {
+            LoginFailure::Internal(e) => tracing::error!("Login task failed with error: {:?}", e),
+            e => tracing::info!("Login failed: {:?}", e),
+        }
+        let _ = message_tx.send(RpcServerPayload::Message(e.to_string())).await;
     }
 }
 
@@ -255,4 +426,37 @@ impl gen::user_service_server::UserService for super::KiteGrpcServer {
         // Function returns, but the stream continues...
         Ok(Response::new(Box::pin(out_stream) as Self::LoginStream))
     }
+
+    async fn refresh_token(&self, request: Request<gen::RefreshTokenRequest>) -> Result<Response<UserToken>, Status> {
+        let request = request.into_inner();
+        let refresh_token =
+            Uuid::parse_str(&request.refresh_token).map_err(|_| Status::invalid_argument("Invalid refresh token."))?;
+
+        store::refresh(&self.db, refresh_token)
+            .await
+            .map_err(ToStatus::to_status)?
+            .map(Response::new)
+            .ok_or_else(|| Status::unauthenticated("Refresh token is expired or revoked, please login again."))
+    }
+
+    async fn logout(&self, request: Request<gen::LogoutRequest>) -> Result<Response<Empty>, Status> {
+        let token = get_token_from_request(&request)?;
+        let request = request.into_inner();
+
+        store::revoke_access_token(&self.db, &token)
+            .await
+            .map_err(ToStatus::to_status)?;
+        campus_session::delete(&self.db, token.uid)
+            .await
+            .map_err(ToStatus::to_status)?;
+        if let Some(refresh_token) = request.refresh_token {
+            let refresh_token =
+                Uuid::parse_str(&refresh_token).map_err(|_| Status::invalid_argument("Invalid refresh token."))?;
+
+            store::revoke_refresh_token(&self.db, token.uid, refresh_token)
+                .await
+                .map_err(ToStatus::to_status)?;
+        }
+        Ok(Response::new(Empty {}))
+    }
 }
@@ -0,0 +1,309 @@
+/*
+ * 上应小风筝  便利校园，一步到位
+ * Copyright (C) 2020-2023 上海应用技术大学 上应小风筝团队
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! End-to-end tests of authserver login against a local mock, relayed through `VirtualStream`
+//! in the same way as the app does.
+
+use authserver_mock::MockAuthserver;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tokio::sync::mpsc;
+
+use std::sync::{Arc, Mutex};
+
+use super::{gen, RpcClientPayload, RpcServerPayload, VirtualStream};
+use crate::authserver::{CaptchaSolver, Credential, LoginError, Portal, PortalConnector};
+use crate::model::captcha_sample::CaptchaSource;
+
+const ACCOUNT: &str = "2112340101";
+const PASSWORD: &str = "p@ssw0rd+中文";
+const NAME: &str = "张三";
+
+/// Act as the app, which forwards TLS bytes between kite-server and authserver.
+async fn relay(
+    mut server: DuplexStream,
+    mut from_kite: mpsc::Receiver<RpcServerPayload>,
+    to_kite: mpsc::Sender<RpcClientPayload>,
+) {
+    let mut buffer = vec![0u8; 4096];
+    loop {
+        tokio::select! {
+            payload = from_kite.recv() => match payload {
+                Some(RpcServerPayload::TlsStream(content)) => {
+                    if server.write_all(&content).await.is_err() {
+                        break;
+                    }
+                }
+                Some(_) => {}
+                None => break,
+            },
+            n = server.read(&mut buffer) => match n {
+                Ok(n) if n > 0 => {
+                    if to_kite.send(RpcClientPayload::TlsStream(buffer[..n].to_vec())).await.is_err() {
+                        break;
+                    }
+                }
+                _ => break,
+            },
+        }
+    }
+}
+
+/// Answer captcha like a user, and record the images shown.
+struct FixedSolver {
+    answers: Vec<&'static str>,
+    images: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+#[async_trait::async_trait]
+impl CaptchaSolver for FixedSolver {
+    async fn solve(&mut self, image: Vec<u8>) -> anyhow::Result<String> {
+        self.images.lock().unwrap().push(image);
+        Ok(self.answers.remove(0).to_string())
+    }
+}
+
+async fn connect(server: &MockAuthserver, password: &str) -> Portal<VirtualStream> {
+    connect_with(server, password, PortalConnector::new()).await
+}
+
+async fn connect_with(server: &MockAuthserver, password: &str, connector: PortalConnector) -> Portal<VirtualStream> {
+    crate::authserver::tls_set_roots(authserver_mock::root_store());
+
+    let (to_kite, rx) = mpsc::channel(16);
+    let (tx, from_kite) = mpsc::channel(16);
+    tokio::spawn(relay(server.connect(), from_kite, to_kite));
+
+    let stream = VirtualStream::new(rx, tx);
+    connector
+        .user(Credential::new(ACCOUNT.to_string(), password.to_string()))
+        .bind(stream)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_login() {
+    let server = MockAuthserver::new().user(ACCOUNT, PASSWORD, NAME);
+    let mut portal = connect(&server, PASSWORD).await;
+
+    portal.try_login().await.unwrap();
+    assert_eq!(portal.get_person_name().await.unwrap(), NAME);
+    // The virtual stream is given back after TLS closed.
+    portal.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_login_with_wrong_password() {
+    let server = MockAuthserver::new().user(ACCOUNT, PASSWORD, NAME);
+    let mut portal = connect(&server, "wrong password").await;
+
+    let error = portal.try_login().await.unwrap_err();
+    match error.downcast::<LoginError>() {
+        Ok(LoginError::Rejected(message)) => assert_eq!(message, authserver_mock::WRONG_PASSWORD_MESSAGE),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[tokio::test]
+async fn test_login_page_changed() {
+    let server = MockAuthserver::new().user(ACCOUNT, PASSWORD, NAME).page_changed();
+    let mut portal = connect(&server, PASSWORD).await;
+
+    let error = portal.try_login().await.unwrap_err();
+    assert!(matches!(error.downcast::<LoginError>(), Ok(LoginError::PageChanged(_))));
+}
+
+#[tokio::test]
+async fn test_login_with_captcha() {
+    let server = MockAuthserver::new().user_with_captcha(ACCOUNT, PASSWORD, NAME, "ab3d");
+    let images = Arc::new(Mutex::new(Vec::new()));
+    let solver = FixedSolver {
+        answers: vec!["wrong", "ab3d"],
+        images: images.clone(),
+    };
+    // OCR model is not loaded in tests, so the solver is asked directly.
+    let connector = PortalConnector::new().captcha_solver(Box::new(solver));
+    let mut portal = connect_with(&server, PASSWORD, connector).await;
+
+    assert!(matches!(
+        portal.try_login().await.unwrap_err().downcast::<LoginError>(),
+        Ok(LoginError::CaptchaFailed)
+    ));
+    portal.try_login().await.unwrap();
+    assert_eq!(images.lock().unwrap().len(), 2);
+
+    let samples = portal.take_captcha_samples();
+    let labels: Vec<_> = samples
+        .iter()
+        .map(|s| (s.answer.as_str(), s.source, s.correct))
+        .collect();
+    assert_eq!(
+        labels,
+        vec![
+            ("wrong", CaptchaSource::User, Some(false)),
+            ("ab3d", CaptchaSource::User, Some(true))
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_login_with_captcha_but_no_solver() {
+    let server = MockAuthserver::new().user_with_captcha(ACCOUNT, PASSWORD, NAME, "ab3d");
+    let mut portal = connect(&server, PASSWORD).await;
+
+    let error = portal.try_login().await.unwrap_err();
+    assert!(matches!(error.downcast::<LoginError>(), Ok(LoginError::CaptchaFailed)));
+}
+
+/// Act as the app on the gRPC stream: send the credential, relay TLS bytes and answer captcha, until
+/// kite-server closes the stream. Messages other than TLS bytes are returned.
+async fn app(
+    mut server: DuplexStream,
+    password: &str,
+    captcha_answer: Option<&str>,
+    to_kite: mpsc::Sender<Result<gen::ClientStream, tonic::Status>>,
+    mut from_kite: mpsc::Receiver<Result<gen::ServerStream, tonic::Status>>,
+) -> Vec<RpcServerPayload> {
+    let send = |payload| gen::ClientStream { payload: Some(payload) };
+    let credential = gen::OaCredential {
+        account: ACCOUNT.to_string(),
+        password: password.to_string(),
+        need_refresh_token: false,
+    };
+    to_kite
+        .send(Ok(send(RpcClientPayload::Credential(credential))))
+        .await
+        .unwrap();
+
+    let mut received = Vec::new();
+    let mut buffer = vec![0u8; 4096];
+    loop {
+        tokio::select! {
+            message = from_kite.recv() => match message.map(|m| m.unwrap().payload.unwrap()) {
+                Some(RpcServerPayload::TlsStream(content)) => {
+                    let _ = server.write_all(&content).await;
+                }
+                Some(RpcServerPayload::CaptchaImage(image)) => {
+                    let answer = captcha_answer.expect("Captcha is not expected.").to_string();
+                    let _ = to_kite.send(Ok(send(RpcClientPayload::CaptchaAnswer(answer)))).await;
+                    received.push(RpcServerPayload::CaptchaImage(image));
+                }
+                Some(payload) => received.push(payload),
+                None => break,
+            },
+            n = server.read(&mut buffer) => match n {
+                Ok(n) if n > 0 => {
+                    let _ = to_kite.send(Ok(send(RpcClientPayload::TlsStream(buffer[..n].to_vec())))).await;
+                }
+                // Authserver closed the connection, wait for kite-server to finish.
+                _ => {
+                    from_kite_only(&mut from_kite, &mut received).await;
+                    break;
+                }
+            },
+        }
+    }
+    received
+}
+
+/// Collect the remaining messages after the connection to authserver is closed.
+async fn from_kite_only(
+    from_kite: &mut mpsc::Receiver<Result<gen::ServerStream, tonic::Status>>,
+    received: &mut Vec<RpcServerPayload>,
+) {
+    while let Some(message) = from_kite.recv().await {
+        match message.unwrap().payload.unwrap() {
+            RpcServerPayload::TlsStream(_) => {}
+            payload => received.push(payload),
+        }
+    }
+}
+
+/// Run `stream_translation_task` and `login_task` as `UserService::login` does, with the app relaying.
+async fn login_stream(server: &MockAuthserver, password: &str, captcha_answer: Option<&str>) -> Vec<RpcServerPayload> {
+    crate::authserver::tls_set_roots(authserver_mock::root_store());
+    crate::load_test_config();
+    // Nothing listens on the port, so the login fails at the database after authserver accepts it.
+    let db = sqlx::postgres::PgPoolOptions::new()
+        .acquire_timeout(std::time::Duration::from_secs(1))
+        .connect_lazy("postgres://kite@127.0.0.1:1/kite")
+        .unwrap();
+
+    let (to_kite, stream_in) = mpsc::channel(16);
+    let (channel_out, from_kite) = mpsc::channel(16);
+    let stream_in = tokio_stream::wrappers::ReceiverStream::new(stream_in);
+    let translation = tokio::spawn(super::stream_translation_task(db, stream_in, channel_out));
+
+    let received = app(server.connect(), password, captcha_answer, to_kite, from_kite).await;
+    translation.await.unwrap();
+    received
+}
+
+fn messages(received: &[RpcServerPayload]) -> Vec<&str> {
+    received
+        .iter()
+        .filter_map(|p| match p {
+            RpcServerPayload::Message(m) => Some(m.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_login_stream_with_wrong_password() {
+    let server = MockAuthserver::new().user(ACCOUNT, PASSWORD, NAME);
+    let received = login_stream(&server, "wrong password", None).await;
+
+    assert_eq!(messages(&received), vec![authserver_mock::WRONG_PASSWORD_MESSAGE]);
+}
+
+#[tokio::test]
+async fn test_login_stream_with_captcha() {
+    let server = MockAuthserver::new().user_with_captcha(ACCOUNT, PASSWORD, NAME, "ab3d");
+    let received = login_stream(&server, PASSWORD, Some("ab3d")).await;
+
+    assert!(matches!(received[0], RpcServerPayload::CaptchaImage(_)));
+    // Authserver accepts the login, and the next step is on the database, which is unavailable here.
+    let internal_error = super::LoginFailure::Internal(anyhow::anyhow!("")).to_string();
+    assert_eq!(messages(&received), vec![internal_error.as_str()]);
+}
+
+#[tokio::test]
+async fn test_login_stream_with_bad_account() {
+    let (to_kite, stream_in) = mpsc::channel(16);
+    let (channel_out, mut from_kite) = mpsc::channel(16);
+    let db = sqlx::postgres::PgPoolOptions::new()
+        .connect_lazy("postgres://kite@127.0.0.1:1/kite")
+        .unwrap();
+    let stream_in = tokio_stream::wrappers::ReceiverStream::new(stream_in);
+    let translation = tokio::spawn(super::stream_translation_task(db, stream_in, channel_out));
+
+    // The stream is closed before any byte sent to authserver.
+    let credential = gen::OaCredential {
+        account: "admin".to_string(),
+        password: PASSWORD.to_string(),
+        need_refresh_token: false,
+    };
+    let payload = Some(RpcClientPayload::Credential(credential));
+    to_kite.send(Ok(gen::ClientStream { payload })).await.unwrap();
+    let mut received = Vec::new();
+    from_kite_only(&mut from_kite, &mut received).await;
+    translation.await.unwrap();
+
+    assert_eq!(messages(&received), vec!["请求无效: 仅支持使用学号或工号登录"]);
+}